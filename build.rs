@@ -0,0 +1,12 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use a vendored protoc so builds don't depend on a system install
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+
+    tonic_prost_build::configure().compile_with_config(
+        config,
+        &["proto/savings.proto"],
+        &["proto"],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,10 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS update_alert_thresholds_updated_at ON alert_thresholds;
+
+-- Drop indexes
+DROP INDEX IF EXISTS idx_alerts_threshold_id;
+DROP INDEX IF EXISTS idx_alert_thresholds_source;
+
+-- Drop tables
+DROP TABLE IF EXISTS alerts;
+DROP TABLE IF EXISTS alert_thresholds;
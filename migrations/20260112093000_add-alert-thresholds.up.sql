@@ -0,0 +1,37 @@
+-- Add up migration script here
+-- Create alert thresholds table
+CREATE TABLE IF NOT EXISTS alert_thresholds (
+  id BIGSERIAL PRIMARY KEY,
+  source VARCHAR(255) NOT NULL,
+  period TEXT NOT NULL CHECK (period IN ('day', 'week', 'month')),
+  comparison TEXT NOT NULL CHECK (comparison IN ('below', 'above')),
+  threshold DECIMAL(19, 4) NOT NULL CHECK (threshold >= 0),
+  hysteresis DECIMAL(19, 4) NOT NULL DEFAULT 0 CHECK (hysteresis >= 0),
+  state TEXT NOT NULL DEFAULT 'ok' CHECK (state IN ('ok', 'triggered')),
+  last_value DECIMAL(19, 4),
+  last_evaluated_at TIMESTAMPTZ,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create index on source for faster lookups
+CREATE INDEX idx_alert_thresholds_source ON alert_thresholds(source);
+
+-- Create alerts table, one row per threshold state transition
+CREATE TABLE IF NOT EXISTS alerts (
+  id BIGSERIAL PRIMARY KEY,
+  threshold_id BIGINT NOT NULL REFERENCES alert_thresholds(id) ON DELETE CASCADE,
+  kind TEXT NOT NULL CHECK (kind IN ('triggered', 'resolved')),
+  value DECIMAL(19, 4) NOT NULL,
+  threshold DECIMAL(19, 4) NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create index on threshold_id for faster lookups
+CREATE INDEX idx_alerts_threshold_id ON alerts(threshold_id);
+
+-- Trigger to automatically update updated_at on row updates
+CREATE TRIGGER update_alert_thresholds_updated_at
+  BEFORE UPDATE ON alert_thresholds
+  FOR EACH ROW
+  EXECUTE FUNCTION update_updated_at_column();
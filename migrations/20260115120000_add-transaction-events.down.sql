@@ -0,0 +1,11 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS notify_transactions_change ON transactions;
+
+-- Drop function
+DROP FUNCTION IF EXISTS notify_transaction_change();
+
+-- Drop indexes
+DROP INDEX IF EXISTS idx_transaction_events_source;
+
+-- Drop table
+DROP TABLE IF EXISTS transaction_events;
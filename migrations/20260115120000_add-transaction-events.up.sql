@@ -0,0 +1,56 @@
+-- Add up migration script here
+-- Create transaction events table, an append-only log of changes used for stream resumption
+CREATE TABLE IF NOT EXISTS transaction_events (
+  id BIGSERIAL PRIMARY KEY,
+  operation TEXT NOT NULL CHECK (operation IN ('created', 'updated', 'deleted')),
+  transaction_id BIGINT NOT NULL,
+  source VARCHAR(255) NOT NULL,
+  payload JSONB NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create index on source for filtered replays
+CREATE INDEX idx_transaction_events_source ON transaction_events(source);
+
+-- Function to record every change on transactions and notify listeners
+CREATE OR REPLACE FUNCTION notify_transaction_change()
+RETURNS TRIGGER AS $$
+DECLARE
+  row_data transactions;
+  event transaction_events;
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    row_data := OLD;
+  ELSE
+    row_data := NEW;
+  END IF;
+
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES (
+    CASE TG_OP
+      WHEN 'INSERT' THEN 'created'
+      WHEN 'UPDATE' THEN 'updated'
+      ELSE 'deleted'
+    END,
+    row_data.id,
+    row_data.source,
+    jsonb_build_object(
+      'id', row_data.id,
+      'amount', row_data.amount::TEXT,
+      'source', row_data.source,
+      'created_at', row_data.created_at,
+      'updated_at', row_data.updated_at
+    )
+  )
+  RETURNING * INTO event;
+
+  PERFORM pg_notify('transaction_events', row_to_json(event)::TEXT);
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Trigger to publish every change on transactions
+CREATE TRIGGER notify_transactions_change
+  AFTER INSERT OR UPDATE OR DELETE ON transactions
+  FOR EACH ROW
+  EXECUTE FUNCTION notify_transaction_change();
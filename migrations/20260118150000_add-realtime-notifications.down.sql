@@ -0,0 +1,8 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS notify_alerts_created ON alerts;
+DROP TRIGGER IF EXISTS notify_alert_thresholds_progress ON alert_thresholds;
+DROP TRIGGER IF EXISTS notify_alert_thresholds_change ON alert_thresholds;
+
+-- Drop functions
+DROP FUNCTION IF EXISTS notify_alert_created();
+DROP FUNCTION IF EXISTS notify_alert_threshold_change();
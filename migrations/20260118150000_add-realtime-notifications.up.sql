@@ -0,0 +1,61 @@
+-- Add up migration script here
+-- Function to publish alert threshold (goal) changes
+CREATE OR REPLACE FUNCTION notify_alert_threshold_change()
+RETURNS TRIGGER AS $$
+DECLARE
+  row_data alert_thresholds;
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    row_data := OLD;
+  ELSE
+    row_data := NEW;
+  END IF;
+
+  PERFORM pg_notify('goal_events', jsonb_build_object(
+    'operation', CASE TG_OP
+      WHEN 'INSERT' THEN 'created'
+      WHEN 'UPDATE' THEN 'updated'
+      ELSE 'deleted'
+    END,
+    'payload', to_jsonb(row_data) || jsonb_build_object(
+      'threshold', row_data.threshold::TEXT,
+      'hysteresis', row_data.hysteresis::TEXT,
+      'last_value', row_data.last_value::TEXT
+    )
+  )::TEXT);
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Trigger to publish created and deleted goals
+CREATE TRIGGER notify_alert_thresholds_change
+  AFTER INSERT OR DELETE ON alert_thresholds
+  FOR EACH ROW
+  EXECUTE FUNCTION notify_alert_threshold_change();
+
+-- Trigger to publish goal progress, skipping evaluations that changed nothing
+CREATE TRIGGER notify_alert_thresholds_progress
+  AFTER UPDATE ON alert_thresholds
+  FOR EACH ROW
+  WHEN (OLD.state IS DISTINCT FROM NEW.state OR OLD.last_value IS DISTINCT FROM NEW.last_value)
+  EXECUTE FUNCTION notify_alert_threshold_change();
+
+-- Function to publish new alert records
+CREATE OR REPLACE FUNCTION notify_alert_created()
+RETURNS TRIGGER AS $$
+BEGIN
+  PERFORM pg_notify('notification_events', (
+    to_jsonb(NEW) || jsonb_build_object(
+      'value', NEW.value::TEXT,
+      'threshold', NEW.threshold::TEXT
+    )
+  )::TEXT);
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Trigger to publish every new alert
+CREATE TRIGGER notify_alerts_created
+  AFTER INSERT ON alerts
+  FOR EACH ROW
+  EXECUTE FUNCTION notify_alert_created();
@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- Create rate limit buckets table, the token buckets shared by every replica.
+-- The state is disposable, so the table skips the WAL and is emptied after a crash.
+CREATE UNLOGGED TABLE IF NOT EXISTS rate_limit_buckets (
+  key TEXT PRIMARY KEY,
+  tokens DOUBLE PRECISION NOT NULL,
+  allowed BOOLEAN NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create index on updated_at for removing idle buckets
+CREATE INDEX idx_rate_limit_buckets_updated_at ON rate_limit_buckets(updated_at);
@@ -0,0 +1,46 @@
+-- Add down migration script here
+-- Restore the change events without the new columns
+CREATE OR REPLACE FUNCTION notify_transaction_change()
+RETURNS TRIGGER AS $$
+DECLARE
+  row_data transactions;
+  event transaction_events;
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    row_data := OLD;
+  ELSE
+    row_data := NEW;
+  END IF;
+
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES (
+    CASE TG_OP
+      WHEN 'INSERT' THEN 'created'
+      WHEN 'UPDATE' THEN 'updated'
+      ELSE 'deleted'
+    END,
+    row_data.id,
+    row_data.source,
+    jsonb_build_object(
+      'id', row_data.id,
+      'amount', row_data.amount::TEXT,
+      'source', row_data.source,
+      'created_at', row_data.created_at,
+      'updated_at', row_data.updated_at
+    )
+  )
+  RETURNING * INTO event;
+
+  PERFORM pg_notify('transaction_events', row_to_json(event)::TEXT);
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Drop indexes
+DROP INDEX IF EXISTS idx_transactions_tags;
+
+-- Drop columns
+ALTER TABLE transactions
+  DROP COLUMN IF EXISTS tags,
+  DROP COLUMN IF EXISTS kind,
+  DROP COLUMN IF EXISTS currency;
@@ -0,0 +1,50 @@
+-- Add up migration script here
+-- Add the currency, kind and tags of transactions, existing rows become USD deposits
+ALTER TABLE transactions
+  ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'USD' CHECK (currency ~ '^[A-Z]{3}$'),
+  ADD COLUMN kind TEXT NOT NULL DEFAULT 'deposit'
+    CHECK (kind IN ('deposit', 'withdrawal', 'interest', 'transfer')),
+  ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+-- Create index on tags for tag lookups
+CREATE INDEX idx_transactions_tags ON transactions USING GIN (tags);
+
+-- Include the new columns in the recorded change events
+CREATE OR REPLACE FUNCTION notify_transaction_change()
+RETURNS TRIGGER AS $$
+DECLARE
+  row_data transactions;
+  event transaction_events;
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    row_data := OLD;
+  ELSE
+    row_data := NEW;
+  END IF;
+
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES (
+    CASE TG_OP
+      WHEN 'INSERT' THEN 'created'
+      WHEN 'UPDATE' THEN 'updated'
+      ELSE 'deleted'
+    END,
+    row_data.id,
+    row_data.source,
+    jsonb_build_object(
+      'id', row_data.id,
+      'amount', row_data.amount::TEXT,
+      'currency', row_data.currency,
+      'kind', row_data.kind,
+      'tags', to_jsonb(row_data.tags),
+      'source', row_data.source,
+      'created_at', row_data.created_at,
+      'updated_at', row_data.updated_at
+    )
+  )
+  RETURNING * INTO event;
+
+  PERFORM pg_notify('transaction_events', row_to_json(event)::TEXT);
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
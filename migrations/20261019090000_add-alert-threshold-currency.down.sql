@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE alert_thresholds
+  DROP COLUMN IF EXISTS currency;
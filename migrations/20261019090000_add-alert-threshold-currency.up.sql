@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- Thresholds watch the savings of a single currency, existing ones USD
+ALTER TABLE alert_thresholds
+  ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'USD' CHECK (currency ~ '^[A-Z]{3}$');
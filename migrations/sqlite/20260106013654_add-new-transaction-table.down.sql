@@ -0,0 +1,9 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS update_transactions_updated_at;
+
+-- Drop indexes
+DROP INDEX IF EXISTS idx_transactions_created_at;
+DROP INDEX IF EXISTS idx_transactions_source;
+
+-- Drop table
+DROP TABLE IF EXISTS transactions;
@@ -0,0 +1,30 @@
+-- Add up migration script here
+-- Create transactions table. Amounts are decimals stored as text, exactly,
+-- timestamps are RFC 3339 text in UTC with microseconds so that they sort
+-- as text.
+CREATE TABLE IF NOT EXISTS transactions (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  amount TEXT NOT NULL,
+  source TEXT NOT NULL CHECK (length(source) <= 255),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'))
+);
+
+-- Create index on source for faster lookups
+CREATE INDEX idx_transactions_source ON transactions(source);
+
+-- Create index on created_at for time-based queries
+CREATE INDEX idx_transactions_created_at ON transactions(created_at DESC);
+
+-- Trigger to automatically update updated_at on row updates. SQLite triggers
+-- cannot assign NEW, updates leaving updated_at unchanged are followed by
+-- another one setting it.
+CREATE TRIGGER update_transactions_updated_at
+  AFTER UPDATE ON transactions
+  FOR EACH ROW
+  WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE transactions
+  SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')
+  WHERE id = NEW.id;
+END;
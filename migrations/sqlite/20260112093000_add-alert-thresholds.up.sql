@@ -0,0 +1,42 @@
+-- Add up migration script here
+-- Create alert thresholds table
+CREATE TABLE IF NOT EXISTS alert_thresholds (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  source TEXT NOT NULL CHECK (length(source) <= 255),
+  period TEXT NOT NULL CHECK (period IN ('day', 'week', 'month')),
+  comparison TEXT NOT NULL CHECK (comparison IN ('below', 'above')),
+  threshold TEXT NOT NULL CHECK (CAST(threshold AS REAL) >= 0),
+  hysteresis TEXT NOT NULL DEFAULT '0' CHECK (CAST(hysteresis AS REAL) >= 0),
+  state TEXT NOT NULL DEFAULT 'ok' CHECK (state IN ('ok', 'triggered')),
+  last_value TEXT,
+  last_evaluated_at TEXT,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'))
+);
+
+-- Create index on source for faster lookups
+CREATE INDEX idx_alert_thresholds_source ON alert_thresholds(source);
+
+-- Create alerts table, one row per threshold state transition
+CREATE TABLE IF NOT EXISTS alerts (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  threshold_id INTEGER NOT NULL REFERENCES alert_thresholds(id) ON DELETE CASCADE,
+  kind TEXT NOT NULL CHECK (kind IN ('triggered', 'resolved')),
+  value TEXT NOT NULL,
+  threshold TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'))
+);
+
+-- Create index on threshold_id for faster lookups
+CREATE INDEX idx_alerts_threshold_id ON alerts(threshold_id);
+
+-- Trigger to automatically update updated_at on row updates
+CREATE TRIGGER update_alert_thresholds_updated_at
+  AFTER UPDATE ON alert_thresholds
+  FOR EACH ROW
+  WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE alert_thresholds
+  SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')
+  WHERE id = NEW.id;
+END;
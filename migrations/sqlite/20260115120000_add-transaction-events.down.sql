@@ -0,0 +1,10 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS record_transactions_delete;
+DROP TRIGGER IF EXISTS record_transactions_update;
+DROP TRIGGER IF EXISTS record_transactions_insert;
+
+-- Drop indexes
+DROP INDEX IF EXISTS idx_transaction_events_source;
+
+-- Drop table
+DROP TABLE IF EXISTS transaction_events;
@@ -0,0 +1,57 @@
+-- Add up migration script here
+-- Create transaction events table, an append-only log of changes used for stream resumption
+CREATE TABLE IF NOT EXISTS transaction_events (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  operation TEXT NOT NULL CHECK (operation IN ('created', 'updated', 'deleted')),
+  transaction_id INTEGER NOT NULL,
+  source TEXT NOT NULL CHECK (length(source) <= 255),
+  payload TEXT NOT NULL CHECK (json_valid(payload)),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'))
+);
+
+-- Create index on source for filtered replays
+CREATE INDEX idx_transaction_events_source ON transaction_events(source);
+
+-- Triggers to record every change on transactions. SQLite has no
+-- notifications, the events are only replayed.
+CREATE TRIGGER record_transactions_insert
+  AFTER INSERT ON transactions
+  FOR EACH ROW
+BEGIN
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES ('created', NEW.id, NEW.source, json_object(
+    'id', NEW.id,
+    'amount', NEW.amount,
+    'source', NEW.source,
+    'created_at', NEW.created_at,
+    'updated_at', NEW.updated_at
+  ));
+END;
+
+CREATE TRIGGER record_transactions_update
+  AFTER UPDATE ON transactions
+  FOR EACH ROW
+BEGIN
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES ('updated', NEW.id, NEW.source, json_object(
+    'id', NEW.id,
+    'amount', NEW.amount,
+    'source', NEW.source,
+    'created_at', NEW.created_at,
+    'updated_at', NEW.updated_at
+  ));
+END;
+
+CREATE TRIGGER record_transactions_delete
+  AFTER DELETE ON transactions
+  FOR EACH ROW
+BEGIN
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES ('deleted', OLD.id, OLD.source, json_object(
+    'id', OLD.id,
+    'amount', OLD.amount,
+    'source', OLD.source,
+    'created_at', OLD.created_at,
+    'updated_at', OLD.updated_at
+  ));
+END;
@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- Realtime notifications are published with pg_notify, SQLite has nothing
+-- equivalent. Kept so that both databases share their migration versions.
+SELECT 1;
@@ -0,0 +1,11 @@
+-- Add up migration script here
+-- Create rate limit buckets table, the token buckets shared by every replica
+CREATE TABLE IF NOT EXISTS rate_limit_buckets (
+  key TEXT PRIMARY KEY,
+  tokens REAL NOT NULL,
+  allowed INTEGER NOT NULL CHECK (allowed IN (0, 1)),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'))
+);
+
+-- Create index on updated_at for removing idle buckets
+CREATE INDEX idx_rate_limit_buckets_updated_at ON rate_limit_buckets(updated_at);
@@ -0,0 +1,52 @@
+-- Add down migration script here
+-- Restore the change events without the new columns
+DROP TRIGGER IF EXISTS record_transactions_insert;
+DROP TRIGGER IF EXISTS record_transactions_update;
+DROP TRIGGER IF EXISTS record_transactions_delete;
+
+CREATE TRIGGER record_transactions_insert
+  AFTER INSERT ON transactions
+  FOR EACH ROW
+BEGIN
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES ('created', NEW.id, NEW.source, json_object(
+    'id', NEW.id,
+    'amount', NEW.amount,
+    'source', NEW.source,
+    'created_at', NEW.created_at,
+    'updated_at', NEW.updated_at
+  ));
+END;
+
+CREATE TRIGGER record_transactions_update
+  AFTER UPDATE ON transactions
+  FOR EACH ROW
+BEGIN
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES ('updated', NEW.id, NEW.source, json_object(
+    'id', NEW.id,
+    'amount', NEW.amount,
+    'source', NEW.source,
+    'created_at', NEW.created_at,
+    'updated_at', NEW.updated_at
+  ));
+END;
+
+CREATE TRIGGER record_transactions_delete
+  AFTER DELETE ON transactions
+  FOR EACH ROW
+BEGIN
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES ('deleted', OLD.id, OLD.source, json_object(
+    'id', OLD.id,
+    'amount', OLD.amount,
+    'source', OLD.source,
+    'created_at', OLD.created_at,
+    'updated_at', OLD.updated_at
+  ));
+END;
+
+-- Drop columns
+ALTER TABLE transactions DROP COLUMN tags;
+ALTER TABLE transactions DROP COLUMN kind;
+ALTER TABLE transactions DROP COLUMN currency;
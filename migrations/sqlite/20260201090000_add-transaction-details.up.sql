@@ -0,0 +1,68 @@
+-- Add up migration script here
+-- Add the currency, kind and tags of transactions, existing rows become USD
+-- deposits. Tags are a JSON array of strings.
+ALTER TABLE transactions
+  ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD'
+    CHECK (currency GLOB '[A-Z][A-Z][A-Z]');
+ALTER TABLE transactions
+  ADD COLUMN kind TEXT NOT NULL DEFAULT 'deposit'
+    CHECK (kind IN ('deposit', 'withdrawal', 'interest', 'transfer'));
+ALTER TABLE transactions
+  ADD COLUMN tags TEXT NOT NULL DEFAULT '[]'
+    CHECK (json_valid(tags) AND json_type(tags) = 'array');
+
+-- Include the new columns in the recorded change events
+DROP TRIGGER IF EXISTS record_transactions_insert;
+DROP TRIGGER IF EXISTS record_transactions_update;
+DROP TRIGGER IF EXISTS record_transactions_delete;
+
+CREATE TRIGGER record_transactions_insert
+  AFTER INSERT ON transactions
+  FOR EACH ROW
+BEGIN
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES ('created', NEW.id, NEW.source, json_object(
+    'id', NEW.id,
+    'amount', NEW.amount,
+    'currency', NEW.currency,
+    'kind', NEW.kind,
+    'tags', json(NEW.tags),
+    'source', NEW.source,
+    'created_at', NEW.created_at,
+    'updated_at', NEW.updated_at
+  ));
+END;
+
+CREATE TRIGGER record_transactions_update
+  AFTER UPDATE ON transactions
+  FOR EACH ROW
+BEGIN
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES ('updated', NEW.id, NEW.source, json_object(
+    'id', NEW.id,
+    'amount', NEW.amount,
+    'currency', NEW.currency,
+    'kind', NEW.kind,
+    'tags', json(NEW.tags),
+    'source', NEW.source,
+    'created_at', NEW.created_at,
+    'updated_at', NEW.updated_at
+  ));
+END;
+
+CREATE TRIGGER record_transactions_delete
+  AFTER DELETE ON transactions
+  FOR EACH ROW
+BEGIN
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES ('deleted', OLD.id, OLD.source, json_object(
+    'id', OLD.id,
+    'amount', OLD.amount,
+    'currency', OLD.currency,
+    'kind', OLD.kind,
+    'tags', json(OLD.tags),
+    'source', OLD.source,
+    'created_at', OLD.created_at,
+    'updated_at', OLD.updated_at
+  ));
+END;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE alert_thresholds DROP COLUMN currency;
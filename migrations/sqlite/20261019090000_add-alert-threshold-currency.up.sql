@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- Thresholds watch the savings of a single currency, existing ones USD
+ALTER TABLE alert_thresholds
+  ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD'
+    CHECK (currency GLOB '[A-Z][A-Z][A-Z]');
@@ -0,0 +1,19 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS record_transactions_update;
+
+CREATE TRIGGER record_transactions_update
+  AFTER UPDATE ON transactions
+  FOR EACH ROW
+BEGIN
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES ('updated', NEW.id, NEW.source, json_object(
+    'id', NEW.id,
+    'amount', NEW.amount,
+    'currency', NEW.currency,
+    'kind', NEW.kind,
+    'tags', json(NEW.tags),
+    'source', NEW.source,
+    'created_at', NEW.created_at,
+    'updated_at', NEW.updated_at
+  ));
+END;
@@ -0,0 +1,27 @@
+-- Add up migration script here
+-- Record a single event per update. The update of updated_at that follows
+-- updates leaving it unchanged, see update_transactions_updated_at, is not
+-- one: the event of the first update carries the updated_at it sets, 'now'
+-- being the same for the whole statement.
+DROP TRIGGER IF EXISTS record_transactions_update;
+
+CREATE TRIGGER record_transactions_update
+  AFTER UPDATE OF id, amount, currency, kind, tags, source, created_at ON transactions
+  FOR EACH ROW
+BEGIN
+  INSERT INTO transaction_events (operation, transaction_id, source, payload)
+  VALUES ('updated', NEW.id, NEW.source, json_object(
+    'id', NEW.id,
+    'amount', NEW.amount,
+    'currency', NEW.currency,
+    'kind', NEW.kind,
+    'tags', json(NEW.tags),
+    'source', NEW.source,
+    'created_at', NEW.created_at,
+    'updated_at', CASE
+      WHEN NEW.updated_at = OLD.updated_at
+        THEN strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')
+      ELSE NEW.updated_at
+    END
+  ));
+END;
@@ -0,0 +1,270 @@
+use crate::adapters::metrics::METRICS;
+use crate::config::Config;
+use crate::errors::AppResult;
+use serde::{Serialize, de::DeserializeOwned};
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Store for serialized read results. Every removal bumps the generation, so
+/// that a result loaded before an invalidation is never stored after it.
+pub trait Cache: Send + Sync {
+    fn get(&self, key: &str) -> Option<Arc<[u8]>>;
+    /// Store `value` unless something was invalidated since `generation`
+    fn insert(&self, key: String, value: Vec<u8>, generation: u64);
+    fn remove(&self, key: &str);
+    fn remove_prefix(&self, prefix: &str);
+    fn clear(&self);
+    fn generation(&self) -> u64;
+}
+
+struct Entry {
+    value: Arc<[u8]>,
+    expires_at: Instant,
+}
+
+/// In-process cache evicting the least recently used entries beyond its
+/// capacity, entries also expire after a fixed time to live
+pub struct LruCache {
+    entries: Mutex<lru::LruCache<String, Entry>>,
+    ttl: Duration,
+    generation: AtomicU64,
+}
+
+impl LruCache {
+    pub fn new(capacity: NonZeroUsize, ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(lru::LruCache::new(capacity)),
+            ttl,
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Cache described by the configuration, `None` when caching is disabled
+    pub fn from_config(config: &Config) -> Option<Arc<dyn Cache>> {
+        if !config.cache_enabled {
+            return None;
+        }
+
+        let capacity = NonZeroUsize::new(config.cache_capacity)?;
+        Some(Arc::new(Self::new(
+            capacity,
+            Duration::from_secs(config.cache_ttl_secs),
+        )))
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, lru::LruCache<String, Entry>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Cache for LruCache {
+    fn get(&self, key: &str) -> Option<Arc<[u8]>> {
+        let mut entries = self.entries();
+        match entries.get(key) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(entry.value.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, key: String, value: Vec<u8>, generation: u64) {
+        let mut entries = self.entries();
+        // Checked under the lock, removals bump the generation while holding it
+        if self.generation.load(Ordering::Acquire) != generation {
+            return;
+        }
+        entries.put(
+            key,
+            Entry {
+                value: value.into(),
+                expires_at: Instant::now() + self.ttl,
+            },
+        );
+    }
+
+    fn remove(&self, key: &str) {
+        let mut entries = self.entries();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        entries.pop(key);
+    }
+
+    fn remove_prefix(&self, prefix: &str) {
+        let mut entries = self.entries();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        let keys: Vec<String> = entries
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys {
+            entries.pop(&key);
+        }
+    }
+
+    fn clear(&self) {
+        let mut entries = self.entries();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        entries.clear();
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+}
+
+/// Cache-aside read: serve `key` from the cache, or run `load` and cache its
+/// result. `kind` labels the hit/miss metrics. Without a cache, always loads.
+pub async fn get_or_load<T, F, Fut>(
+    cache: Option<&Arc<dyn Cache>>,
+    kind: &str,
+    key: String,
+    load: F,
+) -> AppResult<T>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = AppResult<T>>,
+{
+    let Some(cache) = cache else {
+        return load().await;
+    };
+
+    if let Some(bytes) = cache.get(&key) {
+        match serde_json::from_slice(&bytes) {
+            Ok(value) => {
+                METRICS.record_cache_lookup(kind, true);
+                return Ok(value);
+            }
+            Err(e) => tracing::warn!(%key, error = %e, "⚠️ Discarding unreadable cache entry"),
+        }
+    }
+    METRICS.record_cache_lookup(kind, false);
+
+    let generation = cache.generation();
+    let value = load().await?;
+    match serde_json::to_vec(&value) {
+        Ok(bytes) => cache.insert(key, bytes, generation),
+        Err(e) => tracing::warn!(%key, error = %e, "⚠️ Failed to serialize cache entry"),
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(capacity: usize, ttl: Duration) -> LruCache {
+        LruCache::new(NonZeroUsize::new(capacity).unwrap(), ttl)
+    }
+
+    fn put(cache: &LruCache, key: &str) {
+        cache.insert(key.to_string(), key.as_bytes().to_vec(), cache.generation());
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entry() {
+        let cache = cache(2, Duration::from_secs(60));
+        put(&cache, "a");
+        put(&cache, "b");
+
+        // Reading "a" makes "b" the least recently used
+        assert_eq!(cache.get("a").as_deref(), Some(&b"a"[..]));
+        put(&cache, "c");
+
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn expires_entries_after_their_time_to_live() {
+        let cache = cache(2, Duration::from_millis(50));
+        put(&cache, "a");
+        assert!(cache.get("a").is_some());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(cache.get("a").is_none());
+
+        // Stored again with a fresh time to live
+        put(&cache, "a");
+        assert!(cache.get("a").is_some());
+    }
+
+    #[test]
+    fn removes_entries_by_key_prefix_and_all_at_once() {
+        let cache = cache(10, Duration::from_secs(60));
+        for key in ["savings:1", "savings:2", "savings:count:{}"] {
+            put(&cache, key);
+        }
+
+        cache.remove("savings:1");
+        assert!(cache.get("savings:1").is_none());
+        assert!(cache.get("savings:2").is_some());
+
+        cache.remove_prefix("savings:count:");
+        assert!(cache.get("savings:count:{}").is_none());
+        assert!(cache.get("savings:2").is_some());
+
+        cache.clear();
+        assert!(cache.get("savings:2").is_none());
+    }
+
+    #[test]
+    fn skips_inserts_loaded_before_an_invalidation() {
+        let cache = cache(10, Duration::from_secs(60));
+
+        for invalidate in [
+            |cache: &LruCache| cache.remove("elsewhere"),
+            |cache: &LruCache| cache.remove_prefix("elsewhere"),
+            |cache: &LruCache| cache.clear(),
+        ] {
+            let generation = cache.generation();
+            invalidate(&cache);
+            assert_ne!(cache.generation(), generation);
+
+            cache.insert("a".to_string(), b"stale".to_vec(), generation);
+            assert!(cache.get("a").is_none());
+        }
+
+        cache.insert("a".to_string(), b"fresh".to_vec(), cache.generation());
+        assert_eq!(cache.get("a").as_deref(), Some(&b"fresh"[..]));
+    }
+
+    #[tokio::test]
+    async fn does_not_cache_a_load_raced_by_an_invalidation() {
+        let cache: Arc<dyn Cache> = Arc::new(cache(10, Duration::from_secs(60)));
+
+        // The row changes while it is being read, the invalidation lands
+        // before the stale result gets back
+        let value = get_or_load(Some(&cache), "test", "savings:1".to_string(), || async {
+            cache.remove("savings:1");
+            Ok(1)
+        })
+        .await
+        .unwrap();
+        assert_eq!(value, 1);
+        assert!(cache.get("savings:1").is_none());
+
+        let value = get_or_load(Some(&cache), "test", "savings:1".to_string(), || async {
+            Ok(2)
+        })
+        .await
+        .unwrap();
+        assert_eq!(value, 2);
+
+        // Served from the cache from now on
+        let value: i32 = get_or_load(Some(&cache), "test", "savings:1".to_string(), || async {
+            panic!("loaded a cached value")
+        })
+        .await
+        .unwrap();
+        assert_eq!(value, 2);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::models::events::FeedEvent;
+use crate::shutdown::ShutdownCoordinator;
+use sqlx::{PgPool, postgres::PgListener};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Postgres channel the `transactions` trigger publishes on
+pub const TRANSACTION_EVENTS_CHANNEL: &str = "transaction_events";
+/// Postgres channel the `alert_thresholds` triggers publish on
+pub const GOAL_EVENTS_CHANNEL: &str = "goal_events";
+/// Postgres channel the `alerts` trigger publishes on
+pub const NOTIFICATION_EVENTS_CHANNEL: &str = "notification_events";
+
+const BROADCAST_CAPACITY: usize = 1024;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Fan-out of database change notifications to in-process subscribers
+#[derive(Clone)]
+pub struct ChangeFeed {
+    sender: broadcast::Sender<FeedEvent>,
+    heartbeat_interval: Duration,
+}
+
+impl ChangeFeed {
+    /// A feed nothing is published on, for applications running without a
+    /// database listener
+    pub fn new(heartbeat_interval: Duration) -> Self {
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        Self {
+            sender,
+            heartbeat_interval,
+        }
+    }
+
+    /// Start listening on the change channels and relay every notification
+    /// to subscribers
+    pub async fn start(
+        pool: &PgPool,
+        heartbeat_interval: Duration,
+        shutdown: &ShutdownCoordinator,
+    ) -> Result<Self, sqlx::Error> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener
+            .listen_all([
+                TRANSACTION_EVENTS_CHANNEL,
+                GOAL_EVENTS_CHANNEL,
+                NOTIFICATION_EVENTS_CHANNEL,
+            ])
+            .await?;
+
+        let feed = Self::new(heartbeat_interval);
+        let relay = feed.sender.clone();
+
+        let token = shutdown.token();
+
+        shutdown.spawn("change_feed", async move {
+            // Set after an error, the connection may have been lost with it
+            let mut resync = false;
+
+            loop {
+                if resync {
+                    // Reconnects the listener if needed, listening again on
+                    // every channel, before subscribers are told to resync
+                    let reconnected = tokio::select! {
+                        result = sqlx::query("SELECT 1").execute(&mut listener) => result,
+                        _ = token.cancelled() => break,
+                    };
+                    match reconnected {
+                        Ok(_) => {
+                            resync = false;
+                            let _ = relay.send(FeedEvent::Resync);
+                        }
+                        Err(e) => {
+                            tracing::error!(error = %e, "❌ Change feed listener error");
+                            tokio::time::sleep(RETRY_DELAY).await;
+                            continue;
+                        }
+                    }
+                }
+
+                let received = tokio::select! {
+                    received = listener.try_recv() => received,
+                    // Dropping the listener hands its connection back to the pool
+                    _ = token.cancelled() => break,
+                };
+
+                match received {
+                    // The connection was lost and the listener reconnected,
+                    // notifications sent in between are gone
+                    Ok(None) => {
+                        tracing::warn!("⚠️ Change feed listener reconnected");
+                        let _ = relay.send(FeedEvent::Resync);
+                    }
+                    Ok(Some(notification)) => {
+                        match parse_event(notification.channel(), notification.payload()) {
+                            // Sending only fails when nobody is subscribed
+                            Ok(event) => {
+                                let _ = relay.send(event);
+                            }
+                            Err(e) => tracing::warn!(
+                                channel = notification.channel(),
+                                error = %e,
+                                "⚠️ Ignoring malformed change event"
+                            ),
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!(error = %e, "❌ Change feed listener error");
+                        tokio::time::sleep(RETRY_DELAY).await;
+                        resync = true;
+                    }
+                }
+            }
+        });
+
+        tracing::info!("📡 Listening for database change notifications");
+        Ok(feed)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<FeedEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn heartbeat_interval(&self) -> Duration {
+        self.heartbeat_interval
+    }
+}
+
+fn parse_event(channel: &str, payload: &str) -> Result<FeedEvent, serde_json::Error> {
+    match channel {
+        GOAL_EVENTS_CHANNEL => serde_json::from_str(payload).map(FeedEvent::Goals),
+        NOTIFICATION_EVENTS_CHANNEL => serde_json::from_str(payload).map(FeedEvent::Notifications),
+        _ => serde_json::from_str(payload).map(FeedEvent::Savings),
+    }
+}
@@ -1,53 +1,546 @@
-use sqlx::{Error, PgPool, postgres::PgPoolOptions};
+use crate::adapters::cache::Cache;
+use crate::config::Config;
+use crate::models::migrations::AppliedMigration;
+use sqlx::{
+    Error, PgConnection, PgExecutor, PgPool, Postgres,
+    migrate::{MigrateError, Migrator},
+    pool::PoolConnection,
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
-/// Database configuration
+/// Migrations embedded in the binary
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Session advisory lock held while migrating, so that replicas starting at
+/// the same time apply migrations one after the other
+pub const MIGRATION_LOCK_KEY: i64 = 0x6773_6e5f_6d69_6772;
+
+/// Postgres error raised when `_sqlx_migrations` has not been created yet
+const UNDEFINED_TABLE: &str = "42P01";
+
+/// Longest wait between two attempts at the initial connection
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A replica that can't hand out a connection quickly is treated as down,
+/// the primary serves the read instead
+const REPLICA_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Database configuration. Timeouts of 0 disable the corresponding limit.
 #[derive(Debug, Clone)]
 pub struct DatabaseConfig {
     pub url: String,
+    /// Read replica, reads go to the primary when unset
+    pub read_url: Option<String>,
+    /// Lag beyond which reads go back to the primary
+    pub replica_max_lag_ms: u64,
     pub max_connections: u32,
     pub min_connections: u32,
+    /// Seconds to wait for a connection from the pool
     pub connect_timeout: u64,
+    /// Seconds a connection may stay idle before being closed
     pub idle_timeout: u64,
+    /// Seconds after which a connection is replaced
+    pub max_lifetime: u64,
+    /// Ping connections before handing them out
+    pub test_before_acquire: bool,
+    /// Set as `statement_timeout` on every connection
+    pub statement_timeout_ms: u64,
+    /// Set as `lock_timeout` on every connection
+    pub lock_timeout_ms: u64,
+    /// Reported in `pg_stat_activity`
+    pub application_name: String,
+    /// Extra attempts at the initial connection before giving up
+    pub connect_retries: u32,
+    /// Wait before the first retry, doubled after every failed attempt
+    pub connect_backoff_ms: u64,
 }
 
 impl Default for DatabaseConfig {
     fn default() -> Self {
         Self {
             url: String::new(),
+            read_url: None,
+            replica_max_lag_ms: 5_000,
             max_connections: 10,
             min_connections: 2,
             connect_timeout: 10,
             idle_timeout: 600,
+            max_lifetime: 1800,
+            test_before_acquire: true,
+            statement_timeout_ms: 30_000,
+            lock_timeout_ms: 5_000,
+            application_name: String::new(),
+            connect_retries: 5,
+            connect_backoff_ms: 500,
         }
     }
 }
 
-/// Initialize database connection pool
-pub async fn init_pool(config: &DatabaseConfig) -> Result<PgPool, Error> {
-    log::info!("🔌 Initializing database connection pool...");
+impl From<&Config> for DatabaseConfig {
+    fn from(config: &Config) -> Self {
+        let application_name = if config.db_application_name.is_empty() {
+            config.name.clone()
+        } else {
+            config.db_application_name.clone()
+        };
+
+        Self {
+            url: config.database_url.clone(),
+            read_url: config
+                .database_read_url
+                .clone()
+                .filter(|url| !url.is_empty()),
+            replica_max_lag_ms: config.db_replica_max_lag_ms,
+            max_connections: config.db_max_connections,
+            min_connections: config.db_min_connections,
+            connect_timeout: config.db_timeout_connection,
+            idle_timeout: config.db_idle_timeout,
+            max_lifetime: config.db_max_lifetime,
+            test_before_acquire: config.db_test_before_acquire,
+            statement_timeout_ms: config.db_statement_timeout_ms,
+            lock_timeout_ms: config.db_lock_timeout_ms,
+            application_name,
+            connect_retries: config.db_connect_retries,
+            connect_backoff_ms: config.db_connect_backoff_ms,
+        }
+    }
+}
+
+fn seconds(value: u64) -> Option<Duration> {
+    (value > 0).then(|| Duration::from_secs(value))
+}
+
+fn connect_options(config: &DatabaseConfig, url: &str) -> Result<PgConnectOptions, Error> {
+    // Sent as startup parameters, so every connection has them from the start
+    Ok(PgConnectOptions::from_str(url)?
+        .application_name(&config.application_name)
+        .options([
+            ("statement_timeout", config.statement_timeout_ms.to_string()),
+            ("lock_timeout", config.lock_timeout_ms.to_string()),
+        ]))
+}
 
-    let pool = PgPoolOptions::new()
+fn pool_options(config: &DatabaseConfig) -> PgPoolOptions {
+    PgPoolOptions::new()
         .max_connections(config.max_connections)
         .min_connections(config.min_connections)
         .acquire_timeout(Duration::from_secs(config.connect_timeout))
-        .idle_timeout(Duration::from_secs(config.idle_timeout))
-        .connect(&config.url)
-        .await?;
+        .idle_timeout(seconds(config.idle_timeout))
+        .max_lifetime(seconds(config.max_lifetime))
+        .test_before_acquire(config.test_before_acquire)
+}
+
+/// Initialize database connection pool, retrying with backoff while the
+/// database is unreachable
+pub async fn init_pool(config: &DatabaseConfig) -> Result<PgPool, Error> {
+    tracing::info!("🔌 Initializing database connection pool...");
 
-    log::info!("✅ Database connection pool initialized successfully");
+    let connect_options = connect_options(config, &config.url)?;
+    let pool_options = pool_options(config);
+
+    let mut backoff = Duration::from_millis(config.connect_backoff_ms);
+    let mut attempt = 0;
+    let pool = loop {
+        match pool_options
+            .clone()
+            .connect_with(connect_options.clone())
+            .await
+        {
+            Ok(pool) => break pool,
+            // A malformed configuration won't fix itself
+            Err(e @ Error::Configuration(_)) => return Err(e),
+            Err(e) if attempt < config.connect_retries => {
+                attempt += 1;
+                tracing::warn!(
+                    error = %e,
+                    attempt,
+                    retries = config.connect_retries,
+                    backoff_ms = backoff.as_millis() as u64,
+                    "⚠️ Database connection failed, retrying"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
+            }
+            Err(e) => return Err(e),
+        }
+    };
+
+    tracing::info!("✅ Database connection pool initialized successfully");
     Ok(pool)
 }
 
-/// Run database migrations
-pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::migrate::MigrateError> {
-    log::info!("🔄 Running database migrations...");
-    sqlx::migrate!("./migrations").run(pool).await?;
+/// Initialize the primary pool and, when configured, the read replica pool.
+/// Replica connections are opened on first use so that an unreachable replica
+/// never blocks startup, reads fall back to the primary meanwhile.
+pub async fn init_pools(config: &DatabaseConfig) -> Result<DbPools, Error> {
+    let pools = DbPools::new(init_pool(config).await?);
+
+    match &config.read_url {
+        Some(url) => {
+            tracing::info!("🔌 Read replica configured, routing read-only queries to it");
+            let replica = pool_options(config)
+                .min_connections(0)
+                .acquire_timeout(REPLICA_ACQUIRE_TIMEOUT)
+                .connect_lazy_with(connect_options(config, url)?);
+            Ok(pools.with_replica(replica, Duration::from_millis(config.replica_max_lag_ms)))
+        }
+        None => Ok(pools),
+    }
+}
+
+struct Replica {
+    pool: PgPool,
+    max_lag: Duration,
+    usable: AtomicBool,
+}
+
+/// The primary pool, used for every write, and an optional read replica for
+/// read-only queries. The replica is only used while `check_replica` finds it
+/// reachable and no more than `max_lag` behind the primary.
+///
+/// Cloning is cheap. A handle obtained from `session` keeps reading from the
+/// primary once it wrote, so a request always reads its own writes.
+///
+/// Read results may also be kept in a `Cache` shared by every handle, those
+/// are always read from the primary.
+#[derive(Clone)]
+pub struct DbPools {
+    primary: PgPool,
+    replica: Option<Arc<Replica>>,
+    /// Only tracked by session handles, the shared handle never pins reads
+    wrote: Option<Arc<AtomicBool>>,
+    cache: Option<Arc<dyn Cache>>,
+}
+
+impl From<PgPool> for DbPools {
+    fn from(primary: PgPool) -> Self {
+        Self::new(primary)
+    }
+}
+
+impl DbPools {
+    pub fn new(primary: PgPool) -> Self {
+        Self {
+            primary,
+            replica: None,
+            wrote: None,
+            cache: None,
+        }
+    }
+
+    /// Route reads to `replica`, which is considered unusable until checked
+    pub fn with_replica(mut self, replica: PgPool, max_lag: Duration) -> Self {
+        self.replica = Some(Arc::new(Replica {
+            pool: replica,
+            max_lag,
+            usable: AtomicBool::new(false),
+        }));
+        self
+    }
+
+    /// Keep read results in `cache`
+    pub fn with_cache(mut self, cache: Option<Arc<dyn Cache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn cache(&self) -> Option<&Arc<dyn Cache>> {
+        self.cache.as_ref()
+    }
+
+    /// A handle sharing the pools with its own read-your-writes state
+    pub fn session(&self) -> Self {
+        Self {
+            wrote: Some(Arc::new(AtomicBool::new(false))),
+            ..self.clone()
+        }
+    }
+
+    pub fn primary(&self) -> &PgPool {
+        &self.primary
+    }
+
+    pub fn replica(&self) -> Option<&PgPool> {
+        self.replica.as_ref().map(|replica| &replica.pool)
+    }
+
+    /// Pool for writes. Later reads through a session handle go to the primary.
+    pub fn write(&self) -> &PgPool {
+        if let Some(wrote) = &self.wrote {
+            wrote.store(true, Ordering::SeqCst);
+        }
+        &self.primary
+    }
+
+    fn usable_replica(&self) -> Option<&Replica> {
+        let wrote = self
+            .wrote
+            .as_ref()
+            .is_some_and(|wrote| wrote.load(Ordering::SeqCst));
+        self.replica
+            .as_deref()
+            .filter(|replica| !wrote && replica.usable.load(Ordering::SeqCst))
+    }
+
+    /// Pool for read-only queries
+    pub fn read(&self) -> &PgPool {
+        self.usable_replica()
+            .map_or(&self.primary, |replica| &replica.pool)
+    }
+
+    /// Run a read-only query, on the primary again if the replica turns out
+    /// to be unreachable. The replica is then left aside until the next check.
+    pub async fn read_with<T, F, Fut>(&self, query: F) -> Result<T, Error>
+    where
+        F: Fn(PgPool) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let Some(replica) = self.usable_replica() else {
+            return query(self.primary.clone()).await;
+        };
+
+        match query(replica.pool.clone()).await {
+            Err(e) if is_connection_error(&e) => {
+                tracing::warn!(error = %e, "⚠️ Read replica unreachable, reading from the primary");
+                replica.usable.store(false, Ordering::SeqCst);
+                query(self.primary.clone()).await
+            }
+            result => result,
+        }
+    }
+
+    /// Run a read-only query whose result may be cached. With a cache, it
+    /// runs on the primary: a lagging replica would refill the cache with the
+    /// values a write just invalidated, until they expire.
+    pub async fn read_cacheable_with<T, F, Fut>(&self, query: F) -> Result<T, Error>
+    where
+        F: Fn(PgPool) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        if self.cache.is_some() {
+            return query(self.primary.clone()).await;
+        }
+        self.read_with(query).await
+    }
+
+    /// Close the primary and the replica pools
+    pub async fn close(&self) {
+        self.primary.close().await;
+        if let Some(replica) = &self.replica {
+            replica.pool.close().await;
+        }
+    }
+
+    /// Measure how far the replica is behind the primary and decide whether
+    /// reads may use it. Returns the lag, or `None` without a replica.
+    pub async fn check_replica(&self) -> Option<Result<Duration, Error>> {
+        let replica = self.replica.as_ref()?;
+        let lag = replication_lag(&self.primary, &replica.pool).await;
+
+        let usable = matches!(&lag, Ok(lag) if *lag <= replica.max_lag);
+        if replica.usable.swap(usable, Ordering::SeqCst) != usable {
+            match &lag {
+                Ok(lag) if usable => {
+                    tracing::info!(lag_ms = lag.as_millis() as u64, "✅ Read replica in use")
+                }
+                Ok(lag) => tracing::warn!(
+                    lag_ms = lag.as_millis() as u64,
+                    max_lag_ms = replica.max_lag.as_millis() as u64,
+                    "⚠️ Read replica lagging, reading from the primary"
+                ),
+                Err(e) => tracing::warn!(
+                    error = %e,
+                    "⚠️ Read replica unreachable, reading from the primary"
+                ),
+            }
+        }
+        Some(lag)
+    }
+}
+
+fn is_connection_error(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Io(_) | Error::Tls(_) | Error::PoolTimedOut | Error::PoolClosed | Error::Protocol(_)
+    )
+}
+
+/// Time the replica is behind the primary. A replica that replayed all the WAL
+/// the primary has written is not lagging, however old its last transaction.
+/// A server that is not in recovery is not a replica and never lags.
+async fn replication_lag(primary: &PgPool, replica: &PgPool) -> Result<Duration, Error> {
+    let primary_lsn: String = sqlx::query_scalar("SELECT pg_current_wal_lsn()::TEXT")
+        .fetch_one(primary)
+        .await?;
+
+    let lag_secs: f64 = sqlx::query_scalar(
+        r#"
+        SELECT CASE
+            WHEN NOT pg_is_in_recovery() THEN 0
+            WHEN pg_wal_lsn_diff($1::PG_LSN, pg_last_wal_replay_lsn()) <= 0 THEN 0
+            ELSE COALESCE(EXTRACT(EPOCH FROM NOW() - pg_last_xact_replay_timestamp()), 0)
+        END::FLOAT8
+        "#,
+    )
+    .bind(primary_lsn)
+    .fetch_one(replica)
+    .await?;
+
+    Ok(Duration::from_secs_f64(lag_secs.max(0.0)))
+}
+
+/// Run database migrations while holding the migration advisory lock.
+/// Refuses to touch a schema migrated by a newer build.
+pub async fn run_migrations(pool: &PgPool) -> Result<(), MigrateError> {
+    let mut conn = lock_migrations(pool).await?;
+
+    tracing::info!("🔄 Running database migrations...");
+    let result = migrate_locked(&mut conn).await;
+    unlock_migrations(conn, result).await?;
+
+    tracing::info!("✅ Database migrations completed successfully");
+    Ok(())
+}
+
+/// Revert every applied migration newer than `target`
+pub async fn revert_migrations(pool: &PgPool, target: i64) -> Result<(), MigrateError> {
+    let mut conn = lock_migrations(pool).await?;
+
+    tracing::info!(target, "🔄 Reverting database migrations...");
+    let result = MIGRATOR.undo(&mut *conn, target).await;
+    unlock_migrations(conn, result).await?;
+
+    tracing::info!("✅ Database migrations reverted successfully");
+    Ok(())
+}
+
+async fn lock_migrations(pool: &PgPool) -> Result<PoolConnection<Postgres>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+
+    if let Err(e) = wait_for_migration_lock(&mut conn).await {
+        // Its timeouts may be disabled, it must not go back to the pool
+        drop(conn.detach());
+        return Err(e.into());
+    }
+    Ok(conn)
+}
+
+async fn wait_for_migration_lock(conn: &mut PgConnection) -> Result<(), Error> {
+    // Waiting for another instance and migrating may both take longer than
+    // the timeouts of the pool, they are restored on unlock
+    sqlx::raw_sql("SET lock_timeout = 0; SET statement_timeout = 0")
+        .execute(&mut *conn)
+        .await?;
+
+    let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .fetch_one(&mut *conn)
+        .await?;
+    if !locked {
+        tracing::info!("⏳ Waiting for another instance to finish migrating...");
+        sqlx::query("SELECT pg_advisory_lock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Release the migration lock, returning the result of the locked operation first
+async fn unlock_migrations(
+    mut conn: PoolConnection<Postgres>,
+    result: Result<(), MigrateError>,
+) -> Result<(), MigrateError> {
+    let mut unlocked = sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+        .map(drop);
+    if unlocked.is_ok() {
+        unlocked = sqlx::raw_sql("RESET lock_timeout; RESET statement_timeout")
+            .execute(&mut *conn)
+            .await
+            .map(drop);
+    }
+    if unlocked.is_err() {
+        // Closing the session is the only other way to release the lock,
+        // and keeps a connection without timeouts out of the pool
+        drop(conn.detach());
+    }
+
+    result?;
+    unlocked?;
+    Ok(())
+}
+
+async fn migrate_locked(conn: &mut PgConnection) -> Result<(), MigrateError> {
+    ensure_known_migrations(&mut *conn).await?;
+    MIGRATOR.run(conn).await
+}
+
+/// Fail when the database has migrations applied that this binary does not
+/// know about, i.e. the schema was migrated by a newer build
+pub async fn check_schema_compatibility(pool: &PgPool) -> Result<(), MigrateError> {
+    let mut conn = pool.acquire().await?;
+    ensure_known_migrations(&mut conn).await
+}
 
-    log::info!("✅ Database migrations completed successfully");
+async fn ensure_known_migrations(conn: &mut PgConnection) -> Result<(), MigrateError> {
+    for applied in applied_migrations(conn).await? {
+        if !MIGRATOR
+            .iter()
+            .any(|migration| migration.version == applied.version)
+        {
+            return Err(MigrateError::VersionMissing(applied.version));
+        }
+    }
     Ok(())
 }
 
+/// Migrations recorded in `_sqlx_migrations`, empty when none ever ran
+pub async fn applied_migrations<'e, E>(executor: E) -> Result<Vec<AppliedMigration>, Error>
+where
+    E: PgExecutor<'e>,
+{
+    let result = sqlx::query_as::<_, AppliedMigration>(
+        r#"
+        SELECT version, description, installed_on, success,
+               execution_time / 1000000 AS execution_time_ms
+        FROM _sqlx_migrations
+        ORDER BY version
+        "#,
+    )
+    .fetch_all(executor)
+    .await;
+
+    match result {
+        Err(Error::Database(e)) if e.code().as_deref() == Some(UNDEFINED_TABLE) => Ok(Vec::new()),
+        result => result,
+    }
+}
+
+/// Versions of the embedded migrations not yet applied to the database
+pub async fn pending_migrations(pool: &PgPool) -> Result<Vec<i64>, Error> {
+    let applied: Vec<i64> = applied_migrations(pool)
+        .await?
+        .into_iter()
+        .filter(|migration| migration.success)
+        .map(|migration| migration.version)
+        .collect();
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| migration.version)
+        .filter(|version| !applied.contains(version))
+        .collect())
+}
+
 /// Health check for database connection
 pub async fn health_check(pool: &PgPool) -> Result<(), Error> {
     sqlx::query("SELECT 1").fetch_one(pool).await?;
@@ -1,39 +1,211 @@
-use crate::config::{Config, LogLevel};
-use env_logger::{Builder, Target};
-use log::LevelFilter;
-use std::io::Write;
-
-pub fn init_logger(config: &Config) {
-    let log_level = match config.log_level {
-        LogLevel::Trace => LevelFilter::Trace,
-        LogLevel::Debug => LevelFilter::Debug,
-        LogLevel::Info => LevelFilter::Info,
-        LogLevel::Warn => LevelFilter::Warn,
-        LogLevel::Error => LevelFilter::Error,
-    };
-
-    let env = config.environment.clone();
-    let service_name = config.name.clone();
-
-    Builder::new()
-        .target(Target::Stdout)
-        .filter_level(log_level)
-        .format(move |buf, record| {
-            writeln!(
-                buf,
-                "[{} {} {}:{}] env={} service={} pid={} lang=Rust | {}",
-                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                record.level(),
-                record.file().unwrap_or("unknown"),
-                record.line().unwrap_or(0),
-                env,
-                service_name,
-                std::process::id(),
-                record.args()
-            )
-        })
+use crate::adapters::request_id::current_request_id;
+use crate::config::{Config, ConfigIssue, Environment, LogFormat, LogLevel};
+use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use serde_json::{Map, Value};
+use std::fmt;
+use tracing::{
+    Event, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_log::NormalizeEvent;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{
+    filter::LevelFilter,
+    fmt::{FmtContext, FormatEvent, FormatFields, format::Writer},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+};
+
+/// Formats every event either as a human readable line or as a single JSON
+/// object, tagged with the active trace and request ids when there are some
+struct LineFormat {
+    format: LogFormat,
+    env: Environment,
+    service_name: String,
+}
+
+impl<S, N> FormatEvent<S, N> for LineFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        // Events bridged from the `log` crate carry their original location here
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let trace_id = current_trace_id();
+        let request_id = current_request_id();
+
+        match self.format {
+            LogFormat::Text => {
+                write!(
+                    writer,
+                    "[{} {} {}:{}] env={} service={} pid={} lang=Rust",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    metadata.level(),
+                    metadata.file().unwrap_or("unknown"),
+                    metadata.line().unwrap_or(0),
+                    self.env,
+                    self.service_name,
+                    std::process::id(),
+                )?;
+                if let Some(trace_id) = trace_id {
+                    write!(writer, " trace_id={}", trace_id)?;
+                }
+                if let Some(request_id) = request_id {
+                    write!(writer, " request_id={}", request_id)?;
+                }
+
+                write!(writer, " | ")?;
+                ctx.format_fields(writer.by_ref(), event)?;
+                writeln!(writer)
+            }
+            LogFormat::Json => {
+                let mut visitor = JsonVisitor::default();
+                event.record(&mut visitor);
+
+                let mut line = Map::new();
+                line.insert("timestamp".into(), chrono::Utc::now().to_rfc3339().into());
+                line.insert("level".into(), metadata.level().as_str().into());
+                line.insert("target".into(), metadata.target().into());
+                line.insert("file".into(), metadata.file().into());
+                line.insert("line".into(), metadata.line().into());
+                line.insert("env".into(), self.env.to_string().into());
+                line.insert("service".into(), self.service_name.clone().into());
+                line.insert("pid".into(), std::process::id().into());
+                line.insert("message".into(), visitor.message.unwrap_or_default().into());
+                line.insert("trace_id".into(), trace_id.into());
+                line.insert("request_id".into(), request_id.into());
+                if !visitor.fields.is_empty() {
+                    line.insert("fields".into(), Value::Object(visitor.fields));
+                }
+
+                writeln!(writer, "{}", Value::Object(line))
+            }
+        }
+    }
+}
+
+/// Collects the message and structured fields of an event
+#[derive(Default)]
+struct JsonVisitor {
+    message: Option<String>,
+    fields: Map<String, Value>,
+}
+
+impl JsonVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        match field.name() {
+            "message" => self.message = value.as_str().map(str::to_string),
+            // Fields added by the `log` bridge, already part of the metadata
+            name if name.starts_with("log.") => {}
+            name => {
+                self.fields.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+impl Visit for JsonVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{:?}", value).into());
+    }
+}
+
+/// Trace id of the span the current event was recorded in
+pub fn current_trace_id() -> Option<String> {
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    span_context
+        .is_valid()
+        .then(|| span_context.trace_id().to_string())
+}
+
+fn level_filter(config: &Config) -> LevelFilter {
+    match config.log_level {
+        LogLevel::Trace => LevelFilter::TRACE,
+        LogLevel::Debug => LevelFilter::DEBUG,
+        LogLevel::Info => LevelFilter::INFO,
+        LogLevel::Warn => LevelFilter::WARN,
+        LogLevel::Error => LevelFilter::ERROR,
+    }
+}
+
+fn line_format(config: &Config) -> LineFormat {
+    LineFormat {
+        format: config.log_format.clone(),
+        env: config.environment.clone(),
+        service_name: config.name.clone(),
+    }
+}
+
+pub fn init_logger(config: &Config, tracer_provider: &SdkTracerProvider) {
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .event_format(line_format(config));
+    let otel_layer =
+        tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer(config.name.clone()));
+
+    tracing_subscriber::registry()
+        .with(level_filter(config))
+        .with(fmt_layer)
+        .with(otel_layer)
+        .init();
+
+    tracing::info!(level = %config.log_level, "Logger initialized");
+    tracing::info!(environment = %config.environment, exporter = %config.otel_exporter, "Tracing initialized");
+}
+
+/// Logger for management commands: no tracing export, and logs go to stderr
+/// so that stdout only carries the command output
+pub fn init_cli_logger(config: &Config) {
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_writer(std::io::stderr)
+        .event_format(line_format(config));
+
+    tracing_subscriber::registry()
+        .with(level_filter(config))
+        .with(fmt_layer)
         .init();
+}
 
-    log::info!("Logger initialized with level: {}", config.log_level);
-    log::info!("Environment: {:?}", config.environment);
+/// Report the invalid values `Config::load` replaced by their default, once
+/// a logger is initialized
+pub fn log_ignored_config(issues: &[ConfigIssue]) {
+    for issue in issues {
+        tracing::warn!(
+            key = %issue.key,
+            error = %issue.message,
+            "⚠️ Ignoring invalid config value, using the default"
+        );
+    }
 }
@@ -0,0 +1,343 @@
+use crate::models::transactions::{ImportedTransaction, Transaction, TransactionKind};
+use actix_web::{
+    Error,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+};
+use prometheus::{
+    CounterVec, Encoder, Gauge, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec,
+    Opts, Registry, TextEncoder,
+};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use sqlx::{ColumnIndex, Database, Decode, Executor, IntoArguments, Pool, Type};
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::time::Instant;
+
+pub(crate) const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Sources are free text from clients, only the first ones seen get a label
+/// of their own so that the series stay bounded
+const MAX_SOURCE_LABELS: usize = 100;
+const OTHER_SOURCES_LABEL: &str = "other";
+
+/// Prometheus collectors shared by the HTTP middleware, services and `/metrics`
+pub struct Metrics {
+    registry: Registry,
+    http_requests_total: IntCounterVec,
+    http_request_duration_seconds: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    db_pool_acquire_seconds: Gauge,
+    db_migration_version: IntGauge,
+    savings_created_total: IntCounterVec,
+    savings_amount_total: CounterVec,
+    source_labels: Mutex<HashSet<String>>,
+    cache_lookups_total: IntCounterVec,
+    cache_invalidations_total: IntCounterVec,
+}
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests_total = IntCounterVec::new(
+            Opts::new("http_requests_total", "Total HTTP requests"),
+            &["method", "route", "status"],
+        )
+        .expect("valid http_requests_total metric");
+        let http_request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency in seconds",
+            ),
+            &["method", "route", "status"],
+        )
+        .expect("valid http_request_duration_seconds metric");
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )
+        .expect("valid db_pool_connections metric");
+        let db_pool_acquire_seconds = Gauge::new(
+            "db_pool_acquire_seconds",
+            "Time taken to acquire a pooled connection at scrape time",
+        )
+        .expect("valid db_pool_acquire_seconds metric");
+        let db_migration_version = IntGauge::new(
+            "db_migration_version",
+            "Latest successfully applied database migration",
+        )
+        .expect("valid db_migration_version metric");
+        let app_info = IntGaugeVec::new(
+            Opts::new("app_info", "Application build information"),
+            &["version"],
+        )
+        .expect("valid app_info metric");
+        // Counted by each process for the savings it recorded, sum them over
+        // every instance for the totals
+        let savings_created_total = IntCounterVec::new(
+            Opts::new(
+                "savings_created_total",
+                "Savings created or imported by this process, per source",
+            ),
+            &["source"],
+        )
+        .expect("valid savings_created_total metric");
+        let savings_amount_total = CounterVec::new(
+            Opts::new(
+                "savings_amount_total",
+                "Amount of the savings created or imported by this process, per source, currency and kind",
+            ),
+            &["source", "currency", "kind"],
+        )
+        .expect("valid savings_amount_total metric");
+
+        let cache_lookups_total = IntCounterVec::new(
+            Opts::new("cache_lookups_total", "Cache lookups per kind and result"),
+            &["kind", "result"],
+        )
+        .expect("valid cache_lookups_total metric");
+        let cache_invalidations_total = IntCounterVec::new(
+            Opts::new(
+                "cache_invalidations_total",
+                "Cache invalidations per origin, local writes or database notifications",
+            ),
+            &["origin"],
+        )
+        .expect("valid cache_invalidations_total metric");
+
+        app_info
+            .with_label_values(&[env!("CARGO_PKG_VERSION")])
+            .set(1);
+
+        for collector in [
+            Box::new(http_requests_total.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_request_duration_seconds.clone()),
+            Box::new(db_pool_connections.clone()),
+            Box::new(db_pool_acquire_seconds.clone()),
+            Box::new(db_migration_version.clone()),
+            Box::new(app_info),
+            Box::new(savings_created_total.clone()),
+            Box::new(savings_amount_total.clone()),
+            Box::new(cache_lookups_total.clone()),
+            Box::new(cache_invalidations_total.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metrics are only registered once");
+        }
+
+        Self {
+            registry,
+            http_requests_total,
+            http_request_duration_seconds,
+            db_pool_connections,
+            db_pool_acquire_seconds,
+            db_migration_version,
+            savings_created_total,
+            savings_amount_total,
+            source_labels: Mutex::new(HashSet::new()),
+            cache_lookups_total,
+            cache_invalidations_total,
+        }
+    }
+
+    /// Record a newly created saving in the business counters
+    pub fn record_saving_created(&self, transaction: &Transaction) {
+        self.record_saving(
+            &transaction.source,
+            &transaction.currency,
+            transaction.kind,
+            transaction.amount,
+        );
+    }
+
+    /// Record imported savings in the business counters, like created ones
+    pub fn record_savings_imported(&self, records: &[ImportedTransaction]) {
+        for record in records {
+            self.record_saving(&record.source, &record.currency, record.kind, record.amount);
+        }
+    }
+
+    /// Amounts are counted per kind, withdrawals are not negative: the net
+    /// amount saved is the deposits and interests less the withdrawals
+    fn record_saving(&self, source: &str, currency: &str, kind: TransactionKind, amount: Decimal) {
+        let source = self.source_label(source);
+        self.savings_created_total
+            .with_label_values(&[source])
+            .inc();
+        self.savings_amount_total
+            .with_label_values(&[source, currency, kind.as_str()])
+            .inc_by(amount.abs().to_f64().unwrap_or_default());
+    }
+
+    fn source_label<'a>(&self, source: &'a str) -> &'a str {
+        let mut labels = self
+            .source_labels
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if labels.contains(source) {
+            return source;
+        }
+        if labels.len() < MAX_SOURCE_LABELS {
+            labels.insert(source.to_string());
+            return source;
+        }
+        OTHER_SOURCES_LABEL
+    }
+
+    pub fn record_cache_lookup(&self, kind: &str, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        self.cache_lookups_total
+            .with_label_values(&[kind, result])
+            .inc();
+    }
+
+    pub fn record_cache_invalidation(&self, origin: &str) {
+        self.cache_invalidations_total
+            .with_label_values(&[origin])
+            .inc();
+    }
+
+    /// Refresh the database gauges and render every metric in the Prometheus text format
+    pub async fn render<DB>(&self, pool: &Pool<DB>) -> Result<String, prometheus::Error>
+    where
+        DB: Database,
+        for<'c> &'c Pool<DB>: Executor<'c, Database = DB>,
+        for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+        for<'r> Option<i64>: Decode<'r, DB> + Type<DB>,
+        usize: ColumnIndex<DB::Row>,
+    {
+        let size = pool.size() as i64;
+        let idle = pool.num_idle() as i64;
+        self.db_pool_connections
+            .with_label_values(&["size"])
+            .set(size);
+        self.db_pool_connections
+            .with_label_values(&["idle"])
+            .set(idle);
+        self.db_pool_connections
+            .with_label_values(&["in_use"])
+            .set(size - idle);
+
+        let started = Instant::now();
+        match pool.acquire().await {
+            Ok(_) => self
+                .db_pool_acquire_seconds
+                .set(started.elapsed().as_secs_f64()),
+            Err(e) => tracing::warn!(error = %e, "⚠️ Failed to acquire connection for metrics"),
+        }
+
+        match sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT MAX(version) FROM _sqlx_migrations WHERE success",
+        )
+        .fetch_one(pool)
+        .await
+        {
+            Ok(version) => self.db_migration_version.set(version.unwrap_or(0)),
+            Err(e) => tracing::warn!(error = %e, "⚠️ Failed to read migration version"),
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        String::from_utf8(buffer).map_err(|e| prometheus::Error::Msg(e.to_string()))
+    }
+}
+
+/// Middleware recording request counts and latencies per route and status
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let started = Instant::now();
+    let method = req.method().to_string();
+
+    let response = next.call(req).await?;
+
+    let route = response
+        .request()
+        .match_pattern()
+        .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+    let status = response.status().as_u16().to_string();
+    let labels = [method.as_str(), route.as_str(), status.as_str()];
+
+    METRICS.http_requests_total.with_label_values(&labels).inc();
+    METRICS
+        .http_request_duration_seconds
+        .with_label_values(&labels)
+        .observe(started.elapsed().as_secs_f64());
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(source: &str, kind: TransactionKind, amount: i64) -> Transaction {
+        Transaction {
+            id: 1,
+            amount: Decimal::from(amount),
+            currency: "EUR".to_string(),
+            kind,
+            tags: Vec::new(),
+            source: source.to_string(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn counts_amounts_per_source_currency_and_kind() {
+        let metrics = Metrics::new();
+        metrics.record_saving_created(&transaction("bank", TransactionKind::Deposit, 10));
+        metrics.record_saving_created(&transaction("bank", TransactionKind::Deposit, 5));
+        metrics.record_saving_created(&transaction("bank", TransactionKind::Withdrawal, 3));
+
+        let amount = |kind: &str| {
+            metrics
+                .savings_amount_total
+                .with_label_values(&["bank", "EUR", kind])
+                .get()
+        };
+        assert_eq!(amount("deposit"), 15.0);
+        assert_eq!(amount("withdrawal"), 3.0);
+        assert_eq!(
+            metrics
+                .savings_created_total
+                .with_label_values(&["bank"])
+                .get(),
+            3
+        );
+    }
+
+    #[test]
+    fn bounds_the_source_labels() {
+        let metrics = Metrics::new();
+        for i in 0..MAX_SOURCE_LABELS + 5 {
+            let source = format!("source-{}", i);
+            metrics.record_saving_created(&transaction(&source, TransactionKind::Deposit, 1));
+        }
+        // Sources seen before the bound keep their label
+        metrics.record_saving_created(&transaction("source-0", TransactionKind::Deposit, 1));
+
+        let created = |source: &str| {
+            metrics
+                .savings_created_total
+                .with_label_values(&[source])
+                .get()
+        };
+        assert_eq!(created("source-0"), 2);
+        assert_eq!(created(OTHER_SOURCES_LABEL), 5);
+        assert_eq!(
+            prometheus::core::Collector::collect(&metrics.savings_created_total)[0]
+                .get_metric()
+                .len(),
+            MAX_SOURCE_LABELS + 1
+        );
+    }
+}
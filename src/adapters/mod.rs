@@ -1,2 +1,13 @@
+pub mod cache;
+pub mod change_feed;
 pub mod db;
 pub mod logger;
+pub mod metrics;
+pub mod negotiation;
+pub mod rate_limit;
+pub mod readiness;
+pub mod request_id;
+pub mod route_rule;
+pub mod sqlite;
+pub mod telemetry;
+pub mod versioning;
@@ -0,0 +1,460 @@
+use crate::adapters::metrics::UNMATCHED_ROUTE;
+use crate::adapters::route_rule::parse_route_rule;
+use crate::config::{Config, ConfigError, ConfigIssue};
+use crate::errors::AppError;
+use actix_web::{
+    FromRequest, HttpMessage, HttpRequest, HttpResponse, Responder, ResponseError,
+    body::BoxBody,
+    dev::{Decompress, Payload},
+    http::{
+        StatusCode,
+        header::{self, Accept, Header, HeaderValue},
+    },
+    mime,
+    web::{BytesMut, Data},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use tokio_stream::StreamExt;
+
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Media types accepted as MessagePack, none of them is registered
+const MSGPACK_SUBTYPES: &[&str] = &["msgpack", "x-msgpack", "vnd.msgpack"];
+
+/// Limit used when no `PayloadLimits` is registered
+const DEFAULT_PAYLOAD_LIMIT: usize = 16 * 1024;
+
+/// Largest accepted request body, in bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PayloadLimit(pub usize);
+
+impl FromStr for PayloadLimit {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().parse() {
+            Ok(0) | Err(_) => Err(format!("'{}' is not a size in bytes greater than 0", value)),
+            Ok(bytes) => Ok(PayloadLimit(bytes)),
+        }
+    }
+}
+
+/// Request body size limits, per route or the default one
+#[derive(Debug, Clone)]
+pub struct PayloadLimits {
+    default_limit: usize,
+    routes: HashMap<String, usize>,
+}
+
+impl PayloadLimits {
+    pub fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        let routes = config
+            .payload_limit_routes
+            .iter()
+            .map(|rule| {
+                let (route, PayloadLimit(bytes)) = parse_route_rule(rule)
+                    .map_err(|message| ConfigIssue::new("payload_limit_routes", message))?;
+                Ok((route, bytes))
+            })
+            .collect::<Result<_, ConfigError>>()?;
+
+        Ok(Self {
+            default_limit: config.payload_limit_bytes,
+            routes,
+        })
+    }
+
+    fn limit_for(&self, req: &HttpRequest) -> usize {
+        let route = format!(
+            "{} {}",
+            req.method(),
+            req.match_pattern()
+                .unwrap_or_else(|| UNMATCHED_ROUTE.to_string())
+        );
+        self.routes
+            .get(&route)
+            .copied()
+            .unwrap_or(self.default_limit)
+    }
+}
+
+/// Serialization of a request or response body
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BodyFormat {
+    Json,
+    Form,
+    MessagePack,
+}
+
+impl BodyFormat {
+    /// Format of the request body, from its `Content-Type`
+    fn of_request(req: &HttpRequest) -> Result<Self, AppError> {
+        let unsupported = || {
+            AppError::UnsupportedMediaType(
+                "Expected application/json, application/x-www-form-urlencoded or application/msgpack"
+                    .to_string(),
+            )
+        };
+
+        let mime = req.mime_type().ok().flatten().ok_or_else(unsupported)?;
+        match (
+            mime.type_().as_str(),
+            mime.subtype().as_str(),
+            mime.suffix(),
+        ) {
+            ("application", "json", _) | ("application", _, Some(mime::JSON)) => Ok(Self::Json),
+            ("application", "x-www-form-urlencoded", _) => Ok(Self::Form),
+            ("application", subtype, _) if MSGPACK_SUBTYPES.contains(&subtype) => {
+                Ok(Self::MessagePack)
+            }
+            _ => Err(unsupported()),
+        }
+    }
+
+    /// Format of the response, the preferred one of the `Accept` header.
+    /// JSON unless MessagePack is explicitly preferred.
+    pub fn of_response(req: &HttpRequest) -> Self {
+        let Ok(accept) = Accept::parse(req) else {
+            return Self::Json;
+        };
+
+        accept
+            .ranked()
+            .into_iter()
+            .find_map(
+                |mime| match (mime.type_().as_str(), mime.subtype().as_str()) {
+                    ("application", subtype) if MSGPACK_SUBTYPES.contains(&subtype) => {
+                        Some(Self::MessagePack)
+                    }
+                    ("application", "json") | ("application", "*") | ("*", "*") => Some(Self::Json),
+                    _ => None,
+                },
+            )
+            .unwrap_or(Self::Json)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T, AppError> {
+        match self {
+            Self::Json => {
+                serde_json::from_slice(body).map_err(|e| AppError::InvalidJson(e.to_string()))
+            }
+            Self::Form => serde_urlencoded::from_bytes(body)
+                .map_err(|e| AppError::InvalidBody(format!("Invalid form body: {}", e))),
+            Self::MessagePack => rmp_serde::from_slice(body)
+                .map_err(|e| AppError::InvalidBody(format!("Invalid MessagePack body: {}", e))),
+        }
+    }
+}
+
+/// Request body extractor decoding JSON, form or MessagePack bodies
+/// according to their `Content-Type`, within the route's `PayloadLimits`
+pub struct Body<T>(pub T);
+
+impl<T> Body<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Body<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Body<T> {
+    type Error = AppError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, AppError>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let limit = req
+            .app_data::<Data<PayloadLimits>>()
+            .map(|limits| limits.limit_for(req))
+            .unwrap_or(DEFAULT_PAYLOAD_LIMIT);
+        let format = BodyFormat::of_request(req);
+        let declared_length = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<usize>().ok());
+        let mut payload = Decompress::from_headers(payload.take(), req.headers());
+
+        Box::pin(async move {
+            let format = format?;
+            // Rejected before reading anything when the size is known upfront
+            if declared_length.is_some_and(|length| length > limit) {
+                return Err(AppError::PayloadTooLarge(limit));
+            }
+
+            let mut body = BytesMut::new();
+            while let Some(chunk) = payload.next().await {
+                let chunk =
+                    chunk.map_err(|e| AppError::BadRequest(format!("Payload error: {}", e)))?;
+                if body.len() + chunk.len() > limit {
+                    return Err(AppError::PayloadTooLarge(limit));
+                }
+                body.extend_from_slice(&chunk);
+            }
+
+            format.decode(&body).map(Body)
+        })
+    }
+}
+
+/// Responder serializing its value as JSON or MessagePack, following the
+/// request's `Accept` header
+pub struct Reply<T> {
+    status: StatusCode,
+    value: T,
+}
+
+impl<T: Serialize> Reply<T> {
+    pub fn ok(value: T) -> Self {
+        Self {
+            status: StatusCode::OK,
+            value,
+        }
+    }
+
+    pub fn created(value: T) -> Self {
+        Self {
+            status: StatusCode::CREATED,
+            value,
+        }
+    }
+}
+
+impl<T: Serialize> Responder for Reply<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        let mut response = match BodyFormat::of_response(req) {
+            BodyFormat::MessagePack => match rmp_serde::to_vec_named(&self.value) {
+                Ok(body) => HttpResponse::build(self.status)
+                    .content_type(MSGPACK_CONTENT_TYPE)
+                    .body(body),
+                Err(e) => {
+                    return AppError::InternalServerError(format!(
+                        "Failed to encode MessagePack response: {}",
+                        e
+                    ))
+                    .error_response();
+                }
+            },
+            _ => HttpResponse::build(self.status).json(&self.value),
+        };
+
+        response
+            .headers_mut()
+            .insert(header::VARY, HeaderValue::from_static("accept"));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Sample {
+        amount: i64,
+        source: String,
+    }
+
+    fn sample() -> Sample {
+        Sample {
+            amount: 12,
+            source: "bank".to_string(),
+        }
+    }
+
+    async fn extract(req: TestRequest) -> Result<Sample, AppError> {
+        let (req, mut payload) = req.to_http_parts();
+        Body::<Sample>::from_request(&req, &mut payload)
+            .await
+            .map(Body::into_inner)
+    }
+
+    fn with_body(content_type: &str, body: Vec<u8>) -> TestRequest {
+        TestRequest::post()
+            .insert_header((header::CONTENT_TYPE, content_type))
+            .set_payload(body)
+    }
+
+    fn response_format(accept: Option<&str>) -> BodyFormat {
+        let req = match accept {
+            Some(accept) => TestRequest::get().insert_header((header::ACCEPT, accept)),
+            None => TestRequest::get(),
+        };
+        BodyFormat::of_response(&req.to_http_request())
+    }
+
+    #[test]
+    fn parses_payload_limits() {
+        assert_eq!("1024".parse(), Ok(PayloadLimit(1024)));
+        assert_eq!(" 1 ".parse(), Ok(PayloadLimit(1)));
+        for invalid in ["0", "-1", "1kb", ""] {
+            assert!(invalid.parse::<PayloadLimit>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[actix_web::test]
+    async fn decodes_bodies_by_content_type() {
+        let json = serde_json::to_vec(&sample()).unwrap();
+        for content_type in [
+            "application/json",
+            "application/json; charset=utf-8",
+            "application/merge-patch+json",
+        ] {
+            let decoded = extract(with_body(content_type, json.clone())).await;
+            assert_eq!(decoded.unwrap(), sample(), "{}", content_type);
+        }
+
+        let form = b"amount=12&source=bank".to_vec();
+        let decoded = extract(with_body("application/x-www-form-urlencoded", form)).await;
+        assert_eq!(decoded.unwrap(), sample());
+
+        let msgpack = rmp_serde::to_vec_named(&sample()).unwrap();
+        for subtype in MSGPACK_SUBTYPES {
+            let content_type = format!("application/{}", subtype);
+            let decoded = extract(with_body(&content_type, msgpack.clone())).await;
+            assert_eq!(decoded.unwrap(), sample(), "{}", content_type);
+        }
+    }
+
+    #[actix_web::test]
+    async fn rejects_bodies_not_matching_their_content_type() {
+        for (content_type, body) in [
+            ("application/json", b"amount=12".to_vec()),
+            ("application/x-www-form-urlencoded", b"amount=many".to_vec()),
+            ("application/msgpack", b"{}".to_vec()),
+        ] {
+            let error = extract(with_body(content_type, body)).await.unwrap_err();
+            assert_eq!(
+                error.status_code(),
+                StatusCode::BAD_REQUEST,
+                "{}",
+                content_type
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn rejects_unsupported_media_types() {
+        let json = serde_json::to_vec(&sample()).unwrap();
+        for content_type in ["text/plain", "application/xml", "text/json", "json"] {
+            let error = extract(with_body(content_type, json.clone()))
+                .await
+                .unwrap_err();
+            assert_eq!(
+                error.status_code(),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "{}",
+                content_type
+            );
+        }
+
+        let error = extract(TestRequest::post().set_payload(json))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[actix_web::test]
+    async fn rejects_bodies_over_the_payload_limit() {
+        let json = serde_json::to_vec(&sample()).unwrap();
+        let limits = |bytes| {
+            Data::new(PayloadLimits {
+                default_limit: bytes,
+                routes: HashMap::new(),
+            })
+        };
+
+        let decoded =
+            extract(with_body("application/json", json.clone()).app_data(limits(json.len()))).await;
+        assert_eq!(decoded.unwrap(), sample());
+
+        let error =
+            extract(with_body("application/json", json.clone()).app_data(limits(json.len() - 1)))
+                .await
+                .unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Counted while reading when the declared length understates the body
+        let error = extract(
+            with_body("application/json", json.clone())
+                .insert_header((header::CONTENT_LENGTH, "1"))
+                .app_data(limits(json.len() - 1)),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Without registered limits the default one applies
+        let error = extract(with_body(
+            "application/json",
+            vec![b' '; DEFAULT_PAYLOAD_LIMIT + 1],
+        ))
+        .await
+        .unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn negotiates_the_response_format_from_accept() {
+        for (accept, format) in [
+            (None, BodyFormat::Json),
+            (Some("application/json"), BodyFormat::Json),
+            (Some("*/*"), BodyFormat::Json),
+            (Some("text/html"), BodyFormat::Json),
+            (Some("application/msgpack"), BodyFormat::MessagePack),
+            (Some("application/x-msgpack"), BodyFormat::MessagePack),
+            (Some("application/vnd.msgpack"), BodyFormat::MessagePack),
+            (
+                Some("application/json, application/msgpack;q=0.5"),
+                BodyFormat::Json,
+            ),
+            (
+                Some("application/msgpack, application/json;q=0.5"),
+                BodyFormat::MessagePack,
+            ),
+            (
+                Some("text/html, application/msgpack;q=0.1"),
+                BodyFormat::MessagePack,
+            ),
+        ] {
+            assert_eq!(response_format(accept), format, "{:?}", accept);
+        }
+    }
+
+    #[test]
+    fn replies_in_the_negotiated_format() {
+        let req = TestRequest::get()
+            .insert_header((header::ACCEPT, MSGPACK_CONTENT_TYPE))
+            .to_http_request();
+        let response = Reply::created(sample()).respond_to(&req);
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            MSGPACK_CONTENT_TYPE
+        );
+        assert_eq!(response.headers().get(header::VARY).unwrap(), "accept");
+
+        let req = TestRequest::get().to_http_request();
+        let response = Reply::ok(sample()).respond_to(&req);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        assert_eq!(response.headers().get(header::VARY).unwrap(), "accept");
+    }
+}
@@ -0,0 +1,489 @@
+use crate::adapters::metrics::UNMATCHED_ROUTE;
+use crate::adapters::route_rule::parse_route_rule;
+use crate::auth;
+use crate::config::{Config, ConfigError, ConfigIssue, RateLimitBackend};
+use crate::errors::AppError;
+use actix_web::{
+    Error, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{self, HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    web::Data,
+};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const LIMIT_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+const REMAINING_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+const RESET_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+
+/// How often buckets left idle long enough to be full again are dropped
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Token bucket size and the time it takes to refill it completely
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limit {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl Limit {
+    fn refill_per_sec(&self) -> f64 {
+        self.capacity as f64 / self.period.as_secs_f64()
+    }
+
+    /// Tokens in a bucket left with `tokens`, `elapsed` ago
+    fn refilled(&self, tokens: f64, elapsed: Duration) -> f64 {
+        (tokens + elapsed.as_secs_f64() * self.refill_per_sec()).min(self.capacity as f64)
+    }
+}
+
+/// Parse `CAPACITY/SECONDS`, e.g. `30/60` for 30 requests per minute
+impl FromStr for Limit {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (capacity, seconds) = value
+            .split_once('/')
+            .ok_or_else(|| format!("'{}' is not CAPACITY/SECONDS", value))?;
+        let capacity: u32 = capacity
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' has an invalid capacity", value))?;
+        let seconds: u64 = seconds
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' has an invalid period", value))?;
+
+        if capacity == 0 || seconds == 0 {
+            return Err(format!(
+                "'{}' must have a capacity and a period greater than 0",
+                value
+            ));
+        }
+
+        Ok(Limit {
+            capacity,
+            period: Duration::from_secs(seconds),
+        })
+    }
+}
+
+/// State of a client's bucket after a request, reported in the
+/// `X-RateLimit-*` headers
+#[derive(Debug, Clone)]
+pub struct RateLimitStatus {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the bucket is full again
+    pub reset_after: Duration,
+    /// Set when the request was rejected, time until a token is available
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimitStatus {
+    fn new(limit: &Limit, tokens: f64, allowed: bool) -> Self {
+        let rate = limit.refill_per_sec();
+        let tokens = tokens.max(0.0);
+
+        Self {
+            limit: limit.capacity,
+            remaining: tokens.floor() as u32,
+            reset_after: Duration::from_secs_f64((limit.capacity as f64 - tokens).max(0.0) / rate),
+            retry_after: (!allowed).then(|| Duration::from_secs_f64((1.0 - tokens) / rate)),
+        }
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        self.retry_after.is_none()
+    }
+
+    /// Add the `X-RateLimit-*` headers, and `Retry-After` for rejections.
+    /// Durations are rounded up to whole seconds.
+    pub fn apply_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(LIMIT_HEADER, HeaderValue::from(self.limit));
+        headers.insert(REMAINING_HEADER, HeaderValue::from(self.remaining));
+        headers.insert(
+            RESET_HEADER,
+            HeaderValue::from(whole_seconds(self.reset_after)),
+        );
+        if let Some(retry_after) = self.retry_after {
+            headers.insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(whole_seconds(retry_after)),
+            );
+        }
+    }
+}
+
+pub fn whole_seconds(duration: Duration) -> u64 {
+    duration.as_secs_f64().ceil() as u64
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+    limit: Limit,
+}
+
+enum Store {
+    /// Buckets local to this process, each replica counts on its own
+    Memory(Mutex<HashMap<String, Bucket>>),
+    /// Buckets shared by every replica through the primary database
+    Postgres(PgPool),
+}
+
+/// Token bucket limits per client and route. Clients are identified by their
+/// API key when it is a known one, by their IP address otherwise.
+pub struct RateLimiter {
+    default_limit: Limit,
+    routes: HashMap<String, Limit>,
+    exempt_paths: Vec<String>,
+    trust_forwarded_for: bool,
+    api_keys: Vec<String>,
+    store: Store,
+    /// Unix time of the last idle bucket cleanup, in seconds
+    last_cleanup: AtomicU64,
+}
+
+impl RateLimiter {
+    /// Limiter described by the configuration, `None` when rate limiting is
+    /// disabled. Buckets stay in memory without a Postgres `pool`.
+    pub fn from_config(
+        config: &Config,
+        pool: Option<&PgPool>,
+    ) -> Result<Option<Self>, ConfigError> {
+        if !config.rate_limit_enabled {
+            return Ok(None);
+        }
+
+        let default_limit = config
+            .rate_limit_default
+            .parse()
+            .map_err(|message| ConfigIssue::new("rate_limit_default", message))?;
+        let routes = config
+            .rate_limit_routes
+            .iter()
+            .map(|rule| {
+                parse_route_rule(rule)
+                    .map_err(|message| ConfigIssue::new("rate_limit_routes", message))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let store = match (&config.rate_limit_backend, pool) {
+            (RateLimitBackend::Postgres, Some(pool)) => Store::Postgres(pool.clone()),
+            (RateLimitBackend::Postgres, None) => {
+                tracing::warn!("⚠️ Rate limit buckets need Postgres, keeping them in memory");
+                Store::Memory(Mutex::new(HashMap::new()))
+            }
+            (RateLimitBackend::Memory, _) => Store::Memory(Mutex::new(HashMap::new())),
+        };
+
+        Ok(Some(Self {
+            default_limit,
+            routes,
+            exempt_paths: config.rate_limit_exempt_paths.clone(),
+            trust_forwarded_for: config.rate_limit_trust_forwarded_for,
+            api_keys: config.api_keys.clone(),
+            store,
+            last_cleanup: AtomicU64::new(unix_seconds()),
+        }))
+    }
+
+    /// Take a token from the bucket of the request's client and route.
+    /// Exempt paths and backend failures let the request through unlimited.
+    pub async fn check(&self, req: &ServiceRequest) -> Option<RateLimitStatus> {
+        if self.exempt_paths.iter().any(|path| path == req.path()) {
+            return None;
+        }
+
+        let route = format!(
+            "{} {}",
+            req.method(),
+            req.match_pattern()
+                .unwrap_or_else(|| UNMATCHED_ROUTE.to_string())
+        );
+        let client = {
+            let info = req.connection_info();
+            let addr = if self.trust_forwarded_for {
+                info.realip_remote_addr()
+            } else {
+                info.peer_addr()
+            };
+            self.client(auth::api_key_from_request(req.request()).as_deref(), addr)
+        };
+
+        self.take(&client, &route).await
+    }
+
+    /// Take a token from the bucket of `client` for `route`, written
+    /// `METHOD /pattern` like the configured routes. Backend failures let
+    /// the request through unlimited.
+    pub async fn take(&self, client: &str, route: &str) -> Option<RateLimitStatus> {
+        let limit = self
+            .routes
+            .get(route)
+            .copied()
+            .unwrap_or(self.default_limit);
+        let key = format!("{}|{}", client, route);
+
+        self.cleanup_if_due();
+
+        let (tokens, allowed) = match &self.store {
+            Store::Memory(buckets) => take_memory(buckets, key, limit),
+            Store::Postgres(pool) => match take_postgres(pool, &key, &limit).await {
+                Ok(taken) => taken,
+                Err(e) => {
+                    tracing::warn!(error = %e, "⚠️ Rate limit backend unavailable, request not limited");
+                    return None;
+                }
+            },
+        };
+
+        let status = RateLimitStatus::new(&limit, tokens, allowed);
+        if !status.is_allowed() {
+            tracing::warn!(%route, "🚦 Rate limit exceeded");
+        }
+        Some(status)
+    }
+
+    /// Bucket owner of a request carrying `api_key`, from `addr`: the key
+    /// when it is a known one, the address otherwise
+    pub fn client(&self, api_key: Option<&str>, addr: Option<&str>) -> String {
+        if let Some(key) = api_key
+            && let Some(index) = auth::api_key_index(&self.api_keys, key)
+        {
+            // Keys themselves are credentials, they are never stored
+            return format!("key:{}", index);
+        }
+
+        format!("ip:{}", addr.unwrap_or("unknown"))
+    }
+
+    /// Drop the buckets idle for longer than any period, they are full again
+    /// and behave exactly like missing ones
+    fn cleanup_if_due(&self) {
+        let now = unix_seconds();
+        let last = self.last_cleanup.load(Ordering::Relaxed);
+        if now.saturating_sub(last) < CLEANUP_INTERVAL.as_secs()
+            || self
+                .last_cleanup
+                .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+
+        match &self.store {
+            Store::Memory(buckets) => {
+                let now = Instant::now();
+                buckets
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .retain(|_, bucket| {
+                        now.duration_since(bucket.updated_at) < bucket.limit.period
+                    });
+            }
+            Store::Postgres(pool) => {
+                let pool = pool.clone();
+                let idle = self
+                    .routes
+                    .values()
+                    .map(|limit| limit.period)
+                    .fold(self.default_limit.period, Duration::max);
+                tokio::spawn(async move {
+                    let result = sqlx::query(
+                        "DELETE FROM rate_limit_buckets WHERE updated_at < NOW() - make_interval(secs => $1)",
+                    )
+                    .bind(idle.as_secs_f64())
+                    .execute(&pool)
+                    .await;
+                    if let Err(e) = result {
+                        tracing::warn!(error = %e, "⚠️ Failed to remove idle rate limit buckets");
+                    }
+                });
+            }
+        }
+    }
+}
+
+fn take_memory(buckets: &Mutex<HashMap<String, Bucket>>, key: String, limit: Limit) -> (f64, bool) {
+    let now = Instant::now();
+    let mut buckets = buckets.lock().unwrap_or_else(PoisonError::into_inner);
+    let bucket = buckets.entry(key).or_insert(Bucket {
+        tokens: limit.capacity as f64,
+        updated_at: now,
+        limit,
+    });
+
+    let tokens = limit.refilled(bucket.tokens, now.duration_since(bucket.updated_at));
+    let allowed = tokens >= 1.0;
+    bucket.tokens = if allowed { tokens - 1.0 } else { tokens };
+    bucket.updated_at = now;
+    bucket.limit = limit;
+
+    (bucket.tokens, allowed)
+}
+
+/// Refill and take a token in a single statement, so that concurrent
+/// requests on any replica never take the same token twice
+async fn take_postgres(
+    pool: &PgPool,
+    key: &str,
+    limit: &Limit,
+) -> Result<(f64, bool), sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        INSERT INTO rate_limit_buckets AS bucket (key, tokens, allowed, updated_at)
+        VALUES ($1, $2 - 1, TRUE, NOW())
+        ON CONFLICT (key) DO UPDATE SET
+            tokens = LEAST($2, bucket.tokens + EXTRACT(EPOCH FROM NOW() - bucket.updated_at)::FLOAT8 * $3)
+                - CASE WHEN LEAST($2, bucket.tokens + EXTRACT(EPOCH FROM NOW() - bucket.updated_at)::FLOAT8 * $3) >= 1
+                    THEN 1 ELSE 0 END,
+            allowed = LEAST($2, bucket.tokens + EXTRACT(EPOCH FROM NOW() - bucket.updated_at)::FLOAT8 * $3) >= 1,
+            updated_at = NOW()
+        RETURNING tokens, allowed
+        "#,
+    )
+    .bind(key)
+    .bind(limit.capacity as f64)
+    .bind(limit.refill_per_sec())
+    .fetch_one(pool)
+    .await
+}
+
+fn unix_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Middleware applying the configured `RateLimiter`, rejected requests get a
+/// 429 and every limited response carries the `X-RateLimit-*` headers
+pub async fn enforce_rate_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let status = match req.app_data::<Data<RateLimiter>>().cloned() {
+        Some(limiter) => limiter.check(&req).await,
+        None => None,
+    };
+
+    // Answered here rather than returned as an error, so that the outer
+    // middleware still see the response
+    if let Some(status) = &status
+        && !status.is_allowed()
+    {
+        let response = AppError::TooManyRequests(status.clone()).error_response();
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    let mut response = next.call(req).await?;
+    if let Some(status) = status {
+        status.apply_headers(response.headers_mut());
+    }
+
+    Ok(response.map_into_left_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(capacity: u32, seconds: u64) -> Limit {
+        Limit {
+            capacity,
+            period: Duration::from_secs(seconds),
+        }
+    }
+
+    #[test]
+    fn parses_limits() {
+        assert_eq!("30/60".parse(), Ok(limit(30, 60)));
+        assert_eq!(" 5 / 1 ".parse(), Ok(limit(5, 1)));
+        for invalid in ["30", "x/60", "30/x", "0/60", "30/0", "-1/60"] {
+            assert!(invalid.parse::<Limit>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn refills_in_proportion_to_elapsed_time() {
+        let limit = limit(10, 20);
+
+        assert_eq!(limit.refilled(0.0, Duration::ZERO), 0.0);
+        assert_eq!(limit.refilled(0.0, Duration::from_secs(5)), 2.5);
+        assert_eq!(limit.refilled(1.5, Duration::from_millis(1000)), 2.0);
+        assert_eq!(limit.refilled(4.0, Duration::from_secs(20)), 10.0);
+        assert_eq!(limit.refilled(9.0, Duration::from_secs(3600)), 10.0);
+    }
+
+    #[test]
+    fn takes_tokens_until_the_bucket_is_empty_then_refills_it() {
+        let buckets = Mutex::new(HashMap::new());
+        let limit = limit(2, 2);
+
+        assert!(take_memory(&buckets, "a".to_string(), limit).1);
+        assert!(take_memory(&buckets, "a".to_string(), limit).1);
+        let (tokens, allowed) = take_memory(&buckets, "a".to_string(), limit);
+        assert!(!allowed);
+        assert!(tokens < 1.0);
+
+        // Other clients and routes have buckets of their own
+        assert!(take_memory(&buckets, "b".to_string(), limit).1);
+
+        // A token per second, as if the last request was a second ago
+        buckets.lock().unwrap().get_mut("a").unwrap().updated_at -= Duration::from_secs(1);
+        let (tokens, allowed) = take_memory(&buckets, "a".to_string(), limit);
+        assert!(allowed);
+        assert!(tokens < 1.0);
+        assert!(!take_memory(&buckets, "a".to_string(), limit).1);
+    }
+
+    #[test]
+    fn reports_when_the_next_token_and_a_full_bucket_are_due() {
+        // A token every 30 seconds
+        let limit = limit(2, 60);
+
+        let status = RateLimitStatus::new(&limit, 1.0, true);
+        assert!(status.is_allowed());
+        assert_eq!(status.remaining, 1);
+        assert_eq!(status.reset_after, Duration::from_secs(30));
+        assert_eq!(status.retry_after, None);
+
+        let status = RateLimitStatus::new(&limit, 0.5, false);
+        assert!(!status.is_allowed());
+        assert_eq!(status.remaining, 0);
+        assert_eq!(status.reset_after, Duration::from_secs(45));
+        assert_eq!(status.retry_after, Some(Duration::from_secs(15)));
+
+        // Postgres buckets may dip below zero under contention
+        let status = RateLimitStatus::new(&limit, -0.5, false);
+        assert_eq!(status.remaining, 0);
+        assert_eq!(status.retry_after, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn rounds_header_durations_up_to_whole_seconds() {
+        // A token every 3.33 seconds
+        let status = RateLimitStatus::new(&limit(3, 10), 0.0, false);
+        let mut headers = HeaderMap::new();
+        status.apply_headers(&mut headers);
+
+        assert_eq!(headers.get(&LIMIT_HEADER).unwrap(), "3");
+        assert_eq!(headers.get(&REMAINING_HEADER).unwrap(), "0");
+        assert_eq!(headers.get(&RESET_HEADER).unwrap(), "10");
+        assert_eq!(headers.get(header::RETRY_AFTER).unwrap(), "4");
+
+        let status = RateLimitStatus::new(&limit(3, 10), 3.0, true);
+        let mut headers = HeaderMap::new();
+        status.apply_headers(&mut headers);
+        assert_eq!(headers.get(&RESET_HEADER).unwrap(), "0");
+        assert!(!headers.contains_key(header::RETRY_AFTER));
+    }
+}
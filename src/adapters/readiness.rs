@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// A worker is considered stalled after missing this many beats in a row
+const MISSED_BEATS_TOLERATED: u32 = 3;
+
+struct Heartbeat {
+    interval: Duration,
+    last_beat: Instant,
+    beaten: bool,
+}
+
+/// Liveness of a registered background worker
+pub struct WorkerHeartbeat {
+    pub name: &'static str,
+    pub healthy: bool,
+    pub since_last_beat: Option<Duration>,
+}
+
+#[derive(Default)]
+struct ReadinessInner {
+    shutting_down: AtomicBool,
+    heartbeats: RwLock<HashMap<&'static str, Heartbeat>>,
+}
+
+/// Process-wide readiness state: background worker heartbeats and whether
+/// a graceful shutdown has started
+#[derive(Clone, Default)]
+pub struct Readiness {
+    inner: Arc<ReadinessInner>,
+}
+
+impl Readiness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track a background worker expected to beat at least once per `interval`
+    pub fn register_worker(&self, name: &'static str, interval: Duration) {
+        let mut heartbeats = self.inner.heartbeats.write().expect("heartbeats lock");
+        heartbeats.insert(
+            name,
+            Heartbeat {
+                interval,
+                last_beat: Instant::now(),
+                beaten: false,
+            },
+        );
+    }
+
+    pub fn beat(&self, name: &'static str) {
+        let mut heartbeats = self.inner.heartbeats.write().expect("heartbeats lock");
+        if let Some(heartbeat) = heartbeats.get_mut(name) {
+            heartbeat.last_beat = Instant::now();
+            heartbeat.beaten = true;
+        }
+    }
+
+    pub fn workers(&self) -> Vec<WorkerHeartbeat> {
+        let heartbeats = self.inner.heartbeats.read().expect("heartbeats lock");
+        let mut workers: Vec<WorkerHeartbeat> = heartbeats
+            .iter()
+            .map(|(name, heartbeat)| {
+                let elapsed = heartbeat.last_beat.elapsed();
+                WorkerHeartbeat {
+                    name,
+                    healthy: elapsed <= heartbeat.interval * MISSED_BEATS_TOLERATED,
+                    since_last_beat: heartbeat.beaten.then_some(elapsed),
+                }
+            })
+            .collect();
+        workers.sort_by_key(|worker| worker.name);
+        workers
+    }
+
+    /// Report the service as not ready from now on
+    pub fn mark_shutting_down(&self) {
+        self.inner.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.inner.shutting_down.load(Ordering::SeqCst)
+    }
+}
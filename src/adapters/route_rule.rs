@@ -0,0 +1,26 @@
+use actix_web::http::Method;
+use std::str::FromStr;
+
+/// Parse a per-route setting written `METHOD /pattern=VALUE`, returning the
+/// route as `METHOD /pattern`. The pattern is the full route pattern, as
+/// matched by actix, e.g. `POST /api/savings/{saving_id}`.
+pub fn parse_route_rule<T>(rule: &str) -> Result<(String, T), String>
+where
+    T: FromStr<Err = String>,
+{
+    let (route, value) = rule
+        .rsplit_once('=')
+        .ok_or_else(|| format!("'{}' is not METHOD /pattern=VALUE", rule))?;
+    let (method, pattern) = route
+        .trim()
+        .split_once(' ')
+        .ok_or_else(|| format!("'{}' is missing the HTTP method", rule))?;
+    let method = Method::from_str(&method.to_uppercase())
+        .map_err(|_| format!("'{}' has an invalid HTTP method", rule))?;
+    let pattern = pattern.trim();
+    if !pattern.starts_with('/') {
+        return Err(format!("'{}' has a pattern not starting with /", rule));
+    }
+
+    Ok((format!("{} {}", method, pattern), value.trim().parse()?))
+}
@@ -0,0 +1,132 @@
+use crate::adapters::db::DatabaseConfig;
+use crate::models::migrations::AppliedMigration;
+use sqlx::{
+    Error, SqlitePool,
+    migrate::{MigrateError, Migrator},
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Migrations of the SQLite schema embedded in the binary. They mirror the
+/// Postgres ones, version for version.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+/// Whether `url` selects SQLite rather than Postgres, e.g. `sqlite://savings.db`
+/// or `sqlite::memory:`
+pub fn is_sqlite_url(url: &str) -> bool {
+    url.starts_with("sqlite:")
+}
+
+/// Every connection to an in-memory database opens a database of its own
+fn is_in_memory(url: &str) -> bool {
+    url.contains(":memory:") || url.contains("mode=memory")
+}
+
+/// Open the database, creating the file when missing. An in-memory database
+/// lives as long as the pool, on a single connection that is never recycled.
+pub async fn init_pool(config: &DatabaseConfig) -> Result<SqlitePool, Error> {
+    tracing::info!("🔌 Initializing SQLite database...");
+
+    let connect_options = SqliteConnectOptions::from_str(&config.url)?
+        .create_if_missing(true)
+        .foreign_keys(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(Duration::from_millis(config.lock_timeout_ms));
+
+    let pool_options = if is_in_memory(&config.url) {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+    } else {
+        SqlitePoolOptions::new().max_connections(config.max_connections)
+    };
+    let pool = pool_options
+        .acquire_timeout(Duration::from_secs(config.connect_timeout))
+        .connect_with(connect_options)
+        .await?;
+
+    tracing::info!("✅ SQLite database initialized successfully");
+    Ok(pool)
+}
+
+/// Run database migrations. Refuses to touch a schema migrated by a newer build.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<(), MigrateError> {
+    tracing::info!("🔄 Running database migrations...");
+    check_schema_compatibility(pool).await?;
+    MIGRATOR.run(pool).await?;
+
+    tracing::info!("✅ Database migrations completed successfully");
+    Ok(())
+}
+
+/// Revert every applied migration newer than `target`
+pub async fn revert_migrations(pool: &SqlitePool, target: i64) -> Result<(), MigrateError> {
+    tracing::info!(target, "🔄 Reverting database migrations...");
+    MIGRATOR.undo(pool, target).await?;
+
+    tracing::info!("✅ Database migrations reverted successfully");
+    Ok(())
+}
+
+/// Fail when the database has migrations applied that this binary does not
+/// know about, i.e. the schema was migrated by a newer build
+pub async fn check_schema_compatibility(pool: &SqlitePool) -> Result<(), MigrateError> {
+    for applied in applied_migrations(pool).await? {
+        if !MIGRATOR
+            .iter()
+            .any(|migration| migration.version == applied.version)
+        {
+            return Err(MigrateError::VersionMissing(applied.version));
+        }
+    }
+    Ok(())
+}
+
+/// Migrations recorded in `_sqlx_migrations`, empty when none ever ran
+pub async fn applied_migrations(pool: &SqlitePool) -> Result<Vec<AppliedMigration>, Error> {
+    let migrated: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(pool)
+    .await?;
+    if !migrated {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query_as::<_, AppliedMigration>(
+        r#"
+        SELECT version, description, installed_on, success,
+               execution_time / 1000000 AS execution_time_ms
+        FROM _sqlx_migrations
+        ORDER BY version
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Versions of the embedded migrations not yet applied to the database
+pub async fn pending_migrations(pool: &SqlitePool) -> Result<Vec<i64>, Error> {
+    let applied: Vec<i64> = applied_migrations(pool)
+        .await?
+        .into_iter()
+        .filter(|migration| migration.success)
+        .map(|migration| migration.version)
+        .collect();
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| migration.version)
+        .filter(|version| !applied.contains(version))
+        .collect())
+}
+
+/// Health check for database connection
+pub async fn health_check(pool: &SqlitePool) -> Result<(), Error> {
+    sqlx::query("SELECT 1").fetch_one(pool).await?;
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use crate::config::{Config, OtelExporter};
+use opentelemetry::global;
+use opentelemetry_otlp::{ExporterBuildError, WithExportConfig};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+
+/// Build the tracer provider for the configured exporter and register the W3C
+/// `traceparent` propagator. With the `none` exporter spans are still recorded,
+/// so log lines keep carrying trace ids without anything being exported.
+pub fn init_tracer_provider(config: &Config) -> Result<SdkTracerProvider, ExporterBuildError> {
+    let resource = Resource::builder()
+        .with_service_name(config.name.clone())
+        .with_attribute(opentelemetry::KeyValue::new(
+            "deployment.environment",
+            config.environment.to_string(),
+        ))
+        .build();
+
+    let builder = SdkTracerProvider::builder().with_resource(resource);
+    let provider = match config.otel_exporter {
+        OtelExporter::None => builder.build(),
+        OtelExporter::Stdout => builder
+            .with_simple_exporter(opentelemetry_stdout::SpanExporter::default())
+            .build(),
+        OtelExporter::Otlp => {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .with_endpoint(config.otel_endpoint.clone())
+                .build()?;
+            builder.with_batch_exporter(exporter).build()
+        }
+    };
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+
+    Ok(provider)
+}
@@ -0,0 +1,324 @@
+use crate::config::{Config, ConfigError, ConfigIssue};
+use crate::errors::AppError;
+use actix_web::{
+    Error, FromRequest, HttpRequest, ResponseError,
+    body::{EitherBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::{
+        Uri,
+        header::{self, Accept, Header, HeaderMap, HeaderName, HeaderValue},
+        uri::PathAndQuery,
+    },
+    middleware::Next,
+    web::Data,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::{Ready, ready};
+use std::str::FromStr;
+
+const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+
+/// Media type parameter selecting the version of unversioned paths, e.g.
+/// `Accept: application/json; version=2`
+const VERSION_PARAM: &str = "version";
+
+/// Versions of the REST API, each one served under `{url_prefix}/v{n}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    pub const ALL: [ApiVersion; 2] = [ApiVersion::V1, ApiVersion::V2];
+    pub const LATEST: ApiVersion = ApiVersion::V2;
+
+    /// Path segment of the version, also its name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V2 => "v2",
+        }
+    }
+
+    fn from_path_segment(segment: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|version| version.as_str() == segment)
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parse `v2` or `2`
+impl FromStr for ApiVersion {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_lowercase();
+        let segment = if value.starts_with('v') {
+            value
+        } else {
+            format!("v{}", value)
+        };
+
+        Self::from_path_segment(&segment).ok_or_else(|| {
+            let supported: Vec<&str> = Self::ALL.iter().map(ApiVersion::as_str).collect();
+            format!(
+                "'{}' is not a supported API version, expected one of {}",
+                segment,
+                supported.join(", ")
+            )
+        })
+    }
+}
+
+/// Version of the scope serving the request, registered as its app data
+impl FromRequest for ApiVersion {
+    type Error = AppError;
+    type Future = Ready<Result<Self, AppError>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(req.app_data::<ApiVersion>().copied().ok_or_else(|| {
+            AppError::InternalServerError(format!("No API version for {}", req.path()))
+        }))
+    }
+}
+
+/// A version still served but due for removal, written
+/// `VERSION=DEPRECATED_ON[/SUNSET_ON]` with dates as YYYY-MM-DD
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApiDeprecation {
+    pub version: ApiVersion,
+    pub deprecated_on: NaiveDate,
+    pub sunset_on: Option<NaiveDate>,
+}
+
+impl FromStr for ApiDeprecation {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{}' is not VERSION=DEPRECATED_ON[/SUNSET_ON]", value);
+        let parse_date = |date: &str| {
+            NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                .map_err(|_| format!("'{}' has a date not written YYYY-MM-DD", value))
+        };
+
+        let (version, dates) = value.split_once('=').ok_or_else(invalid)?;
+        let version = version.parse()?;
+        let (deprecated_on, sunset_on) = match dates.split_once('/') {
+            Some((deprecated_on, sunset_on)) => {
+                (parse_date(deprecated_on)?, Some(parse_date(sunset_on)?))
+            }
+            None => (parse_date(dates)?, None),
+        };
+        if sunset_on.is_some_and(|sunset_on| sunset_on < deprecated_on) {
+            return Err(format!("'{}' has a sunset before its deprecation", value));
+        }
+
+        Ok(Self {
+            version,
+            deprecated_on,
+            sunset_on,
+        })
+    }
+}
+
+impl ApiDeprecation {
+    /// `Deprecation` (RFC 9745) and `Sunset` (RFC 8594) headers, plus a link
+    /// to the same resource in the latest version when it exists
+    fn apply_headers(&self, headers: &mut HeaderMap, successor: Option<&str>) {
+        let deprecated_at = self.deprecated_on.and_time(Default::default()).and_utc();
+        if let Ok(value) = HeaderValue::from_str(&format!("@{}", deprecated_at.timestamp())) {
+            headers.insert(DEPRECATION_HEADER, value);
+        }
+
+        if let Some(sunset_on) = self.sunset_on {
+            let sunset_at = sunset_on.and_time(Default::default()).and_utc();
+            let http_date = sunset_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+            if let Ok(value) = HeaderValue::from_str(&http_date) {
+                headers.insert(SUNSET_HEADER, value);
+            }
+        }
+
+        if let Some(successor) = successor
+            && let Ok(value) =
+                HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor))
+        {
+            headers.append(header::LINK, value);
+        }
+    }
+}
+
+/// How requests under `url_prefix` are mapped to a version
+#[derive(Debug, Clone)]
+pub struct ApiVersioning {
+    prefix: String,
+    default_version: ApiVersion,
+    deprecations: HashMap<ApiVersion, ApiDeprecation>,
+}
+
+impl ApiVersioning {
+    pub fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        let deprecations = config
+            .api_deprecations
+            .iter()
+            .map(|rule| {
+                let deprecation: ApiDeprecation = rule
+                    .parse()
+                    .map_err(|message| ConfigIssue::new("api_deprecations", message))?;
+                Ok((deprecation.version, deprecation))
+            })
+            .collect::<Result<_, ConfigError>>()?;
+
+        Ok(Self {
+            prefix: config.url_prefix.trim_end_matches('/').to_string(),
+            default_version: config.api_default_version,
+            deprecations,
+        })
+    }
+
+    pub fn is_deprecated(&self, version: ApiVersion) -> bool {
+        self.deprecations.contains_key(&version)
+    }
+
+    /// Version of a request, from its path or for unversioned paths from its
+    /// `Accept` header, rewriting the path to the versioned one. `None` for
+    /// requests outside of the versioned scopes.
+    fn negotiate(&self, req: &mut ServiceRequest) -> Result<Option<Negotiated>, AppError> {
+        let Some(rest) = req
+            .path()
+            .strip_prefix(&self.prefix)
+            .filter(|rest| rest.starts_with('/'))
+            .map(str::to_string)
+        else {
+            return Ok(None);
+        };
+
+        let segment = rest[1..].split('/').next().unwrap_or_default();
+        if let Some(version) = ApiVersion::from_path_segment(segment) {
+            return Ok(Some(Negotiated {
+                version,
+                from_accept: false,
+            }));
+        }
+
+        // Unsupported versions are only rejected on versioned routes
+        let requested = requested_version(req);
+        let version = requested
+            .clone()
+            .ok()
+            .flatten()
+            .unwrap_or(self.default_version);
+        let versioned = self.versioned_path(version, &rest);
+        if !req.resource_map().has_resource(&versioned) {
+            return Ok(None);
+        }
+        requested.map_err(AppError::UnsupportedApiVersion)?;
+
+        rewrite_path(req, &versioned);
+        Ok(Some(Negotiated {
+            version,
+            from_accept: true,
+        }))
+    }
+
+    fn versioned_path(&self, version: ApiVersion, rest: &str) -> String {
+        format!("{}/{}{}", self.prefix, version, rest)
+    }
+
+    /// Same resource in the latest version, `rest` being the path after the
+    /// version segment
+    fn successor(&self, req: &ServiceRequest, version: ApiVersion) -> Option<String> {
+        let rest = req
+            .path()
+            .strip_prefix(&self.prefix)?
+            .strip_prefix('/')?
+            .strip_prefix(version.as_str())?;
+        let successor = self.versioned_path(ApiVersion::LATEST, rest);
+        req.resource_map()
+            .has_resource(&successor)
+            .then_some(successor)
+    }
+}
+
+struct Negotiated {
+    version: ApiVersion,
+    from_accept: bool,
+}
+
+/// Version asked for in the `Accept` header, through its `version` parameter
+fn requested_version(req: &ServiceRequest) -> Result<Option<ApiVersion>, String> {
+    let Ok(accept) = Accept::parse(req) else {
+        return Ok(None);
+    };
+
+    accept
+        .ranked()
+        .iter()
+        .find_map(|mime| mime.get_param(VERSION_PARAM))
+        .map(|version| version.as_str().parse())
+        .transpose()
+}
+
+fn rewrite_path(req: &mut ServiceRequest, path: &str) {
+    let path_and_query = match req.query_string() {
+        "" => path.to_string(),
+        query => format!("{}?{}", path, query),
+    };
+    let Ok(path_and_query) = PathAndQuery::from_str(&path_and_query) else {
+        return;
+    };
+
+    let mut parts = req.head().uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query);
+    if let Ok(uri) = Uri::from_parts(parts) {
+        req.match_info_mut().get_mut().update(&uri);
+        req.head_mut().uri = uri;
+    }
+}
+
+/// Route unversioned requests to the negotiated version and flag responses
+/// of deprecated versions
+pub async fn negotiate_version(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let Some(versioning) = req.app_data::<Data<ApiVersioning>>().cloned() else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+
+    let negotiated = match versioning.negotiate(&mut req) {
+        Ok(Some(negotiated)) => negotiated,
+        Ok(None) => return Ok(next.call(req).await?.map_into_left_body()),
+        // Answered here rather than returned as an error, so that the outer
+        // middleware still see the response
+        Err(e) => {
+            let response = e.error_response();
+            return Ok(req.into_response(response).map_into_right_body());
+        }
+    };
+
+    let deprecation = versioning.deprecations.get(&negotiated.version).copied();
+    let successor = deprecation.and_then(|_| versioning.successor(&req, negotiated.version));
+
+    let mut response = next.call(req).await?;
+    let headers = response.headers_mut();
+    if negotiated.from_accept && !headers.contains_key(header::VARY) {
+        headers.insert(header::VARY, HeaderValue::from_static("accept"));
+    }
+    if let Some(deprecation) = deprecation {
+        deprecation.apply_headers(headers, successor.as_deref());
+    }
+
+    Ok(response.map_into_left_body())
+}
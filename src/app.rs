@@ -0,0 +1,234 @@
+use crate::adapters::{
+    change_feed::ChangeFeed,
+    db::DbPools,
+    metrics,
+    negotiation::PayloadLimits,
+    rate_limit::{self, RateLimiter},
+    readiness::Readiness,
+    request_id,
+    versioning::{self, ApiVersion, ApiVersioning},
+};
+use crate::config::{Config, ConfigError};
+use crate::errors::{json_error_handler, path_error_handler, query_error_handler};
+use crate::graphql::{self, AppSchema};
+use crate::repositories::Storage;
+use crate::routes;
+use crate::shutdown::{ShutdownCoordinator, track_in_flight};
+use actix_web::{
+    App, Error,
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    middleware::{Compress, Logger, from_fn},
+    web::{Data, JsonConfig, PathConfig, QueryConfig, ServiceConfig, scope, to},
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing_actix_web::TracingLogger;
+
+// Default actix access log format, plus the id assigned to the request
+const ACCESS_LOG_FORMAT: &str =
+    r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T request_id=%{X-Request-Id}o"#;
+
+/// Everything the HTTP application shares across workers. Built once, then
+/// turned into an `App` by each worker with `app`.
+///
+/// The change feed relays nothing until one started with `ChangeFeed::start`
+/// is set with `with_change_feed`.
+///
+/// Alerts, GraphQL and the admin routes are only served on Postgres.
+#[derive(Clone)]
+pub struct AppState {
+    config: Data<Config>,
+    storage: Data<Storage>,
+    postgres: Option<PostgresState>,
+    change_feed: Data<ChangeFeed>,
+    readiness: Data<Readiness>,
+    shutdown: Data<ShutdownCoordinator>,
+    rate_limiter: Option<Data<RateLimiter>>,
+    payload_limits: Data<PayloadLimits>,
+    api_versioning: Data<ApiVersioning>,
+}
+
+/// What the routes only served on Postgres need
+#[derive(Clone)]
+struct PostgresState {
+    pools: Data<DbPools>,
+    pool: Data<PgPool>,
+    graphql_schema: Data<AppSchema>,
+}
+
+impl AppState {
+    /// Fails when the rate limit, payload limit or deprecation rules of
+    /// `config` are invalid, see `Config::validate`
+    pub fn new(config: Config, storage: impl Into<Storage>) -> Result<Self, ConfigError> {
+        let storage = storage.into();
+        let postgres = storage.postgres().map(|pools| PostgresState {
+            pools: Data::new(pools.clone()),
+            pool: Data::new(pools.primary().clone()),
+            graphql_schema: Data::new(graphql::build_schema(pools.clone(), &config)),
+        });
+        let readiness = Readiness::new();
+        let shutdown = ShutdownCoordinator::new(
+            readiness.clone(),
+            Duration::from_secs(config.shutdown_timeout_secs),
+        );
+
+        Ok(Self {
+            change_feed: Data::new(ChangeFeed::new(Duration::from_secs(
+                config.sse_heartbeat_secs,
+            ))),
+            readiness: Data::new(readiness),
+            shutdown: Data::new(shutdown),
+            rate_limiter: RateLimiter::from_config(
+                &config,
+                postgres.as_ref().map(|postgres| postgres.pool.get_ref()),
+            )?
+            .map(Data::new),
+            payload_limits: Data::new(PayloadLimits::from_config(&config)?),
+            api_versioning: Data::new(ApiVersioning::from_config(&config)?),
+            storage: Data::new(storage),
+            postgres,
+            config: Data::new(config),
+        })
+    }
+
+    pub fn with_change_feed(mut self, change_feed: ChangeFeed) -> Self {
+        self.change_feed = Data::new(change_feed);
+        self
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    pub fn readiness(&self) -> &Readiness {
+        &self.readiness
+    }
+
+    /// Limiter shared with the gRPC server, `None` when rate limiting is
+    /// disabled
+    pub fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        self.rate_limiter
+            .as_ref()
+            .map(|rate_limiter| rate_limiter.clone().into_inner())
+    }
+
+    /// Coordinator draining the application, its readiness is the one of
+    /// the application
+    pub fn shutdown(&self) -> &ShutdownCoordinator {
+        &self.shutdown
+    }
+
+    /// The actix application: every route, under `url_prefix` for the API,
+    /// with its middleware and error handlers
+    pub fn app(
+        &self,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<impl MessageBody + use<>>,
+            Error = Error,
+            InitError = (),
+        > + use<>,
+    > {
+        let mut app = App::new();
+        if let Some(rate_limiter) = &self.rate_limiter {
+            app = app.app_data(rate_limiter.clone());
+        }
+        if let Some(postgres) = &self.postgres {
+            app = app
+                .app_data(postgres.pool.clone())
+                .app_data(postgres.pools.clone())
+                .app_data(postgres.graphql_schema.clone());
+        }
+        let postgres = self.postgres.is_some();
+        let postgres_routes = move |routes: fn(&mut ServiceConfig)| {
+            move |cfg: &mut ServiceConfig| {
+                if postgres {
+                    routes(cfg);
+                }
+            }
+        };
+
+        let url_prefix = &self.config.url_prefix;
+        app.app_data(self.config.clone())
+            .app_data(self.storage.clone())
+            .app_data(self.change_feed.clone())
+            .app_data(self.readiness.clone())
+            .app_data(self.shutdown.clone())
+            .app_data(self.payload_limits.clone())
+            .app_data(self.api_versioning.clone())
+            .app_data(
+                JsonConfig::default()
+                    .limit(self.config.payload_limit_bytes)
+                    .error_handler(json_error_handler),
+            )
+            .app_data(PathConfig::default().error_handler(path_error_handler))
+            .app_data(QueryConfig::default().error_handler(query_error_handler))
+            .wrap(from_fn(rate_limit::enforce_rate_limit))
+            .wrap(from_fn(track_in_flight))
+            .wrap(from_fn(metrics::track_requests))
+            .wrap(from_fn(versioning::negotiate_version))
+            .wrap(from_fn(request_id::assign_request_id))
+            .wrap(Logger::new(ACCESS_LOG_FORMAT))
+            .wrap(Compress::default())
+            .wrap(TracingLogger::default())
+            .default_service(to(routes::route_not_found))
+            .configure(routes::cfg_monitoring_routes)
+            .configure(postgres_routes(routes::cfg_admin_routes))
+            .configure(routes::cfg_docs_routes)
+            .service(
+                scope(url_prefix)
+                    .service(
+                        scope(ApiVersion::V1.as_str())
+                            .app_data(ApiVersion::V1)
+                            .configure(routes::cfg_savings_routes)
+                            .configure(postgres_routes(routes::cfg_alerts_routes))
+                            .configure(routes::cfg_realtime_routes),
+                    )
+                    .service(
+                        scope(ApiVersion::V2.as_str())
+                            .app_data(ApiVersion::V2)
+                            .configure(routes::cfg_savings_v2_routes)
+                            .configure(postgres_routes(routes::cfg_alerts_routes))
+                            .configure(routes::cfg_realtime_routes),
+                    )
+                    .configure(postgres_routes(routes::cfg_graphql_routes)),
+            )
+    }
+}
+
+/// Build a single application for `config` on top of `storage`, a Postgres
+/// pool or a `Storage`, for testing it with `actix_web::test`. The database
+/// schema must be migrated, see `adapters::db::run_migrations` and
+/// `adapters::sqlite::run_migrations`.
+///
+/// Every call builds its own `AppState`, so calling it per worker would give
+/// each worker its own rate limits, readiness and shutdown coordinator. To
+/// embed the application in a server, build an `AppState` once and call
+/// `AppState::app` per worker instead.
+///
+/// Fails as `AppState::new` does.
+pub fn app_factory(
+    config: Config,
+    storage: impl Into<Storage>,
+) -> Result<
+    App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<impl MessageBody>,
+            Error = Error,
+            InitError = (),
+        >,
+    >,
+    ConfigError,
+> {
+    Ok(AppState::new(config, storage)?.app())
+}
@@ -0,0 +1,72 @@
+use crate::config::{Config, Environment};
+use crate::errors::{AppError, AppResult};
+use actix_web::{HttpRequest, web::Query};
+use std::collections::HashMap;
+use subtle::ConstantTimeEq;
+
+pub const API_KEY_HEADER: &str = "X-API-Key";
+const API_KEY_QUERY_PARAM: &str = "api_key";
+
+/// Extract the API key from the `X-API-Key` header, falling back to the
+/// `api_key` query parameter for clients that can't set headers (e.g. browser WebSockets)
+pub fn api_key_from_request(req: &HttpRequest) -> Option<String> {
+    if let Some(value) = req.headers().get(API_KEY_HEADER) {
+        return value.to_str().ok().map(str::to_string);
+    }
+
+    Query::<HashMap<String, String>>::from_query(req.query_string())
+        .ok()
+        .and_then(|mut params| params.remove(API_KEY_QUERY_PARAM))
+}
+
+/// Check the request carries one of the configured API keys.
+/// When no keys are configured, only the dev environment lets requests through.
+pub fn authenticate(req: &HttpRequest, config: &Config) -> AppResult<()> {
+    check_api_key(api_key_from_request(req).as_deref(), config)
+}
+
+/// Check `key` is one of the configured API keys, see `authenticate`
+pub fn check_api_key(key: Option<&str>, config: &Config) -> AppResult<()> {
+    if config.api_keys.is_empty() {
+        if config.environment == Environment::Dev {
+            return Ok(());
+        }
+        return Err(AppError::Unauthorized(
+            "Authentication is not configured".to_string(),
+        ));
+    }
+
+    match key {
+        Some(key) if api_key_index(&config.api_keys, key).is_some() => Ok(()),
+        Some(_) => Err(AppError::Unauthorized("Invalid API key".to_string())),
+        None => Err(AppError::Unauthorized("Missing API key".to_string())),
+    }
+}
+
+/// Position of `key` among `api_keys`. Every key is compared in constant
+/// time, so that timings don't tell how close a guess came to one of them.
+pub(crate) fn api_key_index(api_keys: &[String], key: &str) -> Option<usize> {
+    api_keys
+        .iter()
+        .enumerate()
+        .fold(None, |found, (index, known)| {
+            let matches = bool::from(known.as_bytes().ct_eq(key.as_bytes()));
+            found.or(matches.then_some(index))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_only_exact_api_keys() {
+        let keys = vec!["first-key".to_string(), "second-key".to_string()];
+        assert_eq!(api_key_index(&keys, "first-key"), Some(0));
+        assert_eq!(api_key_index(&keys, "second-key"), Some(1));
+        assert_eq!(api_key_index(&keys, "second-ke"), None);
+        assert_eq!(api_key_index(&keys, "second-keys"), None);
+        assert_eq!(api_key_index(&keys, ""), None);
+        assert_eq!(api_key_index(&[], "first-key"), None);
+    }
+}
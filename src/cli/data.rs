@@ -0,0 +1,133 @@
+use crate::adapters::db::DbPools;
+use crate::cli::CliResult;
+use crate::config::{Config, Environment};
+use crate::models::transactions::{
+    CreateTransaction, DEFAULT_CURRENCY, ImportedTransaction, TransactionKind,
+};
+use crate::services::MaintenanceService;
+use chrono::{Duration, Utc};
+use rand::Rng;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use tokio_stream::StreamExt;
+use validator::Validate;
+
+const SEED_SOURCES: &[&str] = &["paycheck", "freelance", "bonus", "refund", "gift"];
+const SEED_HISTORY_DAYS: i64 = 90;
+
+/// Random transactions spread over the last 90 days
+pub async fn seed(pool: &PgPool, config: &Config, count: usize) -> CliResult {
+    if config.environment != Environment::Dev {
+        return Err(format!(
+            "Seeding is not allowed in the {} environment",
+            config.environment
+        )
+        .into());
+    }
+
+    let mut rng = rand::rng();
+    let now = Utc::now();
+    let records: Vec<ImportedTransaction> = (0..count)
+        .map(|_| ImportedTransaction {
+            amount: Decimal::new(rng.random_range(100..50_000), 2),
+            currency: DEFAULT_CURRENCY.to_string(),
+            kind: TransactionKind::Deposit,
+            tags: Vec::new(),
+            source: SEED_SOURCES[rng.random_range(0..SEED_SOURCES.len())].to_string(),
+            created_at: Some(
+                now - Duration::seconds(rng.random_range(0..SEED_HISTORY_DAYS * 24 * 60 * 60)),
+            ),
+        })
+        .collect();
+
+    let inserted = MaintenanceService::insert_transactions(pool, &records).await?;
+    tracing::info!(inserted, "🌱 Seeded transactions");
+    Ok(())
+}
+
+/// Reads from the replica when it is configured and caught up
+pub async fn export(pools: &DbPools, output: Option<PathBuf>) -> CliResult {
+    pools.check_replica().await;
+
+    let writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut writer = BufWriter::new(writer);
+
+    let mut exported = 0u64;
+    let mut transactions = MaintenanceService::stream_transactions(pools.read());
+    while let Some(transaction) = transactions.next().await {
+        serde_json::to_writer(&mut writer, &transaction?)?;
+        writer.write_all(b"\n")?;
+        exported += 1;
+    }
+    writer.flush()?;
+
+    tracing::info!(exported, "📤 Exported transactions");
+    Ok(())
+}
+
+/// Every line is validated before anything is inserted
+pub async fn import(pool: &PgPool, input: Option<PathBuf>) -> CliResult {
+    let reader: Box<dyn BufRead> = match input {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(io::stdin().lock()),
+    };
+
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let line_number = index + 1;
+        match serde_json::from_str::<ImportedTransaction>(&line) {
+            Ok(record) => match CreateTransaction::from(&record).validate() {
+                Ok(()) => records.push(record),
+                Err(e) => errors.push(format!("line {}: {}", line_number, e)),
+            },
+            Err(e) => errors.push(format!("line {}: {}", line_number, e)),
+        }
+    }
+
+    if !errors.is_empty() {
+        for error in &errors {
+            eprintln!("{}", error);
+        }
+        return Err(format!("{} invalid lines, nothing was imported", errors.len()).into());
+    }
+
+    let imported = MaintenanceService::insert_transactions(pool, &records).await?;
+    tracing::info!(imported, "📥 Imported transactions");
+    Ok(())
+}
+
+pub async fn purge(pool: &PgPool, older_than_days: u32, dry_run: bool) -> CliResult {
+    let before = Utc::now() - Duration::days(older_than_days.into());
+
+    if dry_run {
+        let summary = MaintenanceService::count_history(pool, before).await?;
+        println!(
+            "Would delete {} transaction events and {} alerts created before {}",
+            summary.transaction_events,
+            summary.alerts,
+            before.to_rfc3339()
+        );
+        return Ok(());
+    }
+
+    let summary = MaintenanceService::purge_history(pool, before).await?;
+    tracing::info!(
+        transaction_events = summary.transaction_events,
+        alerts = summary.alerts,
+        before = %before,
+        "🧹 Purged history"
+    );
+    Ok(())
+}
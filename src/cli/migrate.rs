@@ -0,0 +1,92 @@
+use crate::adapters::{db, sqlite};
+use crate::cli::CliResult;
+use crate::models::migrations::AppliedMigration;
+use sqlx::{PgPool, SqlitePool, migrate::Migrator};
+
+pub async fn up(pool: &PgPool) -> CliResult {
+    db::run_migrations(pool).await?;
+    Ok(())
+}
+
+/// Without a target, revert only the latest applied migration
+pub async fn down(pool: &PgPool, target: Option<i64>) -> CliResult {
+    let target = match target {
+        Some(target) => target,
+        None => match previous_version(&db::applied_migrations(pool).await?) {
+            Some(target) => target,
+            None => return Ok(()),
+        },
+    };
+
+    db::revert_migrations(pool, target).await?;
+    Ok(())
+}
+
+pub async fn status(pool: &PgPool) -> CliResult {
+    print_status(&db::MIGRATOR, &db::applied_migrations(pool).await?);
+    Ok(())
+}
+
+pub async fn up_sqlite(pool: &SqlitePool) -> CliResult {
+    sqlite::run_migrations(pool).await?;
+    Ok(())
+}
+
+pub async fn down_sqlite(pool: &SqlitePool, target: Option<i64>) -> CliResult {
+    let target = match target {
+        Some(target) => target,
+        None => match previous_version(&sqlite::applied_migrations(pool).await?) {
+            Some(target) => target,
+            None => return Ok(()),
+        },
+    };
+
+    sqlite::revert_migrations(pool, target).await?;
+    Ok(())
+}
+
+pub async fn status_sqlite(pool: &SqlitePool) -> CliResult {
+    print_status(&sqlite::MIGRATOR, &sqlite::applied_migrations(pool).await?);
+    Ok(())
+}
+
+/// Version to revert to for undoing only the latest applied migration
+fn previous_version(applied: &[AppliedMigration]) -> Option<i64> {
+    match applied {
+        [] => {
+            println!("No migration to revert");
+            None
+        }
+        [_] => Some(0),
+        [.., previous, _] => Some(previous.version),
+    }
+}
+
+fn print_status(migrator: &Migrator, applied: &[AppliedMigration]) {
+    println!("{:<16} {:<40} STATUS", "VERSION", "DESCRIPTION");
+    for migration in migrator
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+    {
+        let status = match applied.iter().find(|a| a.version == migration.version) {
+            Some(a) if a.success => format!("applied {}", a.installed_on.to_rfc3339()),
+            Some(_) => "failed".to_string(),
+            None => "pending".to_string(),
+        };
+        println!(
+            "{:<16} {:<40} {}",
+            migration.version, migration.description, status
+        );
+    }
+
+    // Applied by a newer build, this one cannot run against the database
+    for migration in applied
+        .iter()
+        .filter(|a| migrator.iter().all(|m| m.version != a.version))
+    {
+        println!(
+            "{:<16} {:<40} unknown to this build",
+            migration.version, migration.description
+        );
+    }
+}
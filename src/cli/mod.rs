@@ -0,0 +1,181 @@
+mod data;
+mod migrate;
+
+use crate::adapters::db::{self, DbPools};
+use crate::adapters::sqlite;
+use crate::config::{Config, ConfigSources};
+use clap::{Parser, Subcommand};
+use sqlx::{PgPool, SqlitePool};
+use std::error::Error;
+use std::path::PathBuf;
+
+pub type CliResult = Result<(), Box<dyn Error>>;
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Savings push processing service and management commands"
+)]
+pub struct Cli {
+    /// Defaults to `serve` when omitted
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// TOML configuration file, `CONFIG_FILE` is used when unset
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Override a configuration value, taking precedence over every other source
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE", value_parser = parse_override)]
+    pub overrides: Vec<(String, String)>,
+}
+
+impl Cli {
+    /// `check-config` reports every invalid value, even in dev
+    pub fn config_sources(&self) -> ConfigSources {
+        ConfigSources {
+            file: self.config.clone(),
+            overrides: self.overrides.clone(),
+            strict: matches!(self.command, Some(Command::CheckConfig)),
+        }
+    }
+}
+
+fn parse_override(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected KEY=VALUE, got `{}`", value))
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP and gRPC servers
+    Serve,
+    /// Manage database migrations
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+    /// Insert random transactions, only allowed in the dev environment
+    Seed {
+        #[arg(long, default_value_t = 100)]
+        count: usize,
+    },
+    /// Write every transaction as JSON lines, to stdout unless `--output` is set
+    Export {
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Insert transactions from JSON lines as written by `export`, read from
+    /// stdin unless `--input` is set
+    Import {
+        #[arg(long, short)]
+        input: Option<PathBuf>,
+    },
+    /// Validate the configuration, print it with secrets masked and check the
+    /// database is reachable and compatible
+    CheckConfig,
+    /// Delete change events and alerts older than the given number of days
+    Purge {
+        #[arg(long)]
+        older_than_days: u32,
+        /// Only report what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateCommand {
+    /// Apply every pending migration
+    Up,
+    /// Revert migrations, only the latest one unless `--target` is set
+    Down {
+        /// Revert every migration newer than this version, 0 reverts them all
+        #[arg(long)]
+        target: Option<i64>,
+    },
+    /// List embedded and applied migrations
+    Status,
+}
+
+/// Run a management command. `serve` is handled by the binary itself.
+pub async fn run(command: Command, config: &Config) -> CliResult {
+    if sqlite::is_sqlite_url(&config.database_url) {
+        return run_sqlite(command, config).await;
+    }
+
+    let pools = connect(config).await?;
+    let pool = pools.primary();
+
+    let result = match command {
+        Command::Serve => Err("serve is handled by the binary".into()),
+        Command::Migrate { command } => match command {
+            MigrateCommand::Up => migrate::up(pool).await,
+            MigrateCommand::Down { target } => migrate::down(pool, target).await,
+            MigrateCommand::Status => migrate::status(pool).await,
+        },
+        Command::Seed { count } => data::seed(pool, config, count).await,
+        Command::Export { output } => data::export(&pools, output).await,
+        Command::Import { input } => data::import(pool, input).await,
+        Command::CheckConfig => check_config(pool, config).await,
+        Command::Purge {
+            older_than_days,
+            dry_run,
+        } => data::purge(pool, older_than_days, dry_run).await,
+    };
+
+    pools.close().await;
+    result
+}
+
+/// Only migrations and the configuration check are available on SQLite, the
+/// other commands work on Postgres data
+async fn run_sqlite(command: Command, config: &Config) -> CliResult {
+    let pool = sqlite::init_pool(&db::DatabaseConfig::from(config)).await?;
+
+    let result = match command {
+        Command::Serve => Err("serve is handled by the binary".into()),
+        Command::Migrate { command } => match command {
+            MigrateCommand::Up => migrate::up_sqlite(&pool).await,
+            MigrateCommand::Down { target } => migrate::down_sqlite(&pool, target).await,
+            MigrateCommand::Status => migrate::status_sqlite(&pool).await,
+        },
+        Command::CheckConfig => check_sqlite_config(&pool, config).await,
+        command => Err(format!("{:?} requires a Postgres database_url", command).into()),
+    };
+
+    pool.close().await;
+    result
+}
+
+async fn connect(config: &Config) -> Result<DbPools, sqlx::Error> {
+    db::init_pools(&db::DatabaseConfig::from(config)).await
+}
+
+async fn check_config(pool: &PgPool, config: &Config) -> CliResult {
+    for (key, value) in config.redacted() {
+        println!("{} = {}", key, value);
+    }
+
+    db::health_check(pool).await?;
+    db::check_schema_compatibility(pool).await?;
+    let pending = db::pending_migrations(pool).await?;
+    println!("# database reachable, {} pending migrations", pending.len());
+
+    Ok(())
+}
+
+async fn check_sqlite_config(pool: &SqlitePool, config: &Config) -> CliResult {
+    for (key, value) in config.redacted() {
+        println!("{} = {}", key, value);
+    }
+
+    sqlite::health_check(pool).await?;
+    sqlite::check_schema_compatibility(pool).await?;
+    let pending = sqlite::pending_migrations(pool).await?;
+    println!("# database reachable, {} pending migrations", pending.len());
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use super::ConfigIssue;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming the TOML file when none is given explicitly
+const CONFIG_FILE_VAR: &str = "CONFIG_FILE";
+
+/// Sources read on top of the defaults and the environment
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSources {
+    /// TOML file with config keys at the top level, `CONFIG_FILE` when unset
+    pub file: Option<PathBuf>,
+    /// `key=value` pairs from the command line, applied last
+    pub overrides: Vec<(String, String)>,
+    /// Fail on any invalid value, even in dev
+    pub strict: bool,
+}
+
+/// Raw values from every layer, later layers replacing earlier ones. Values
+/// are kept as strings so they are parsed the same way whatever their layer.
+pub(super) fn collect(
+    sources: &ConfigSources,
+    keys: &[String],
+) -> (BTreeMap<String, String>, Vec<ConfigIssue>) {
+    let mut values = BTreeMap::new();
+    let mut issues = Vec::new();
+
+    let file = sources
+        .file
+        .clone()
+        .or_else(|| std::env::var_os(CONFIG_FILE_VAR).map(PathBuf::from));
+    if let Some(path) = file {
+        match read_file(&path) {
+            Ok(entries) => {
+                for (key, value) in entries {
+                    if keys.contains(&key) {
+                        values.insert(key, value);
+                    } else {
+                        issues.push(ConfigIssue::new(
+                            key,
+                            format!("unknown key in {}", path.display()),
+                        ));
+                    }
+                }
+            }
+            Err(message) => issues.push(ConfigIssue::new("config_file", message)),
+        }
+    }
+
+    // Other variables of the process environment are not config keys
+    for (key, value) in std::env::vars() {
+        let key = key.to_lowercase();
+        if keys.contains(&key) {
+            values.insert(key, value);
+        }
+    }
+
+    for (key, value) in &sources.overrides {
+        let key = key.to_lowercase();
+        if keys.contains(&key) {
+            values.insert(key, value.clone());
+        } else {
+            issues.push(ConfigIssue::new(key, "unknown key on the command line"));
+        }
+    }
+
+    (values, issues)
+}
+
+fn read_file(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    let table: toml::Table = toml::from_str(&content)
+        .map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            let value = env_value(value).map_err(|e| format!("{}: {}", key, e))?;
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// Render a TOML value the way it would be written in an environment variable
+fn env_value(value: toml::Value) -> Result<String, String> {
+    match value {
+        toml::Value::String(value) => Ok(value),
+        toml::Value::Array(items) => Ok(items
+            .into_iter()
+            .map(env_value)
+            .collect::<Result<Vec<_>, _>>()?
+            .join(",")),
+        toml::Value::Table(_) => Err("nested tables are not supported".to_string()),
+        value => Ok(value.to_string()),
+    }
+}
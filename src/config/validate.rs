@@ -0,0 +1,163 @@
+use super::{Config, ConfigError, ConfigIssue, OtelExporter, RateLimitBackend};
+use crate::adapters::negotiation::PayloadLimit;
+use crate::adapters::rate_limit::Limit;
+use crate::adapters::route_rule::parse_route_rule;
+use crate::adapters::sqlite;
+use crate::adapters::versioning::{ApiDeprecation, ApiVersion};
+
+impl Config {
+    /// Checks across fields, once every source has been merged
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut issues = Vec::new();
+        let mut check = |valid: bool, key: &str, message: &str| {
+            if !valid {
+                issues.push(ConfigIssue::new(key, message));
+            }
+        };
+
+        check(!self.database_url.is_empty(), "database_url", "must be set");
+        let sqlite = sqlite::is_sqlite_url(&self.database_url);
+        check(
+            self.database_url.is_empty()
+                || sqlite
+                || self.database_url.starts_with("postgres://")
+                || self.database_url.starts_with("postgresql://"),
+            "database_url",
+            "must be a postgres:// or sqlite: URL",
+        );
+        check(
+            self.database_read_url.as_deref().is_none_or(|url| {
+                url.is_empty() || url.starts_with("postgres://") || url.starts_with("postgresql://")
+            }),
+            "database_read_url",
+            "must be a postgres:// URL",
+        );
+        check(
+            !sqlite || self.database_read_url.as_deref().is_none_or(str::is_empty),
+            "database_read_url",
+            "is only supported with a Postgres database_url",
+        );
+        check(
+            !sqlite
+                || !self.rate_limit_enabled
+                || self.rate_limit_backend != RateLimitBackend::Postgres,
+            "rate_limit_backend",
+            "postgres requires a Postgres database_url",
+        );
+        check(
+            self.db_replica_check_interval_secs > 0,
+            "db_replica_check_interval_secs",
+            "must be greater than 0",
+        );
+        check(
+            self.db_max_connections > 0,
+            "db_max_connections",
+            "must be greater than 0",
+        );
+        // Bounds every wait for a connection, 0 would fail them all at once
+        check(
+            self.db_timeout_connection > 0,
+            "db_timeout_connection",
+            "must be greater than 0",
+        );
+        check(
+            self.db_min_connections <= self.db_max_connections,
+            "db_min_connections",
+            "must not be greater than db_max_connections",
+        );
+        check(
+            self.port != self.grpc_port,
+            "grpc_port",
+            "must differ from port",
+        );
+        check(
+            self.url_prefix.is_empty() || self.url_prefix.starts_with('/'),
+            "url_prefix",
+            "must start with /",
+        );
+        check(
+            self.alert_eval_interval_secs > 0,
+            "alert_eval_interval_secs",
+            "must be greater than 0",
+        );
+        check(
+            self.sse_heartbeat_secs > 0,
+            "sse_heartbeat_secs",
+            "must be greater than 0",
+        );
+        check(
+            self.readiness_timeout_ms > 0,
+            "readiness_timeout_ms",
+            "must be greater than 0",
+        );
+        check(
+            self.payload_limit_bytes > 0,
+            "payload_limit_bytes",
+            "must be greater than 0",
+        );
+        check(
+            self.cache_capacity > 0,
+            "cache_capacity",
+            "must be greater than 0",
+        );
+        check(
+            self.cache_ttl_secs > 0,
+            "cache_ttl_secs",
+            "must be greater than 0",
+        );
+        check(
+            self.ws_client_buffer > 0,
+            "ws_client_buffer",
+            "must be greater than 0",
+        );
+        check(
+            self.graphql_max_depth > 0,
+            "graphql_max_depth",
+            "must be greater than 0",
+        );
+        check(
+            self.graphql_max_complexity > 0,
+            "graphql_max_complexity",
+            "must be greater than 0",
+        );
+        check(
+            self.otel_exporter != OtelExporter::Otlp || !self.otel_endpoint.is_empty(),
+            "otel_endpoint",
+            "must be set when otel_exporter is otlp",
+        );
+
+        if let Err(message) = self.rate_limit_default.parse::<Limit>() {
+            issues.push(ConfigIssue::new("rate_limit_default", message));
+        }
+        for rule in &self.rate_limit_routes {
+            if let Err(message) = parse_route_rule::<Limit>(rule) {
+                issues.push(ConfigIssue::new("rate_limit_routes", message));
+            }
+        }
+
+        for rule in &self.payload_limit_routes {
+            if let Err(message) = parse_route_rule::<PayloadLimit>(rule) {
+                issues.push(ConfigIssue::new("payload_limit_routes", message));
+            }
+        }
+
+        for rule in &self.api_deprecations {
+            match rule.parse::<ApiDeprecation>() {
+                Ok(deprecation) if deprecation.version == ApiVersion::LATEST => {
+                    issues.push(ConfigIssue::new(
+                        "api_deprecations",
+                        format!("'{}' deprecates the latest version", rule),
+                    ))
+                }
+                Ok(_) => {}
+                Err(message) => issues.push(ConfigIssue::new("api_deprecations", message)),
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError { issues })
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! API representations of the models, one module per API version. The models
+//! follow the database, these follow what each version promised its clients,
+//! so that both can evolve separately.
+
+pub mod v1;
+pub mod v2;
+
+use crate::adapters::versioning::ApiVersion;
+use crate::models::transactions::Transaction;
+use serde::Serialize;
+
+/// A saving in the representation of a given version, for the routes shared
+/// by every version
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum VersionedSaving {
+    V1(v1::Saving),
+    V2(v2::Saving),
+}
+
+impl VersionedSaving {
+    pub fn new(version: ApiVersion, transaction: Transaction) -> Self {
+        match version {
+            ApiVersion::V1 => VersionedSaving::V1(transaction.into()),
+            ApiVersion::V2 => VersionedSaving::V2(transaction.into()),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::models::transactions::{
+    CreateTransaction, ImportedTransaction, Transaction, validate_positive_amount,
+    validate_storable_amount,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// A saving as served by v1, which predates currencies, kinds and tags
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(as = SavingV1)]
+pub struct Saving {
+    pub id: i64,
+    #[schema(value_type = String, format = "decimal", example = "25.5000")]
+    pub amount: Decimal,
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Transaction> for Saving {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            id: transaction.id,
+            amount: transaction.amount,
+            source: transaction.source,
+            created_at: transaction.created_at,
+            updated_at: transaction.updated_at,
+        }
+    }
+}
+
+/// A new saving, always a deposit in the default currency
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[schema(as = CreateSavingV1)]
+pub struct CreateSaving {
+    #[validate(
+        custom(
+            function = "validate_positive_amount",
+            message = "Amount must be greater than 0"
+        ),
+        custom(
+            function = "validate_storable_amount",
+            message = "Amount must be less than 1000000000000000"
+        )
+    )]
+    #[schema(value_type = f64, exclusive_minimum = 0, exclusive_maximum = 1e15, example = 25.5)]
+    pub amount: Decimal,
+
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "Source must be between 1 and 255 characters"
+    ))]
+    #[schema(min_length = 1, max_length = 255, example = "paycheck")]
+    pub source: String,
+}
+
+impl From<CreateSaving> for CreateTransaction {
+    fn from(saving: CreateSaving) -> Self {
+        CreateTransaction::deposit(saving.amount, saving.source)
+    }
+}
+
+/// A saving read back from an export, the id is assigned again on insert
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+#[schema(as = ImportedSavingV1)]
+pub struct ImportedSaving {
+    #[validate(
+        custom(
+            function = "validate_positive_amount",
+            message = "Amount must be greater than 0"
+        ),
+        custom(
+            function = "validate_storable_amount",
+            message = "Amount must be less than 1000000000000000"
+        )
+    )]
+    #[schema(value_type = f64, exclusive_minimum = 0, exclusive_maximum = 1e15, example = 25.5)]
+    pub amount: Decimal,
+
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "Source must be between 1 and 255 characters"
+    ))]
+    #[schema(min_length = 1, max_length = 255, example = "paycheck")]
+    pub source: String,
+
+    /// Defaults to the time of the import
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<ImportedSaving> for ImportedTransaction {
+    fn from(saving: ImportedSaving) -> Self {
+        let CreateTransaction {
+            amount,
+            currency,
+            kind,
+            tags,
+            source,
+        } = CreateTransaction::deposit(saving.amount, saving.source);
+
+        Self {
+            amount,
+            currency,
+            kind,
+            tags,
+            source,
+            created_at: saving.created_at,
+        }
+    }
+}
+
+/// Savings imported in a single request, either all of them or none
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[schema(as = ImportSavingsV1)]
+pub struct ImportSavings {
+    #[validate(
+        length(min = 1, message = "At least one record must be provided"),
+        nested
+    )]
+    pub records: Vec<ImportedSaving>,
+}
+
+impl From<ImportSavings> for Vec<ImportedTransaction> {
+    fn from(import: ImportSavings) -> Self {
+        import.records.into_iter().map(Into::into).collect()
+    }
+}
@@ -0,0 +1,177 @@
+use crate::models::transactions::{
+    CreateTransaction, ImportedTransaction, Transaction, TransactionKind, validate_currency,
+    validate_positive_amount, validate_storable_amount, validate_tags,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// A saving as served by v2, with its currency, kind and tags
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(as = SavingV2)]
+pub struct Saving {
+    pub id: i64,
+    #[schema(value_type = String, format = "decimal", example = "25.5000")]
+    pub amount: Decimal,
+    #[schema(example = "USD")]
+    pub currency: String,
+    pub kind: TransactionKind,
+    #[schema(example = json!(["salary"]))]
+    pub tags: Vec<String>,
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Transaction> for Saving {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            id: transaction.id,
+            amount: transaction.amount,
+            currency: transaction.currency,
+            kind: transaction.kind,
+            tags: transaction.tags,
+            source: transaction.source,
+            created_at: transaction.created_at,
+            updated_at: transaction.updated_at,
+        }
+    }
+}
+
+/// A new saving, a deposit without tags unless stated otherwise
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[schema(as = CreateSavingV2)]
+pub struct CreateSaving {
+    #[validate(
+        custom(
+            function = "validate_positive_amount",
+            message = "Amount must be greater than 0"
+        ),
+        custom(
+            function = "validate_storable_amount",
+            message = "Amount must be less than 1000000000000000"
+        )
+    )]
+    #[schema(value_type = f64, exclusive_minimum = 0, exclusive_maximum = 1e15, example = 25.5)]
+    pub amount: Decimal,
+
+    #[validate(custom(
+        function = "validate_currency",
+        message = "Currency must be an ISO 4217 code, such as USD"
+    ))]
+    #[schema(min_length = 3, max_length = 3, example = "USD")]
+    pub currency: String,
+
+    #[serde(default)]
+    pub kind: TransactionKind,
+
+    #[serde(default)]
+    #[validate(custom(
+        function = "validate_tags",
+        message = "At most 20 tags of 1 to 50 characters are allowed"
+    ))]
+    #[schema(max_items = 20, example = json!(["salary"]))]
+    pub tags: Vec<String>,
+
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "Source must be between 1 and 255 characters"
+    ))]
+    #[schema(min_length = 1, max_length = 255, example = "paycheck")]
+    pub source: String,
+}
+
+impl From<CreateSaving> for CreateTransaction {
+    fn from(saving: CreateSaving) -> Self {
+        Self {
+            amount: saving.amount,
+            currency: saving.currency,
+            kind: saving.kind,
+            tags: saving.tags,
+            source: saving.source,
+        }
+    }
+}
+
+/// A saving read back from an export, the id is assigned again on insert
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+#[schema(as = ImportedSavingV2)]
+pub struct ImportedSaving {
+    #[validate(
+        custom(
+            function = "validate_positive_amount",
+            message = "Amount must be greater than 0"
+        ),
+        custom(
+            function = "validate_storable_amount",
+            message = "Amount must be less than 1000000000000000"
+        )
+    )]
+    #[schema(value_type = f64, exclusive_minimum = 0, exclusive_maximum = 1e15, example = 25.5)]
+    pub amount: Decimal,
+
+    #[validate(custom(
+        function = "validate_currency",
+        message = "Currency must be an ISO 4217 code, such as USD"
+    ))]
+    #[schema(min_length = 3, max_length = 3, example = "USD")]
+    pub currency: String,
+
+    #[serde(default)]
+    pub kind: TransactionKind,
+
+    #[serde(default)]
+    #[validate(custom(
+        function = "validate_tags",
+        message = "At most 20 tags of 1 to 50 characters are allowed"
+    ))]
+    #[schema(max_items = 20, example = json!(["salary"]))]
+    pub tags: Vec<String>,
+
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "Source must be between 1 and 255 characters"
+    ))]
+    #[schema(min_length = 1, max_length = 255, example = "paycheck")]
+    pub source: String,
+
+    /// Defaults to the time of the import
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<ImportedSaving> for ImportedTransaction {
+    fn from(saving: ImportedSaving) -> Self {
+        Self {
+            amount: saving.amount,
+            currency: saving.currency,
+            kind: saving.kind,
+            tags: saving.tags,
+            source: saving.source,
+            created_at: saving.created_at,
+        }
+    }
+}
+
+/// Savings imported in a single request, either all of them or none
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[schema(as = ImportSavingsV2)]
+pub struct ImportSavings {
+    #[validate(
+        length(min = 1, message = "At least one record must be provided"),
+        nested
+    )]
+    pub records: Vec<ImportedSaving>,
+}
+
+impl From<ImportSavings> for Vec<ImportedTransaction> {
+    fn from(import: ImportSavings) -> Self {
+        import.records.into_iter().map(Into::into).collect()
+    }
+}
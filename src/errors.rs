@@ -1,24 +1,196 @@
-use actix_web::{HttpResponse, ResponseError, http::StatusCode};
+use crate::adapters::rate_limit::{RateLimitStatus, whole_seconds};
+use crate::adapters::request_id::current_request_id;
+use actix_web::{
+    HttpRequest, HttpResponse, ResponseError,
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    http::StatusCode,
+};
 use serde::Serialize;
+use sqlx::error::ErrorKind;
 use std::fmt;
-use validator::ValidationErrors;
+use utoipa::ToSchema;
+use validator::{ValidationErrors, ValidationErrorsKind};
 
 pub type AppResult<T> = Result<T, AppError>;
 
+/// Media type of error bodies, RFC 7807 problem details
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Prefix of the problem `type` URIs, followed by the kebab-case error code
+const PROBLEM_TYPE_PREFIX: &str = "urn:gsn-push-processing:problem:";
+
 #[derive(Debug)]
 pub enum AppError {
     ValidationError(ValidationErrors),
     DatabaseError(sqlx::Error),
     NotFound(String),
+    SavingNotFound(i64),
+    ThresholdNotFound(i64),
     BadRequest(String),
+    InvalidJson(String),
+    InvalidPath(String),
+    InvalidQuery(String),
+    InvalidBody(String),
+    PayloadTooLarge(usize),
+    UnsupportedMediaType(String),
+    UnsupportedApiVersion(String),
+    Unauthorized(String),
+    TooManyRequests(RateLimitStatus),
     InternalServerError(String),
 }
 
-#[derive(Serialize)]
+/// Stable, machine-readable error codes. Clients may rely on them, unlike
+/// on messages: existing codes are never renamed or reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    ValidationFailed,
+    BadRequest,
+    InvalidJson,
+    InvalidPathParameter,
+    InvalidQueryParameter,
+    InvalidBody,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    UnsupportedApiVersion,
+    Unauthorized,
+    NotFound,
+    SavingNotFound,
+    ThresholdNotFound,
+    Duplicate,
+    ReferenceNotFound,
+    MissingField,
+    RateLimited,
+    DatabaseError,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::ValidationFailed => "VALIDATION_FAILED",
+            ErrorCode::BadRequest => "BAD_REQUEST",
+            ErrorCode::InvalidJson => "INVALID_JSON",
+            ErrorCode::InvalidPathParameter => "INVALID_PATH_PARAMETER",
+            ErrorCode::InvalidQueryParameter => "INVALID_QUERY_PARAMETER",
+            ErrorCode::InvalidBody => "INVALID_BODY",
+            ErrorCode::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ErrorCode::UnsupportedMediaType => "UNSUPPORTED_MEDIA_TYPE",
+            ErrorCode::UnsupportedApiVersion => "UNSUPPORTED_API_VERSION",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::SavingNotFound => "SAVING_NOT_FOUND",
+            ErrorCode::ThresholdNotFound => "THRESHOLD_NOT_FOUND",
+            ErrorCode::Duplicate => "DUPLICATE",
+            ErrorCode::ReferenceNotFound => "REFERENCE_NOT_FOUND",
+            ErrorCode::MissingField => "MISSING_FIELD",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::DatabaseError => "DATABASE_ERROR",
+            ErrorCode::InternalError => "INTERNAL_ERROR",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::ValidationFailed
+            | ErrorCode::BadRequest
+            | ErrorCode::InvalidJson
+            | ErrorCode::InvalidPathParameter
+            | ErrorCode::InvalidQueryParameter
+            | ErrorCode::InvalidBody
+            | ErrorCode::ReferenceNotFound
+            | ErrorCode::MissingField => StatusCode::BAD_REQUEST,
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::UnsupportedApiVersion => StatusCode::NOT_ACCEPTABLE,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::NotFound | ErrorCode::SavingNotFound | ErrorCode::ThresholdNotFound => {
+                StatusCode::NOT_FOUND
+            }
+            ErrorCode::Duplicate => StatusCode::CONFLICT,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::DatabaseError | ErrorCode::InternalError => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Short summary of the problem, the same for every occurrence
+    pub fn title(&self) -> &'static str {
+        match self {
+            ErrorCode::ValidationFailed => "Validation failed",
+            ErrorCode::BadRequest => "Bad request",
+            ErrorCode::InvalidJson => "Invalid JSON payload",
+            ErrorCode::InvalidPathParameter => "Invalid path parameter",
+            ErrorCode::InvalidQueryParameter => "Invalid query parameter",
+            ErrorCode::InvalidBody => "Invalid request body",
+            ErrorCode::PayloadTooLarge => "Payload too large",
+            ErrorCode::UnsupportedMediaType => "Unsupported media type",
+            ErrorCode::UnsupportedApiVersion => "Unsupported API version",
+            ErrorCode::Unauthorized => "Unauthorized",
+            ErrorCode::NotFound => "Resource not found",
+            ErrorCode::SavingNotFound => "Saving not found",
+            ErrorCode::ThresholdNotFound => "Alert threshold not found",
+            ErrorCode::Duplicate => "A record with this information already exists",
+            ErrorCode::ReferenceNotFound => "Referenced resource does not exist",
+            ErrorCode::MissingField => "Required field is missing",
+            ErrorCode::RateLimited => "Too many requests",
+            ErrorCode::DatabaseError => "Database operation failed",
+            ErrorCode::InternalError => "An internal error occurred",
+        }
+    }
+
+    pub fn problem_type(&self) -> String {
+        format!(
+            "{}{}",
+            PROBLEM_TYPE_PREFIX,
+            self.as_str().to_lowercase().replace('_', "-")
+        )
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Error body, an RFC 7807 problem details object extended with a stable
+/// `code`, the per-field `errors` of a failed validation and the request id
+#[derive(Serialize, ToSchema)]
 pub struct ErrorResponse {
-    pub error: String,
+    #[serde(rename = "type")]
+    #[schema(example = "urn:gsn-push-processing:problem:saving-not-found")]
+    pub problem_type: String,
+    #[schema(example = "Saving not found")]
+    pub title: String,
+    #[schema(example = 404)]
+    pub status: u16,
+    #[schema(example = "Saving with ID 42 not found")]
+    pub detail: String,
+    pub code: ErrorCode,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub details: Option<Vec<String>>,
+    pub errors: Option<Vec<FieldError>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// A field rejected by validation
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    #[schema(example = "amount")]
+    pub field: String,
+    /// Name of the failed rule
+    #[schema(example = "amount_must_be_positive")]
+    pub code: String,
+    #[schema(example = "Amount must be greater than 0")]
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
 }
 
 impl fmt::Display for AppError {
@@ -27,115 +199,187 @@ impl fmt::Display for AppError {
             AppError::ValidationError(_) => write!(f, "Validation error"),
             AppError::DatabaseError(e) => write!(f, "Database error: {}", e),
             AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            AppError::SavingNotFound(id) => write!(f, "Saving with ID {} not found", id),
+            AppError::ThresholdNotFound(id) => {
+                write!(f, "Alert threshold with ID {} not found", id)
+            }
             AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
+            AppError::InvalidJson(msg) => write!(f, "Invalid JSON payload: {}", msg),
+            AppError::InvalidPath(msg) => write!(f, "Invalid path parameter: {}", msg),
+            AppError::InvalidQuery(msg) => write!(f, "Invalid query parameter: {}", msg),
+            AppError::InvalidBody(msg) => write!(f, "Invalid request body: {}", msg),
+            AppError::PayloadTooLarge(limit) => {
+                write!(f, "Payload larger than the {} bytes limit", limit)
+            }
+            AppError::UnsupportedMediaType(msg) => write!(f, "Unsupported media type: {}", msg),
+            AppError::UnsupportedApiVersion(msg) => write!(f, "Unsupported API version: {}", msg),
+            AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            AppError::TooManyRequests(_) => write!(f, "Too many requests"),
             AppError::InternalServerError(msg) => write!(f, "Internal server error: {}", msg),
         }
     }
 }
 
+impl std::error::Error for AppError {}
+
 impl ResponseError for AppError {
     fn status_code(&self) -> StatusCode {
+        self.code().status()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code())
+            .content_type(PROBLEM_JSON_CONTENT_TYPE)
+            .json(self.to_error_response());
+        if let AppError::TooManyRequests(status) = self {
+            status.apply_headers(response.headers_mut());
+        }
+        response
+    }
+}
+
+impl AppError {
+    pub fn code(&self) -> ErrorCode {
         match self {
-            AppError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            AppError::ValidationError(_) => ErrorCode::ValidationFailed,
             AppError::DatabaseError(e) => match e {
-                sqlx::Error::RowNotFound => StatusCode::NOT_FOUND,
-                sqlx::Error::Database(db_err) => {
-                    // Check for specific database errors
-                    if let Some(code) = db_err.code() {
-                        match code.as_ref() {
-                            "23505" => StatusCode::CONFLICT,    // unique_violation
-                            "23503" => StatusCode::BAD_REQUEST, // foreign_key_violation
-                            "23502" => StatusCode::BAD_REQUEST, // not_null_violation
-                            _ => StatusCode::INTERNAL_SERVER_ERROR,
-                        }
-                    } else {
-                        StatusCode::INTERNAL_SERVER_ERROR
-                    }
-                }
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
+                sqlx::Error::RowNotFound => ErrorCode::NotFound,
+                // By kind rather than SQLSTATE, SQLite reports its own codes
+                sqlx::Error::Database(db_err) => match db_err.kind() {
+                    ErrorKind::UniqueViolation => ErrorCode::Duplicate, // 23505
+                    ErrorKind::ForeignKeyViolation => ErrorCode::ReferenceNotFound, // 23503
+                    ErrorKind::NotNullViolation => ErrorCode::MissingField, // 23502
+                    _ => ErrorCode::DatabaseError,
+                },
+                _ => ErrorCode::DatabaseError,
             },
-            AppError::NotFound(_) => StatusCode::NOT_FOUND,
-            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
-            AppError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::SavingNotFound(_) => ErrorCode::SavingNotFound,
+            AppError::ThresholdNotFound(_) => ErrorCode::ThresholdNotFound,
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::InvalidJson(_) => ErrorCode::InvalidJson,
+            AppError::InvalidPath(_) => ErrorCode::InvalidPathParameter,
+            AppError::InvalidQuery(_) => ErrorCode::InvalidQueryParameter,
+            AppError::InvalidBody(_) => ErrorCode::InvalidBody,
+            AppError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            AppError::UnsupportedMediaType(_) => ErrorCode::UnsupportedMediaType,
+            AppError::UnsupportedApiVersion(_) => ErrorCode::UnsupportedApiVersion,
+            AppError::Unauthorized(_) => ErrorCode::Unauthorized,
+            AppError::TooManyRequests(_) => ErrorCode::RateLimited,
+            AppError::InternalServerError(_) => ErrorCode::InternalError,
         }
     }
 
-    fn error_response(&self) -> HttpResponse {
-        let status_code = self.status_code();
+    /// Build the client-facing error body, logging the underlying error.
+    /// Internal details such as database errors are never exposed.
+    pub fn to_error_response(&self) -> ErrorResponse {
+        let code = self.code();
+        let mut errors = None;
 
-        let error_response = match self {
-            AppError::ValidationError(errors) => {
-                let details: Vec<String> = errors
-                    .field_errors()
+        let detail = match self {
+            AppError::ValidationError(validation_errors) => {
+                let field_errors = field_errors(validation_errors);
+                tracing::warn!(details = ?field_errors, "⚠️ Validation failed");
+
+                let detail = field_errors
                     .iter()
-                    .flat_map(|(field, errors)| {
-                        errors.iter().map(move |error| {
-                            format!(
-                                "{}: {}",
-                                field,
-                                error.message.as_ref().unwrap_or(&"Invalid value".into())
-                            )
-                        })
-                    })
-                    .collect();
-
-                log::warn!("⚠️ Validation failed: {:?}", details);
-
-                ErrorResponse {
-                    error: "Validation failed".to_string(),
-                    details: Some(details),
-                }
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                errors = Some(field_errors);
+                detail
             }
             AppError::DatabaseError(e) => {
-                log::error!("❌ Database error: {}", e);
-
+                tracing::error!(error = %e, "❌ Database error");
                 // Don't expose internal database errors to clients
-                let user_message = match e {
-                    sqlx::Error::RowNotFound => "Resource not found",
-                    sqlx::Error::Database(db_err) => {
-                        if let Some(code) = db_err.code() {
-                            match code.as_ref() {
-                                "23505" => "A record with this information already exists",
-                                "23503" => "Referenced resource does not exist",
-                                "23502" => "Required field is missing",
-                                _ => "Database operation failed",
-                            }
-                        } else {
-                            "Database operation failed"
-                        }
-                    }
-                    _ => "Database operation failed",
-                };
-
-                ErrorResponse {
-                    error: user_message.to_string(),
-                    details: None,
-                }
+                code.title().to_string()
             }
             AppError::NotFound(msg) => {
-                log::error!("Not found: {}", msg);
-                ErrorResponse {
-                    error: msg.clone(),
-                    details: None,
-                }
+                tracing::error!(error = %msg, "Not found");
+                msg.clone()
             }
-            AppError::BadRequest(msg) => {
-                log::error!("Bad request: {}", msg);
-                ErrorResponse {
-                    error: msg.clone(),
-                    details: None,
-                }
+            AppError::SavingNotFound(_) | AppError::ThresholdNotFound(_) => {
+                let msg = self.to_string();
+                tracing::error!(error = %msg, "Not found");
+                msg
+            }
+            AppError::BadRequest(msg)
+            | AppError::InvalidJson(msg)
+            | AppError::InvalidPath(msg)
+            | AppError::InvalidQuery(msg)
+            | AppError::InvalidBody(msg)
+            | AppError::UnsupportedMediaType(msg)
+            | AppError::UnsupportedApiVersion(msg) => {
+                tracing::error!(error = %msg, %code, "Bad request");
+                msg.clone()
+            }
+            AppError::PayloadTooLarge(_) => {
+                let msg = self.to_string();
+                tracing::warn!(error = %msg, "Bad request");
+                msg
+            }
+            AppError::Unauthorized(msg) => {
+                tracing::warn!(error = %msg, "🔒 Unauthorized");
+                msg.clone()
+            }
+            AppError::TooManyRequests(status) => {
+                let retry_after = status.retry_after.map(whole_seconds).unwrap_or_default();
+                format!("Too many requests, retry in {} seconds", retry_after)
             }
             AppError::InternalServerError(msg) => {
-                log::error!("☠️ Internal error: {}", msg);
-                ErrorResponse {
-                    error: "An internal error occurred".to_string(),
-                    details: None,
-                }
+                tracing::error!(error = %msg, "☠️ Internal error");
+                code.title().to_string()
             }
         };
 
-        HttpResponse::build(status_code).json(error_response)
+        ErrorResponse {
+            problem_type: code.problem_type(),
+            title: code.title().to_string(),
+            status: code.status().as_u16(),
+            detail,
+            code,
+            errors,
+            request_id: current_request_id(),
+        }
+    }
+}
+
+fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut field_errors = Vec::new();
+    collect_field_errors(errors, "", &mut field_errors);
+    // Fields come out of a hash map
+    field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+    field_errors
+}
+
+/// Flatten nested errors, naming fields by their path, e.g. `records[2].amount`
+fn collect_field_errors(errors: &ValidationErrors, prefix: &str, out: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", prefix, field)
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => out.extend(errors.iter().map(|error| {
+                FieldError {
+                    field: path.clone(),
+                    code: error.code.to_string(),
+                    message: error
+                        .message
+                        .as_ref()
+                        .map(ToString::to_string)
+                        .unwrap_or_else(|| "Invalid value".to_string()),
+                }
+            })),
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(errors, &path, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_field_errors(errors, &format!("{}[{}]", path, index), out);
+                }
+            }
+        }
     }
 }
 
@@ -152,3 +396,44 @@ impl From<ValidationErrors> for AppError {
         AppError::ValidationError(errors)
     }
 }
+
+/// `JsonConfig` error handler, malformed bodies get the usual error response
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let message = match &err {
+        JsonPayloadError::Deserialize(de_err) => de_err.to_string(),
+        JsonPayloadError::ContentType => {
+            return AppError::UnsupportedMediaType(
+                "Invalid content type, expected application/json".to_string(),
+            )
+            .into();
+        }
+        JsonPayloadError::OverflowKnownLength { limit, .. }
+        | JsonPayloadError::Overflow { limit } => {
+            return AppError::PayloadTooLarge(*limit).into();
+        }
+        JsonPayloadError::Payload(payload_err) => format!("Payload error: {}", payload_err),
+        _ => "Invalid JSON payload".to_string(),
+    };
+
+    AppError::InvalidJson(message).into()
+}
+
+/// `PathConfig` error handler
+pub fn path_error_handler(err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    let message = match &err {
+        PathError::Deserialize(de_err) => format!("Invalid path parameter: {}", de_err),
+        _ => "Invalid path parameter".to_string(),
+    };
+
+    AppError::InvalidPath(message).into()
+}
+
+/// `QueryConfig` error handler
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let message = match &err {
+        QueryPayloadError::Deserialize(de_err) => format!("Invalid query parameter: {}", de_err),
+        _ => "Invalid query parameter".to_string(),
+    };
+
+    AppError::InvalidQuery(message).into()
+}
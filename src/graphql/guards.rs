@@ -0,0 +1,23 @@
+use crate::errors::AppResult;
+use crate::graphql::graphql_error;
+use async_graphql::{Context, Guard, Result};
+
+/// Outcome of the API key check of the HTTP request carrying the operation,
+/// added to every request by the GraphQL route
+pub struct Authentication(Result<()>);
+
+impl From<AppResult<()>> for Authentication {
+    fn from(result: AppResult<()>) -> Self {
+        Self(result.map_err(graphql_error))
+    }
+}
+
+/// Let fields through only for requests with a valid API key, as the HTTP
+/// routes writing savings in bulk require. Denied without an `Authentication`.
+pub struct ApiKeyGuard;
+
+impl Guard for ApiKeyGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        ctx.data::<Authentication>()?.0.clone()
+    }
+}
@@ -0,0 +1,74 @@
+use crate::models::alerts::{Alert, AlertThreshold};
+use crate::services::AlertsService;
+use async_graphql::dataloader::Loader;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Goals are looked up by the source they watch
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceKey(pub String);
+
+/// Alert history is looked up by the threshold that raised it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ThresholdId(pub i64);
+
+/// Batches nested lookups into a single query per key type, avoiding N+1 queries.
+/// A new loader is created per request so cached values never outlive it.
+pub struct DbLoader {
+    db: PgPool,
+}
+
+impl DbLoader {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+impl Loader<SourceKey> for DbLoader {
+    type Value = Vec<AlertThreshold>;
+    type Error = Arc<String>;
+
+    async fn load(
+        &self,
+        keys: &[SourceKey],
+    ) -> Result<HashMap<SourceKey, Self::Value>, Self::Error> {
+        let sources: Vec<String> = keys.iter().map(|key| key.0.clone()).collect();
+        let thresholds = AlertsService::thresholds_for_sources(&self.db, &sources)
+            .await
+            .map_err(|e| Arc::new(e.to_error_response().detail))?;
+
+        let mut grouped: HashMap<SourceKey, Self::Value> = HashMap::new();
+        for threshold in thresholds {
+            grouped
+                .entry(SourceKey(threshold.source.clone()))
+                .or_default()
+                .push(threshold);
+        }
+        Ok(grouped)
+    }
+}
+
+impl Loader<ThresholdId> for DbLoader {
+    type Value = Vec<Alert>;
+    type Error = Arc<String>;
+
+    async fn load(
+        &self,
+        keys: &[ThresholdId],
+    ) -> Result<HashMap<ThresholdId, Self::Value>, Self::Error> {
+        let ids: Vec<i64> = keys.iter().map(|key| key.0).collect();
+        let alerts = AlertsService::alerts_for_thresholds(&self.db, &ids)
+            .await
+            .map_err(|e| Arc::new(e.to_error_response().detail))?;
+
+        let mut grouped: HashMap<ThresholdId, Self::Value> = HashMap::new();
+        for alert in alerts {
+            grouped
+                .entry(ThresholdId(alert.threshold_id))
+                .or_default()
+                .push(alert);
+        }
+        Ok(grouped)
+    }
+}
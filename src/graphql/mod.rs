@@ -0,0 +1,50 @@
+mod guards;
+mod loaders;
+mod mutation;
+mod query;
+mod types;
+
+use crate::adapters::db::DbPools;
+use crate::config::Config;
+use crate::errors::AppError;
+use async_graphql::{EmptySubscription, ErrorExtensions, Schema, dataloader::DataLoader};
+use sqlx::PgPool;
+
+pub use guards::{ApiKeyGuard, Authentication};
+pub use loaders::DbLoader;
+pub use mutation::MutationRoot;
+pub use query::QueryRoot;
+
+pub type AppSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// Build the GraphQL schema with the configured depth and complexity limits
+pub fn build_schema(pools: DbPools, config: &Config) -> AppSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .data(pools)
+        .limit_depth(config.graphql_max_depth)
+        .limit_complexity(config.graphql_max_complexity)
+        .finish()
+}
+
+/// Create the per-request loader used to batch nested lookups
+pub fn request_loader(pool: PgPool) -> DataLoader<DbLoader> {
+    DataLoader::new(DbLoader::new(pool), tokio::spawn)
+}
+
+/// Convert an `AppError` into a GraphQL error with the same client-facing
+/// message as the REST API, and its HTTP status and code as extensions
+pub(crate) fn graphql_error(err: AppError) -> async_graphql::Error {
+    let response = err.to_error_response();
+
+    async_graphql::Error::new(response.detail).extend_with(|_, extensions| {
+        extensions.set("status", response.status);
+        extensions.set("code", response.code.as_str());
+        if let Some(errors) = response
+            .errors
+            .and_then(|errors| serde_json::to_value(errors).ok())
+            .and_then(|errors| async_graphql::Value::from_json(errors).ok())
+        {
+            extensions.set("errors", errors);
+        }
+    })
+}
@@ -0,0 +1,51 @@
+use crate::adapters::db::DbPools;
+use crate::graphql::types::{CreateSavingInput, Saving, UpdateSavingInput};
+use crate::graphql::{ApiKeyGuard, graphql_error};
+use crate::models::transactions::{CreateTransaction, UpdateTransaction};
+use crate::services::SavingsService;
+use async_graphql::{Context, Object, Result};
+use validator::Validate;
+
+pub struct MutationRoot;
+
+/// Writes require an API key, like the HTTP routes writing in bulk
+#[Object]
+impl MutationRoot {
+    #[graphql(guard = "ApiKeyGuard")]
+    async fn create_saving(&self, ctx: &Context<'_>, input: CreateSavingInput) -> Result<Saving> {
+        let db = ctx.data::<DbPools>()?;
+        let payload = CreateTransaction::from(input);
+        payload.validate().map_err(|e| graphql_error(e.into()))?;
+
+        let transaction = SavingsService::create_new_saving(db, &payload)
+            .await
+            .map_err(graphql_error)?;
+        Ok(Saving(transaction))
+    }
+
+    #[graphql(guard = "ApiKeyGuard")]
+    async fn update_saving(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+        input: UpdateSavingInput,
+    ) -> Result<Saving> {
+        let db = ctx.data::<DbPools>()?;
+        let payload = UpdateTransaction::from(input);
+        payload.validate().map_err(|e| graphql_error(e.into()))?;
+
+        let transaction = SavingsService::update_saving(db, id, &payload)
+            .await
+            .map_err(graphql_error)?;
+        Ok(Saving(transaction))
+    }
+
+    #[graphql(guard = "ApiKeyGuard")]
+    async fn delete_saving(&self, ctx: &Context<'_>, id: i64) -> Result<bool> {
+        let db = ctx.data::<DbPools>()?;
+        SavingsService::delete_saving(db, id)
+            .await
+            .map_err(graphql_error)?;
+        Ok(true)
+    }
+}
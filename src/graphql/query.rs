@@ -0,0 +1,64 @@
+use crate::adapters::db::DbPools;
+use crate::graphql::graphql_error;
+use crate::graphql::types::{Goal, Saving, SavingsFilter, SavingsPage, SourceSummary};
+use crate::models::transactions::TransactionFilter;
+use crate::services::{AlertsService, SavingsService};
+use async_graphql::{Context, Object, Result};
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn saving(&self, ctx: &Context<'_>, id: i64) -> Result<Option<Saving>> {
+        let db = ctx.data::<DbPools>()?;
+        let transaction = SavingsService::get_by_id(db, id)
+            .await
+            .map_err(graphql_error)?;
+        Ok(transaction.map(Saving))
+    }
+
+    /// Savings matching the filter, newest first
+    // Every requested saving costs its selection, `limit` is validated to 1..=100
+    #[graphql(complexity = "limit.clamp(1, 100) as usize * child_complexity")]
+    async fn savings(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<SavingsFilter>,
+        #[graphql(default = 20, validator(minimum = 1, maximum = 100))] limit: i64,
+        #[graphql(default = 0, validator(minimum = 0))] offset: i64,
+    ) -> Result<SavingsPage> {
+        let db = ctx.data::<DbPools>()?;
+        let filter = TransactionFilter::from(filter.unwrap_or_default());
+
+        let items = SavingsService::search_savings(db, &filter, limit, offset)
+            .await
+            .map_err(graphql_error)?;
+        let total_count = SavingsService::count_savings(db, &filter)
+            .await
+            .map_err(graphql_error)?;
+
+        Ok(SavingsPage { total_count, items })
+    }
+
+    /// Total saved per source for savings matching the filter
+    async fn aggregates(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<SavingsFilter>,
+    ) -> Result<Vec<SourceSummary>> {
+        let db = ctx.data::<DbPools>()?;
+        let filter = TransactionFilter::from(filter.unwrap_or_default());
+        let aggregates = SavingsService::source_aggregates(db, &filter)
+            .await
+            .map_err(graphql_error)?;
+        Ok(aggregates.into_iter().map(SourceSummary::from).collect())
+    }
+
+    async fn goals(&self, ctx: &Context<'_>) -> Result<Vec<Goal>> {
+        let db = ctx.data::<DbPools>()?;
+        let thresholds = AlertsService::list_thresholds(db.primary())
+            .await
+            .map_err(graphql_error)?;
+        Ok(thresholds.into_iter().map(Goal).collect())
+    }
+}
@@ -0,0 +1,275 @@
+use crate::graphql::loaders::{DbLoader, SourceKey, ThresholdId};
+use crate::models::{alerts, transactions};
+use async_graphql::{
+    ComplexObject, Context, Enum, InputObject, Object, Result, SimpleObject, dataloader::DataLoader,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "alerts::AlertPeriod")]
+pub enum AlertPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "alerts::AlertComparison")]
+pub enum AlertComparison {
+    Below,
+    Above,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "alerts::AlertState")]
+pub enum AlertState {
+    Ok,
+    Triggered,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "alerts::AlertKind")]
+pub enum AlertKind {
+    Triggered,
+    Resolved,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "transactions::TransactionKind")]
+pub enum TransactionKind {
+    Deposit,
+    Withdrawal,
+    Interest,
+    Transfer,
+}
+
+/// A single saving entry
+pub struct Saving(pub transactions::Transaction);
+
+#[Object]
+impl Saving {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
+    async fn amount(&self) -> Decimal {
+        self.0.amount
+    }
+
+    async fn currency(&self) -> &str {
+        &self.0.currency
+    }
+
+    async fn kind(&self) -> TransactionKind {
+        self.0.kind.into()
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.tags
+    }
+
+    async fn source(&self) -> &str {
+        &self.0.source
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    /// Goals watching the source and currency of this saving
+    async fn goals(&self, ctx: &Context<'_>) -> Result<Vec<Goal>> {
+        let loader = ctx.data::<DataLoader<DbLoader>>()?;
+        let goals = loader
+            .load_one(SourceKey(self.0.source.clone()))
+            .await?
+            .unwrap_or_default();
+        Ok(goals
+            .into_iter()
+            .filter(|goal| goal.currency == self.0.currency)
+            .map(Goal)
+            .collect())
+    }
+}
+
+/// A savings target, backed by an alert threshold
+pub struct Goal(pub alerts::AlertThreshold);
+
+#[Object]
+impl Goal {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
+    async fn source(&self) -> &str {
+        &self.0.source
+    }
+
+    async fn currency(&self) -> &str {
+        &self.0.currency
+    }
+
+    async fn period(&self) -> AlertPeriod {
+        self.0.period.into()
+    }
+
+    async fn comparison(&self) -> AlertComparison {
+        self.0.comparison.into()
+    }
+
+    async fn threshold(&self) -> Decimal {
+        self.0.threshold
+    }
+
+    async fn hysteresis(&self) -> Decimal {
+        self.0.hysteresis
+    }
+
+    async fn state(&self) -> AlertState {
+        self.0.state.into()
+    }
+
+    async fn last_value(&self) -> Option<Decimal> {
+        self.0.last_value
+    }
+
+    async fn last_evaluated_at(&self) -> Option<DateTime<Utc>> {
+        self.0.last_evaluated_at
+    }
+
+    /// Alert history of this goal, oldest first
+    async fn alerts(&self, ctx: &Context<'_>) -> Result<Vec<AlertRecord>> {
+        let loader = ctx.data::<DataLoader<DbLoader>>()?;
+        let alerts = loader
+            .load_one(ThresholdId(self.0.id))
+            .await?
+            .unwrap_or_default();
+        Ok(alerts.into_iter().map(AlertRecord).collect())
+    }
+}
+
+pub struct AlertRecord(pub alerts::Alert);
+
+#[Object]
+impl AlertRecord {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
+    async fn kind(&self) -> AlertKind {
+        self.0.kind.into()
+    }
+
+    async fn value(&self) -> Decimal {
+        self.0.value
+    }
+
+    async fn threshold(&self) -> Decimal {
+        self.0.threshold
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct SourceSummary {
+    pub source: String,
+    pub currency: String,
+    /// Net of withdrawals, transfers don't count
+    pub total: Decimal,
+    pub count: i64,
+}
+
+impl From<transactions::SourceAggregate> for SourceSummary {
+    fn from(aggregate: transactions::SourceAggregate) -> Self {
+        Self {
+            source: aggregate.source,
+            currency: aggregate.currency,
+            total: aggregate.total,
+            count: aggregate.count,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct SavingsPage {
+    pub total_count: i64,
+    #[graphql(skip)]
+    pub items: Vec<transactions::Transaction>,
+}
+
+#[ComplexObject]
+impl SavingsPage {
+    async fn items(&self) -> Vec<Saving> {
+        self.items.iter().cloned().map(Saving).collect()
+    }
+}
+
+#[derive(InputObject, Default)]
+pub struct SavingsFilter {
+    pub source: Option<String>,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+impl From<SavingsFilter> for transactions::TransactionFilter {
+    fn from(filter: SavingsFilter) -> Self {
+        Self {
+            source: filter.source,
+            min_amount: filter.min_amount,
+            max_amount: filter.max_amount,
+            created_after: filter.created_after,
+            created_before: filter.created_before,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct CreateSavingInput {
+    pub amount: Decimal,
+    /// ISO 4217 code, defaults to USD
+    pub currency: Option<String>,
+    pub kind: Option<TransactionKind>,
+    pub tags: Option<Vec<String>>,
+    pub source: String,
+}
+
+impl From<CreateSavingInput> for transactions::CreateTransaction {
+    fn from(input: CreateSavingInput) -> Self {
+        let mut payload = Self::deposit(input.amount, input.source);
+        if let Some(currency) = input.currency {
+            payload.currency = currency;
+        }
+        if let Some(kind) = input.kind {
+            payload.kind = kind.into();
+        }
+        if let Some(tags) = input.tags {
+            payload.tags = tags;
+        }
+        payload
+    }
+}
+
+#[derive(InputObject)]
+pub struct UpdateSavingInput {
+    pub amount: Option<Decimal>,
+    pub source: Option<String>,
+}
+
+impl From<UpdateSavingInput> for transactions::UpdateTransaction {
+    fn from(input: UpdateSavingInput) -> Self {
+        Self {
+            amount: input.amount,
+            source: input.source,
+        }
+    }
+}
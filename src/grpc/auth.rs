@@ -0,0 +1,39 @@
+use crate::auth;
+use crate::config::Config;
+use std::sync::Arc;
+use tonic::{Request, Status};
+
+/// Metadata carrying the API key, named like the HTTP header
+const API_KEY_METADATA: &str = "x-api-key";
+
+/// Outcome of the API key check of a call, set by `api_key_interceptor`
+#[derive(Clone)]
+struct Authentication(Result<(), Status>);
+
+/// Interceptor checking the API key of every call against the configured
+/// ones. Only the calls requiring a key fail on it, see `require_api_key`.
+pub fn api_key_interceptor(
+    config: Arc<Config>,
+) -> impl FnMut(Request<()>) -> Result<Request<()>, Status> + Clone {
+    move |mut request: Request<()>| {
+        let result = auth::check_api_key(api_key(&request), &config).map_err(Status::from);
+        request.extensions_mut().insert(Authentication(result));
+        Ok(request)
+    }
+}
+
+/// Fail unless the call carries a valid API key. Calls that did not go
+/// through `api_key_interceptor` are denied.
+pub(crate) fn require_api_key<T>(request: &Request<T>) -> Result<(), Status> {
+    match request.extensions().get::<Authentication>() {
+        Some(Authentication(result)) => result.clone(),
+        None => Err(Status::unauthenticated("Authentication is not configured")),
+    }
+}
+
+pub(crate) fn api_key<T>(request: &Request<T>) -> Option<&str> {
+    request
+        .metadata()
+        .get(API_KEY_METADATA)
+        .and_then(|value| value.to_str().ok())
+}
@@ -0,0 +1,58 @@
+mod auth;
+mod service;
+mod status;
+
+pub mod proto {
+    tonic::include_proto!("savings.v1");
+}
+
+use crate::app::AppState;
+use proto::savings_server::SavingsServer;
+use std::sync::Arc;
+use tokio::net::{TcpListener, lookup_host};
+use tokio_util::sync::CancellationToken;
+use tonic::transport::server::TcpIncoming;
+
+pub use auth::api_key_interceptor;
+pub use service::SavingsGrpcService;
+
+/// Listen on `host`, a name or an address, trying each address it resolves
+/// to until one can be bound
+pub async fn bind(host: &str, port: u16) -> std::io::Result<TcpListener> {
+    let mut last_error = None;
+    for addr in lookup_host((host, port)).await? {
+        match TcpListener::bind(addr).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::AddrNotAvailable,
+            format!("{} resolves to no address", host),
+        )
+    }))
+}
+
+/// Run the gRPC server on `listener` until it fails or `shutdown` is
+/// cancelled, serving the storage of `state` with its API keys and rate
+/// limiter
+pub async fn serve(
+    state: AppState,
+    listener: TcpListener,
+    shutdown: CancellationToken,
+) -> Result<(), tonic::transport::Error> {
+    if let Ok(addr) = listener.local_addr() {
+        tracing::info!(%addr, "🚀 gRPC server running");
+    }
+
+    let service =
+        SavingsGrpcService::new(state.storage().clone()).with_rate_limiter(state.rate_limiter());
+    let interceptor = api_key_interceptor(Arc::new(state.config().clone()));
+
+    tonic::transport::Server::builder()
+        .add_service(SavingsServer::with_interceptor(service, interceptor))
+        .serve_with_incoming_shutdown(TcpIncoming::from(listener), shutdown.cancelled_owned())
+        .await
+}
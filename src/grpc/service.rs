@@ -0,0 +1,215 @@
+use crate::adapters::rate_limit::RateLimiter;
+use crate::errors::AppError;
+use crate::grpc::auth::{api_key, require_api_key};
+use crate::grpc::proto::{
+    CreateSavingRequest, DeleteSavingRequest, DeleteSavingResponse, GetSavingRequest,
+    ListSavingsRequest, ListSavingsResponse, Saving, StreamSavingsRequest, UpdateSavingRequest,
+    savings_server::{SERVICE_NAME, Savings},
+};
+use crate::models::transactions::{
+    CreateTransaction, Transaction, TransactionFilter, UpdateTransaction,
+};
+use crate::repositories::Storage;
+use crate::services::SavingsService;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use validator::Validate;
+
+const DEFAULT_LIST_LIMIT: i64 = 20;
+const MAX_LIST_LIMIT: i64 = 100;
+const STREAM_PAGE_SIZE: i64 = 100;
+const STREAM_BUFFER_SIZE: usize = 32;
+
+pub struct SavingsGrpcService {
+    db: Storage,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl SavingsGrpcService {
+    pub fn new(db: Storage) -> Self {
+        Self {
+            db,
+            rate_limiter: None,
+        }
+    }
+
+    /// Limit the writes like the HTTP routes, their routes are written
+    /// `POST /savings.v1.Savings/Method` in the rate limit rules
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Writes require an API key and a token from the caller's bucket
+    async fn authorize_write<T>(&self, request: &Request<T>, method: &str) -> Result<(), Status> {
+        require_api_key(request)?;
+
+        let Some(rate_limiter) = &self.rate_limiter else {
+            return Ok(());
+        };
+        let addr = request.remote_addr().map(|addr| addr.ip().to_string());
+        let client = rate_limiter.client(api_key(request), addr.as_deref());
+        let route = format!("POST /{}/{}", SERVICE_NAME, method);
+
+        match rate_limiter.take(&client, &route).await {
+            Some(status) if !status.is_allowed() => Err(AppError::TooManyRequests(status).into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Savings for SavingsGrpcService {
+    async fn create_saving(
+        &self,
+        request: Request<CreateSavingRequest>,
+    ) -> Result<Response<Saving>, Status> {
+        self.authorize_write(&request, "CreateSaving").await?;
+        let request = request.into_inner();
+        let payload = CreateTransaction::deposit(parse_amount(&request.amount)?, request.source);
+        payload.validate().map_err(AppError::from)?;
+
+        let transaction = SavingsService::create_new_saving(&self.db, &payload).await?;
+        Ok(Response::new(transaction.into()))
+    }
+
+    async fn get_saving(
+        &self,
+        request: Request<GetSavingRequest>,
+    ) -> Result<Response<Saving>, Status> {
+        let id = request.into_inner().id;
+        if id <= 0 {
+            return Err(Status::invalid_argument(
+                "Invalid ID: must be a positive integer",
+            ));
+        }
+
+        match SavingsService::get_by_id(&self.db, id).await? {
+            Some(transaction) => Ok(Response::new(transaction.into())),
+            None => Err(AppError::SavingNotFound(id).into()),
+        }
+    }
+
+    async fn list_savings(
+        &self,
+        request: Request<ListSavingsRequest>,
+    ) -> Result<Response<ListSavingsResponse>, Status> {
+        let request = request.into_inner();
+        let limit = match request.limit {
+            0 => DEFAULT_LIST_LIMIT,
+            limit => limit.clamp(1, MAX_LIST_LIMIT),
+        };
+        let filter = TransactionFilter {
+            source: request.source,
+            ..Default::default()
+        };
+
+        let savings =
+            SavingsService::search_savings(&self.db, &filter, limit, request.offset.max(0)).await?;
+        let total_count = SavingsService::count_savings(&self.db, &filter).await?;
+
+        Ok(Response::new(ListSavingsResponse {
+            savings: savings.into_iter().map(Saving::from).collect(),
+            total_count,
+        }))
+    }
+
+    async fn update_saving(
+        &self,
+        request: Request<UpdateSavingRequest>,
+    ) -> Result<Response<Saving>, Status> {
+        self.authorize_write(&request, "UpdateSaving").await?;
+        let request = request.into_inner();
+        let payload = UpdateTransaction {
+            amount: request.amount.as_deref().map(parse_amount).transpose()?,
+            source: request.source,
+        };
+        payload.validate().map_err(AppError::from)?;
+
+        let transaction = SavingsService::update_saving(&self.db, request.id, &payload).await?;
+        Ok(Response::new(transaction.into()))
+    }
+
+    async fn delete_saving(
+        &self,
+        request: Request<DeleteSavingRequest>,
+    ) -> Result<Response<DeleteSavingResponse>, Status> {
+        self.authorize_write(&request, "DeleteSaving").await?;
+        SavingsService::delete_saving(&self.db, request.into_inner().id).await?;
+        Ok(Response::new(DeleteSavingResponse {}))
+    }
+
+    type StreamSavingsStream = ReceiverStream<Result<Saving, Status>>;
+
+    async fn stream_savings(
+        &self,
+        request: Request<StreamSavingsRequest>,
+    ) -> Result<Response<Self::StreamSavingsStream>, Status> {
+        let filter = TransactionFilter {
+            source: request.into_inner().source,
+            ..Default::default()
+        };
+        let db = self.db.clone();
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+
+        tokio::spawn(async move {
+            let mut offset = 0;
+            loop {
+                let page =
+                    match SavingsService::search_savings(&db, &filter, STREAM_PAGE_SIZE, offset)
+                        .await
+                    {
+                        Ok(page) => page,
+                        Err(e) => {
+                            let _ = tx.send(Err(e.into())).await;
+                            return;
+                        }
+                    };
+
+                let page_len = page.len() as i64;
+                for transaction in page {
+                    // The client went away
+                    if tx.send(Ok(transaction.into())).await.is_err() {
+                        return;
+                    }
+                }
+
+                if page_len < STREAM_PAGE_SIZE {
+                    return;
+                }
+                offset += page_len;
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+fn parse_amount(amount: &str) -> Result<Decimal, Status> {
+    Decimal::from_str(amount.trim())
+        .map_err(|_| Status::invalid_argument(format!("Invalid amount: {}", amount)))
+}
+
+fn to_timestamp(value: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: value.timestamp(),
+        nanos: value.timestamp_subsec_nanos() as i32,
+    }
+}
+
+impl From<Transaction> for Saving {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            id: transaction.id,
+            amount: transaction.amount.to_string(),
+            source: transaction.source,
+            created_at: Some(to_timestamp(transaction.created_at)),
+            updated_at: Some(to_timestamp(transaction.updated_at)),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::adapters::rate_limit::whole_seconds;
+use crate::errors::AppError;
+use sqlx::error::ErrorKind;
+use tonic::{Code, Status};
+
+// Translate application errors into gRPC status codes, keeping the same
+// client-facing messages as the HTTP API
+impl From<AppError> for Status {
+    fn from(err: AppError) -> Self {
+        let code = match &err {
+            AppError::ValidationError(_)
+            | AppError::BadRequest(_)
+            | AppError::InvalidJson(_)
+            | AppError::InvalidPath(_)
+            | AppError::InvalidQuery(_)
+            | AppError::InvalidBody(_)
+            | AppError::UnsupportedMediaType(_)
+            | AppError::UnsupportedApiVersion(_) => Code::InvalidArgument,
+            AppError::PayloadTooLarge(_) => Code::ResourceExhausted,
+            AppError::NotFound(_)
+            | AppError::SavingNotFound(_)
+            | AppError::ThresholdNotFound(_) => Code::NotFound,
+            AppError::Unauthorized(_) => Code::Unauthenticated,
+            AppError::TooManyRequests(_) => Code::ResourceExhausted,
+            AppError::InternalServerError(_) => Code::Internal,
+            AppError::DatabaseError(e) => match e {
+                sqlx::Error::RowNotFound => Code::NotFound,
+                sqlx::Error::Database(db_err) => match db_err.kind() {
+                    ErrorKind::UniqueViolation => Code::AlreadyExists,
+                    ErrorKind::ForeignKeyViolation => Code::FailedPrecondition,
+                    ErrorKind::NotNullViolation => Code::InvalidArgument,
+                    _ => Code::Internal,
+                },
+                sqlx::Error::PoolTimedOut => Code::Unavailable,
+                _ => Code::Internal,
+            },
+        };
+
+        let retry_after = match &err {
+            AppError::TooManyRequests(status) => status.retry_after.map(whole_seconds),
+            _ => None,
+        };
+
+        let response = err.to_error_response();
+        let mut status = Status::new(code, response.detail);
+        // Same stable code as the HTTP API, for clients that branch on it
+        if let Ok(value) = response.code.as_str().parse() {
+            status.metadata_mut().insert("error-code", value);
+        }
+        // As the HTTP `Retry-After` header
+        if let Some(seconds) = retry_after {
+            status.metadata_mut().insert("retry-after", seconds.into());
+        }
+        status
+    }
+}
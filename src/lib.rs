@@ -1,6 +1,54 @@
-mod adapters;
-mod config;
-mod errors;
-mod models;
-mod routes;
-mod services;
+//! Savings push processing service, usable as a library to embed its HTTP
+//! application in another server or to test it:
+//!
+//! ```no_run
+//! use actix_web::HttpServer;
+//! use gsn_push_processing::{AppState, Config};
+//! use sqlx::PgPool;
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let pool = PgPool::connect_lazy("postgres://localhost/savings").unwrap();
+//! // Built once: every worker shares its rate limits, readiness and shutdown
+//! let state = AppState::new(Config::default(), pool).map_err(std::io::Error::other)?;
+//! HttpServer::new(move || state.app())
+//!     .bind(("127.0.0.1", 8080))?
+//!     .run()
+//!     .await
+//! # }
+//! ```
+//!
+//! `app_factory` builds a state of its own on every call, it is meant for a
+//! single application, such as one under `actix_web::test`.
+//!
+//! The items re-exported here are the stable API, the modules expose the
+//! rest of the service as is.
+
+pub mod adapters;
+pub mod app;
+pub mod auth;
+pub mod cli;
+pub mod config;
+pub mod dto;
+pub mod errors;
+pub mod graphql;
+pub mod grpc;
+pub mod models;
+pub mod repositories;
+pub mod routes;
+pub mod server;
+pub mod services;
+pub mod shutdown;
+pub mod workers;
+
+pub use adapters::db::DbPools;
+pub use app::{AppState, app_factory};
+pub use config::Config;
+pub use errors::{AppError, AppResult, ErrorCode, ErrorResponse};
+pub use models::transactions::{
+    CreateTransaction, ImportedTransaction, SourceAggregate, Transaction, TransactionFilter,
+    TransactionKind, UpdateTransaction,
+};
+pub use repositories::{
+    InMemorySavingsRepository, SavingsRepository, SqliteSavingsRepository, Storage,
+};
+pub use services::SavingsService;
@@ -0,0 +1,249 @@
+use crate::models::transactions::{DEFAULT_CURRENCY, validate_currency};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
+
+/// Time window the savings aggregate is computed over, starting at the
+/// beginning of the current day, week or month.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum AlertPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+impl AlertPeriod {
+    /// Unit accepted by Postgres `date_trunc`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertPeriod::Day => "day",
+            AlertPeriod::Week => "week",
+            AlertPeriod::Month => "month",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum AlertComparison {
+    Below,
+    Above,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum AlertState {
+    Ok,
+    Triggered,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum AlertKind {
+    Triggered,
+    Resolved,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AlertThreshold {
+    pub id: i64,
+    pub source: String,
+    pub currency: String,
+    pub period: AlertPeriod,
+    pub comparison: AlertComparison,
+    #[schema(value_type = String, format = "decimal", example = "200.0000")]
+    pub threshold: Decimal,
+    #[schema(value_type = String, format = "decimal", example = "20.0000")]
+    pub hysteresis: Decimal,
+    pub state: AlertState,
+    #[schema(value_type = Option<String>, format = "decimal")]
+    pub last_value: Option<Decimal>,
+    pub last_evaluated_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AlertThreshold {
+    /// Compute the state for a freshly aggregated value.
+    ///
+    /// An alert triggers as soon as the value crosses the threshold, but only
+    /// resolves once it moves back past the threshold by at least `hysteresis`,
+    /// so values hovering around the limit don't flap between states.
+    pub fn next_state(&self, value: Decimal) -> AlertState {
+        match (self.state, self.comparison) {
+            (AlertState::Ok, AlertComparison::Below) if value < self.threshold => {
+                AlertState::Triggered
+            }
+            (AlertState::Ok, AlertComparison::Above) if value > self.threshold => {
+                AlertState::Triggered
+            }
+            (AlertState::Triggered, AlertComparison::Below)
+                if value >= self.threshold + self.hysteresis =>
+            {
+                AlertState::Ok
+            }
+            (AlertState::Triggered, AlertComparison::Above)
+                if value <= self.threshold - self.hysteresis =>
+            {
+                AlertState::Ok
+            }
+            (state, _) => state,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Alert {
+    pub id: i64,
+    pub threshold_id: i64,
+    pub kind: AlertKind,
+    #[schema(value_type = String, format = "decimal")]
+    pub value: Decimal,
+    #[schema(value_type = String, format = "decimal")]
+    pub threshold: Decimal,
+    pub created_at: DateTime<Utc>,
+}
+
+fn default_currency() -> String {
+    DEFAULT_CURRENCY.to_string()
+}
+
+// Custom validator for Decimal amounts that may be zero
+fn validate_non_negative_amount(amount: &Decimal) -> Result<(), ValidationError> {
+    if *amount < Decimal::ZERO {
+        return Err(ValidationError::new("amount_must_not_be_negative"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreateAlertThreshold {
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "Source must be between 1 and 255 characters"
+    ))]
+    #[schema(min_length = 1, max_length = 255)]
+    pub source: String,
+
+    /// Only savings in this currency count towards the threshold
+    #[validate(custom(
+        function = "validate_currency",
+        message = "Currency must be an ISO 4217 code, such as USD"
+    ))]
+    #[serde(default = "default_currency")]
+    #[schema(min_length = 3, max_length = 3, default = "USD")]
+    pub currency: String,
+
+    pub period: AlertPeriod,
+
+    pub comparison: AlertComparison,
+
+    #[validate(custom(
+        function = "validate_non_negative_amount",
+        message = "Threshold must not be negative"
+    ))]
+    #[schema(value_type = f64, minimum = 0, example = 200)]
+    pub threshold: Decimal,
+
+    #[validate(custom(
+        function = "validate_non_negative_amount",
+        message = "Hysteresis must not be negative"
+    ))]
+    #[serde(default)]
+    #[schema(value_type = f64, minimum = 0, default = 0)]
+    pub hysteresis: Decimal,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct AlertsQuery {
+    /// Only return alerts with an id greater than this one
+    pub since_id: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threshold(comparison: AlertComparison, state: AlertState) -> AlertThreshold {
+        AlertThreshold {
+            id: 1,
+            source: "bank".to_string(),
+            currency: DEFAULT_CURRENCY.to_string(),
+            period: AlertPeriod::Month,
+            comparison,
+            threshold: Decimal::from(100),
+            hysteresis: Decimal::from(10),
+            state,
+            last_value: None,
+            last_evaluated_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn triggers_once_the_value_crosses_the_threshold() {
+        let below = threshold(AlertComparison::Below, AlertState::Ok);
+        assert_eq!(below.next_state(Decimal::from(100)), AlertState::Ok);
+        assert_eq!(below.next_state(Decimal::from(99)), AlertState::Triggered);
+
+        let above = threshold(AlertComparison::Above, AlertState::Ok);
+        assert_eq!(above.next_state(Decimal::from(100)), AlertState::Ok);
+        assert_eq!(above.next_state(Decimal::from(101)), AlertState::Triggered);
+    }
+
+    #[test]
+    fn stays_triggered_inside_the_hysteresis_band() {
+        let below = threshold(AlertComparison::Below, AlertState::Triggered);
+        for value in [50, 99, 100, 105, 109] {
+            assert_eq!(
+                below.next_state(Decimal::from(value)),
+                AlertState::Triggered,
+                "{}",
+                value
+            );
+        }
+
+        let above = threshold(AlertComparison::Above, AlertState::Triggered);
+        for value in [150, 101, 100, 95, 91] {
+            assert_eq!(
+                above.next_state(Decimal::from(value)),
+                AlertState::Triggered,
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn resolves_once_the_value_leaves_the_hysteresis_band() {
+        let below = threshold(AlertComparison::Below, AlertState::Triggered);
+        assert_eq!(below.next_state(Decimal::from(110)), AlertState::Ok);
+        assert_eq!(below.next_state(Decimal::from(500)), AlertState::Ok);
+
+        let above = threshold(AlertComparison::Above, AlertState::Triggered);
+        assert_eq!(above.next_state(Decimal::from(90)), AlertState::Ok);
+        assert_eq!(above.next_state(Decimal::from(-5)), AlertState::Ok);
+    }
+
+    #[test]
+    fn resolves_at_the_threshold_without_hysteresis() {
+        let mut below = threshold(AlertComparison::Below, AlertState::Triggered);
+        below.hysteresis = Decimal::ZERO;
+        assert_eq!(below.next_state(Decimal::from(99)), AlertState::Triggered);
+        assert_eq!(below.next_state(Decimal::from(100)), AlertState::Ok);
+    }
+}
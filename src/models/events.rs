@@ -0,0 +1,85 @@
+use crate::models::alerts::{Alert, AlertThreshold};
+use crate::models::transactions::Transaction;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, types::Json};
+use utoipa::IntoParams;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum ChangeOperation {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl ChangeOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeOperation::Created => "created",
+            ChangeOperation::Updated => "updated",
+            ChangeOperation::Deleted => "deleted",
+        }
+    }
+}
+
+/// A change on `transactions`, recorded and published by the database trigger
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TransactionEvent {
+    pub id: i64,
+    pub operation: ChangeOperation,
+    pub transaction_id: i64,
+    pub source: String,
+    pub payload: Json<Transaction>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A change on `alert_thresholds`, published by the database trigger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalEvent {
+    pub operation: ChangeOperation,
+    pub payload: AlertThreshold,
+}
+
+/// Realtime topics clients can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Topic {
+    Savings,
+    Goals,
+    Notifications,
+}
+
+/// Any change published on the change feed
+#[derive(Debug, Clone)]
+pub enum FeedEvent {
+    Savings(TransactionEvent),
+    Goals(GoalEvent),
+    Notifications(Alert),
+    /// The listener reconnected to the database, changes published while it
+    /// was disconnected were missed
+    Resync,
+}
+
+impl FeedEvent {
+    /// Topic of a change, `None` for `Resync` which concerns every topic
+    pub fn topic(&self) -> Option<Topic> {
+        match self {
+            FeedEvent::Savings(_) => Some(Topic::Savings),
+            FeedEvent::Goals(_) => Some(Topic::Goals),
+            FeedEvent::Notifications(_) => Some(Topic::Notifications),
+            FeedEvent::Resync => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct StreamQuery {
+    /// Only stream changes for this source
+    pub source: Option<String>,
+    /// Resume after this event id, used when the `Last-Event-ID` header can't be set
+    pub last_event_id: Option<i64>,
+}
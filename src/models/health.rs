@@ -0,0 +1,67 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentStatus {
+    Up,
+    Down,
+}
+
+/// Result of checking a single dependency of the service
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ComponentCheck {
+    pub status: ComponentStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl ComponentCheck {
+    pub fn up() -> Self {
+        Self {
+            status: ComponentStatus::Up,
+            latency_ms: None,
+            error: None,
+            details: None,
+        }
+    }
+
+    pub fn down(error: impl Into<String>) -> Self {
+        Self {
+            status: ComponentStatus::Down,
+            error: Some(error.into()),
+            ..Self::up()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReadinessReport {
+    pub status: ComponentStatus,
+    pub components: BTreeMap<String, ComponentCheck>,
+}
+
+impl ReadinessReport {
+    /// The service is ready only when every component is up
+    pub fn new(components: BTreeMap<String, ComponentCheck>) -> Self {
+        let status = if components
+            .values()
+            .all(|check| check.status == ComponentStatus::Up)
+        {
+            ComponentStatus::Up
+        } else {
+            ComponentStatus::Down
+        };
+
+        Self { status, components }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.status == ComponentStatus::Up
+    }
+}
@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A migration recorded as applied in the database
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub installed_on: DateTime<Utc>,
+    pub success: bool,
+    pub execution_time_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MigrationStatus {
+    pub applied: Vec<AppliedMigration>,
+    /// Versions embedded in this build that are not applied yet
+    pub pending: Vec<i64>,
+}
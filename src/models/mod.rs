@@ -1 +1,6 @@
+pub mod alerts;
+pub mod events;
+pub mod health;
+pub mod migrations;
+pub mod realtime;
 pub mod transactions;
@@ -0,0 +1,115 @@
+use crate::adapters::versioning::ApiVersion;
+use crate::dto::VersionedSaving;
+use crate::models::events::{ChangeOperation, FeedEvent, Topic};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Messages accepted from WebSocket clients
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ClientMessage {
+    Subscribe { topics: Vec<Topic> },
+    Unsubscribe { topics: Vec<Topic> },
+    Ping,
+}
+
+/// Messages sent to WebSocket clients
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ServerMessage {
+    /// Current subscriptions after a subscribe or unsubscribe
+    Subscriptions {
+        topics: Vec<Topic>,
+    },
+    Pong,
+    Event {
+        topic: Topic,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        operation: Option<ChangeOperation>,
+        data: Value,
+    },
+    Error {
+        error: String,
+    },
+}
+
+impl ServerMessage {
+    /// Message for a change, savings in the representation of `version`
+    pub fn event(event: &FeedEvent, version: ApiVersion) -> Self {
+        let (topic, operation, data) = match event {
+            FeedEvent::Savings(e) => (
+                Topic::Savings,
+                Some(e.operation),
+                serde_json::to_value(VersionedSaving::new(version, e.payload.0.clone())),
+            ),
+            FeedEvent::Goals(e) => (
+                Topic::Goals,
+                Some(e.operation),
+                serde_json::to_value(&e.payload),
+            ),
+            FeedEvent::Notifications(alert) => {
+                (Topic::Notifications, None, serde_json::to_value(alert))
+            }
+            FeedEvent::Resync => {
+                return ServerMessage::Error {
+                    error: "Missed events while reconnecting to the database".to_string(),
+                };
+            }
+        };
+
+        ServerMessage::Event {
+            topic,
+            operation,
+            data: data.unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::events::TransactionEvent;
+    use crate::models::transactions::{Transaction, TransactionKind};
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use sqlx::types::Json;
+
+    fn saving_created() -> FeedEvent {
+        FeedEvent::Savings(TransactionEvent {
+            id: 1,
+            operation: ChangeOperation::Created,
+            transaction_id: 7,
+            source: "bank".to_string(),
+            payload: Json(Transaction {
+                id: 7,
+                amount: Decimal::from(5),
+                currency: "CHF".to_string(),
+                kind: TransactionKind::Deposit,
+                tags: vec!["salary".to_string()],
+                source: "bank".to_string(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            }),
+            created_at: Utc::now(),
+        })
+    }
+
+    fn data(message: ServerMessage) -> Value {
+        match message {
+            ServerMessage::Event { data, .. } => data,
+            other => panic!("not an event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sends_savings_in_the_negotiated_version() {
+        let v1 = data(ServerMessage::event(&saving_created(), ApiVersion::V1));
+        let v2 = data(ServerMessage::event(&saving_created(), ApiVersion::V2));
+
+        assert_eq!(v1["id"], 7);
+        assert!(v1.get("currency").is_none());
+        assert!(v1.get("tags").is_none());
+        assert_eq!(v2["currency"], "CHF");
+        assert_eq!(v2["tags"][0], "salary");
+    }
+}
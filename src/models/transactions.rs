@@ -1,34 +1,256 @@
 use chrono::{DateTime, Utc};
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 use validator::{Validate, ValidationError};
 
+/// Currency of the transactions recorded before currencies existed, and of
+/// those created without one
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+/// Scale of amounts, stored as `DECIMAL(19, 4)` in Postgres
+pub(crate) const AMOUNT_SCALE: u32 = 4;
+
+/// Amounts have at most 15 digits before the decimal point
+const MAX_AMOUNT: i64 = 1_000_000_000_000_000;
+
+/// Tags a single transaction may carry
+const MAX_TAGS: usize = 20;
+const MAX_TAG_LENGTH: usize = 50;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum TransactionKind {
+    #[default]
+    Deposit,
+    Withdrawal,
+    Interest,
+    Transfer,
+}
+
+impl TransactionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionKind::Deposit => "deposit",
+            TransactionKind::Withdrawal => "withdrawal",
+            TransactionKind::Interest => "interest",
+            TransactionKind::Transfer => "transfer",
+        }
+    }
+
+    /// Contribution of `amount` to savings totals: withdrawals reduce them,
+    /// transfers only move money between the owner's accounts
+    pub fn signed(&self, amount: Decimal) -> Decimal {
+        match self {
+            TransactionKind::Deposit | TransactionKind::Interest => amount,
+            TransactionKind::Withdrawal => -amount,
+            TransactionKind::Transfer => Decimal::ZERO,
+        }
+    }
+}
+
+/// `TransactionKind::signed` of the `amount` column, in SQL
+pub(crate) const SIGNED_AMOUNT_SQL: &str =
+    "CASE kind WHEN 'withdrawal' THEN -amount WHEN 'transfer' THEN 0 ELSE amount END";
+
+/// A transaction as stored. The API serves it through the representation of
+/// each version, see `dto`.
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Transaction {
     pub id: i64,
     pub amount: rust_decimal::Decimal,
+    // Defaults for the change events recorded before these columns existed
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default)]
+    pub kind: TransactionKind,
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub source: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Per-source savings totals in one currency, net of withdrawals. Counts
+/// every transaction, transfers included.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SourceAggregate {
+    pub source: String,
+    pub currency: String,
+    #[schema(value_type = String, format = "decimal")]
+    pub total: Decimal,
+    pub count: i64,
+}
+
+/// A transaction read back from an export, the id is assigned again on insert.
+/// Other exported fields, such as the id, are ignored.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ImportedTransaction {
+    #[validate(
+        custom(
+            function = "validate_positive_amount",
+            message = "Amount must be greater than 0"
+        ),
+        custom(
+            function = "validate_storable_amount",
+            message = "Amount must be less than 1000000000000000"
+        )
+    )]
+    pub amount: Decimal,
+
+    #[serde(default = "default_currency")]
+    #[validate(custom(
+        function = "validate_currency",
+        message = "Currency must be an ISO 4217 code, such as USD"
+    ))]
+    pub currency: String,
+
+    #[serde(default)]
+    pub kind: TransactionKind,
+
+    #[serde(default)]
+    #[validate(custom(
+        function = "validate_tags",
+        message = "At most 20 tags of 1 to 50 characters are allowed"
+    ))]
+    pub tags: Vec<String>,
+
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "Source must be between 1 and 255 characters"
+    ))]
+    pub source: String,
+
+    /// Defaults to the time of the import
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportSummary {
+    pub imported: u64,
+}
+
+impl From<&ImportedTransaction> for CreateTransaction {
+    fn from(record: &ImportedTransaction) -> Self {
+        Self {
+            amount: record.amount,
+            currency: record.currency.clone(),
+            kind: record.kind,
+            tags: record.tags.clone(),
+            source: record.source.clone(),
+        }
+    }
+}
+
+/// Optional criteria for searching transactions, unset fields match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransactionFilter {
+    pub source: Option<String>,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+impl TransactionFilter {
+    /// Whether `transaction` meets every criterion, bounds on amounts are
+    /// inclusive and the creation range excludes its end
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        self.source
+            .as_ref()
+            .is_none_or(|source| &transaction.source == source)
+            && self.matches_amount(transaction.amount)
+            && self
+                .created_after
+                .is_none_or(|created_after| transaction.created_at >= created_after)
+            && self
+                .created_before
+                .is_none_or(|created_before| transaction.created_at < created_before)
+    }
+
+    pub fn matches_amount(&self, amount: Decimal) -> bool {
+        self.min_amount
+            .is_none_or(|min_amount| amount >= min_amount)
+            && self
+                .max_amount
+                .is_none_or(|max_amount| amount <= max_amount)
+    }
+
+    pub fn bounds_amount(&self) -> bool {
+        self.min_amount.is_some() || self.max_amount.is_some()
+    }
+}
+
+fn default_currency() -> String {
+    DEFAULT_CURRENCY.to_string()
+}
+
 // Custom validator for Decimal amounts
-fn validate_positive_amount(amount: &Decimal) -> Result<(), ValidationError> {
+pub(crate) fn validate_positive_amount(amount: &Decimal) -> Result<(), ValidationError> {
     if *amount <= Decimal::ZERO {
         return Err(ValidationError::new("amount_must_be_positive"));
     }
     Ok(())
 }
 
+// Amounts out of `DECIMAL(19, 4)` range are rejected by every backend alike,
+// once rounded to its scale as Postgres does
+pub(crate) fn validate_storable_amount(amount: &Decimal) -> Result<(), ValidationError> {
+    let stored =
+        amount.round_dp_with_strategy(AMOUNT_SCALE, RoundingStrategy::MidpointAwayFromZero);
+    if stored.abs() >= Decimal::from(MAX_AMOUNT) {
+        return Err(ValidationError::new("amount_out_of_range"));
+    }
+    Ok(())
+}
+
+// Currencies are ISO 4217 alphabetic codes
+pub(crate) fn validate_currency(currency: &str) -> Result<(), ValidationError> {
+    if currency.len() != 3 || !currency.bytes().all(|b| b.is_ascii_uppercase()) {
+        return Err(ValidationError::new("invalid_currency"));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_tags(tags: &[String]) -> Result<(), ValidationError> {
+    let valid_tag = |tag: &String| (1..=MAX_TAG_LENGTH).contains(&tag.chars().count());
+    if tags.len() > MAX_TAGS || !tags.iter().all(valid_tag) {
+        return Err(ValidationError::new("invalid_tags"));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
-#[serde(deny_unknown_fields)]
 pub struct CreateTransaction {
+    #[validate(
+        custom(
+            function = "validate_positive_amount",
+            message = "Amount must be greater than 0"
+        ),
+        custom(
+            function = "validate_storable_amount",
+            message = "Amount must be less than 1000000000000000"
+        )
+    )]
+    pub amount: Decimal,
+
     #[validate(custom(
-        function = "validate_positive_amount",
-        message = "Amount must be greater than 0"
+        function = "validate_currency",
+        message = "Currency must be an ISO 4217 code, such as USD"
     ))]
-    pub amount: Decimal,
+    pub currency: String,
+
+    pub kind: TransactionKind,
+
+    #[validate(custom(
+        function = "validate_tags",
+        message = "At most 20 tags of 1 to 50 characters are allowed"
+    ))]
+    pub tags: Vec<String>,
 
     #[validate(length(
         min = 1,
@@ -38,13 +260,33 @@ pub struct CreateTransaction {
     pub source: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+impl CreateTransaction {
+    /// A deposit in the default currency, without tags
+    pub fn deposit(amount: Decimal, source: String) -> Self {
+        Self {
+            amount,
+            currency: default_currency(),
+            kind: TransactionKind::Deposit,
+            tags: Vec::new(),
+            source,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct UpdateTransaction {
-    #[validate(custom(
-        function = "validate_positive_amount",
-        message = "Amount must be greater than 0"
-    ))]
+    #[validate(
+        custom(
+            function = "validate_positive_amount",
+            message = "Amount must be greater than 0"
+        ),
+        custom(
+            function = "validate_storable_amount",
+            message = "Amount must be less than 1000000000000000"
+        )
+    )]
+    #[schema(value_type = Option<f64>, exclusive_minimum = 0, exclusive_maximum = 1e15)]
     pub amount: Option<Decimal>,
 
     #[validate(length(
@@ -52,5 +294,6 @@ pub struct UpdateTransaction {
         max = 255,
         message = "Source must be between 1 and 255 characters"
     ))]
+    #[schema(min_length = 1, max_length = 255)]
     pub source: Option<String>,
 }
@@ -0,0 +1,221 @@
+use crate::errors::AppResult;
+use crate::models::events::{ChangeOperation, TransactionEvent};
+use crate::models::transactions::{
+    CreateTransaction, ImportedTransaction, SourceAggregate, Transaction, TransactionFilter,
+    UpdateTransaction,
+};
+use crate::repositories::{SavingsRepository, stored_amount, stored_time};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::types::Json;
+use std::collections::{BTreeMap, btree_map::Entry};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+#[derive(Default)]
+struct State {
+    transactions: BTreeMap<i64, Transaction>,
+    events: Vec<TransactionEvent>,
+    last_transaction_id: i64,
+    last_event_id: i64,
+}
+
+impl State {
+    fn next_transaction_id(&mut self) -> i64 {
+        self.last_transaction_id += 1;
+        self.last_transaction_id
+    }
+
+    /// Record a change the way the database trigger does
+    fn record(&mut self, operation: ChangeOperation, transaction: &Transaction) {
+        self.last_event_id += 1;
+        let event = TransactionEvent {
+            id: self.last_event_id,
+            operation,
+            transaction_id: transaction.id,
+            source: transaction.source.clone(),
+            payload: Json(transaction.clone()),
+            created_at: now(),
+        };
+        self.events.push(event);
+    }
+
+    fn matching<'a>(
+        &'a self,
+        filter: &'a TransactionFilter,
+    ) -> impl Iterator<Item = &'a Transaction> + 'a {
+        self.transactions
+            .values()
+            .filter(move |transaction| filter.matches(transaction))
+    }
+}
+
+/// Savings kept in memory, for tests and for running without a database.
+/// Behaves as the Postgres storage does, amounts included: they are rounded
+/// to 4 decimal places and timestamps to microseconds.
+#[derive(Default)]
+pub struct InMemorySavingsRepository {
+    state: Mutex<State>,
+}
+
+impl InMemorySavingsRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl SavingsRepository for InMemorySavingsRepository {
+    async fn insert(&self, payload: &CreateTransaction) -> AppResult<Transaction> {
+        let mut state = self.state();
+        let now = now();
+        let transaction = Transaction {
+            id: state.next_transaction_id(),
+            amount: stored_amount(payload.amount),
+            currency: payload.currency.clone(),
+            kind: payload.kind,
+            tags: payload.tags.clone(),
+            source: payload.source.clone(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        state.record(ChangeOperation::Created, &transaction);
+        state
+            .transactions
+            .insert(transaction.id, transaction.clone());
+        Ok(transaction)
+    }
+
+    async fn insert_many(&self, records: &[ImportedTransaction]) -> AppResult<u64> {
+        let mut state = self.state();
+        let now = now();
+
+        for record in records {
+            let created_at = record.created_at.map(stored_time).unwrap_or(now);
+            let transaction = Transaction {
+                id: state.next_transaction_id(),
+                amount: stored_amount(record.amount),
+                currency: record.currency.clone(),
+                kind: record.kind,
+                tags: record.tags.clone(),
+                source: record.source.clone(),
+                created_at,
+                updated_at: created_at,
+            };
+
+            state.record(ChangeOperation::Created, &transaction);
+            state.transactions.insert(transaction.id, transaction);
+        }
+        Ok(records.len() as u64)
+    }
+
+    async fn find(&self, id: i64) -> AppResult<Option<Transaction>> {
+        Ok(self.state().transactions.get(&id).cloned())
+    }
+
+    async fn search(
+        &self,
+        filter: &TransactionFilter,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<Transaction>> {
+        let state = self.state();
+        let mut transactions: Vec<&Transaction> = state.matching(filter).collect();
+        transactions.sort_by(|a, b| {
+            b.created_at
+                .cmp(&a.created_at)
+                .then_with(|| b.id.cmp(&a.id))
+        });
+
+        Ok(transactions
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn count(&self, filter: &TransactionFilter) -> AppResult<i64> {
+        Ok(self.state().matching(filter).count() as i64)
+    }
+
+    async fn source_aggregates(
+        &self,
+        filter: &TransactionFilter,
+    ) -> AppResult<Vec<SourceAggregate>> {
+        let state = self.state();
+        let mut aggregates: BTreeMap<(&str, &str), SourceAggregate> = BTreeMap::new();
+        for transaction in state.matching(filter) {
+            let aggregate = aggregates
+                .entry((&transaction.source, &transaction.currency))
+                .or_insert_with(|| SourceAggregate {
+                    source: transaction.source.clone(),
+                    currency: transaction.currency.clone(),
+                    total: stored_amount(Decimal::ZERO),
+                    count: 0,
+                });
+            aggregate.total += transaction.kind.signed(transaction.amount);
+            aggregate.count += 1;
+        }
+
+        Ok(aggregates.into_values().collect())
+    }
+
+    async fn update(&self, id: i64, payload: &UpdateTransaction) -> AppResult<Option<Transaction>> {
+        let mut state = self.state();
+        let Some(transaction) = state.transactions.get_mut(&id) else {
+            return Ok(None);
+        };
+
+        if let Some(amount) = payload.amount {
+            transaction.amount = stored_amount(amount);
+        }
+        if let Some(source) = &payload.source {
+            transaction.source = source.clone();
+        }
+        transaction.updated_at = now();
+
+        let transaction = transaction.clone();
+        state.record(ChangeOperation::Updated, &transaction);
+        Ok(Some(transaction))
+    }
+
+    async fn delete(&self, id: i64) -> AppResult<bool> {
+        let mut state = self.state();
+        let Entry::Occupied(entry) = state.transactions.entry(id) else {
+            return Ok(false);
+        };
+
+        let transaction = entry.remove();
+        state.record(ChangeOperation::Deleted, &transaction);
+        Ok(true)
+    }
+
+    async fn events_since(
+        &self,
+        after_id: i64,
+        source: Option<&str>,
+        limit: i64,
+    ) -> AppResult<Vec<TransactionEvent>> {
+        Ok(self
+            .state()
+            .events
+            .iter()
+            .filter(|event| event.id > after_id)
+            .filter(|event| source.is_none_or(|source| event.source == source))
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn last_event_id(&self) -> AppResult<i64> {
+        Ok(self.state().events.last().map_or(0, |event| event.id))
+    }
+}
+
+fn now() -> DateTime<Utc> {
+    stored_time(Utc::now())
+}
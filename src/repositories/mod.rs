@@ -0,0 +1,224 @@
+mod memory;
+mod postgres;
+mod sqlite;
+
+pub use memory::InMemorySavingsRepository;
+pub use sqlite::SqliteSavingsRepository;
+
+use crate::adapters::cache::Cache;
+use crate::adapters::db::DbPools;
+use crate::errors::AppResult;
+use crate::models::events::TransactionEvent;
+use crate::models::transactions::{
+    AMOUNT_SCALE, CreateTransaction, ImportedTransaction, SourceAggregate, Transaction,
+    TransactionFilter, UpdateTransaction,
+};
+use chrono::{DateTime, SubsecRound, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::PgPool;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Storage of savings and of their change events. Payloads are validated
+/// by `SavingsService` beforehand.
+///
+/// Every implementation lists savings newest first, ties broken by the
+/// newest id, and events oldest first. A missing saving is `None` or
+/// `false`, never an error. `DbPools` stores them in Postgres, `Storage`
+/// in the database selected by `database_url`.
+pub trait SavingsRepository: Send + Sync {
+    /// Cache in front of the reads, if any
+    fn cache(&self) -> Option<&Arc<dyn Cache>> {
+        None
+    }
+
+    fn insert(
+        &self,
+        payload: &CreateTransaction,
+    ) -> impl Future<Output = AppResult<Transaction>> + Send;
+
+    /// Insert every record or none of them. Missing creation dates default
+    /// to now.
+    fn insert_many(
+        &self,
+        records: &[ImportedTransaction],
+    ) -> impl Future<Output = AppResult<u64>> + Send;
+
+    fn find(&self, id: i64) -> impl Future<Output = AppResult<Option<Transaction>>> + Send;
+
+    fn search(
+        &self,
+        filter: &TransactionFilter,
+        limit: i64,
+        offset: i64,
+    ) -> impl Future<Output = AppResult<Vec<Transaction>>> + Send;
+
+    fn count(&self, filter: &TransactionFilter) -> impl Future<Output = AppResult<i64>> + Send;
+
+    /// Total and count per source, ordered by source
+    fn source_aggregates(
+        &self,
+        filter: &TransactionFilter,
+    ) -> impl Future<Output = AppResult<Vec<SourceAggregate>>> + Send;
+
+    /// Apply the fields set in `payload`, refreshing `updated_at`
+    fn update(
+        &self,
+        id: i64,
+        payload: &UpdateTransaction,
+    ) -> impl Future<Output = AppResult<Option<Transaction>>> + Send;
+
+    /// Whether the saving existed
+    fn delete(&self, id: i64) -> impl Future<Output = AppResult<bool>> + Send;
+
+    /// Change events after `after_id`, optionally of a single source
+    fn events_since(
+        &self,
+        after_id: i64,
+        source: Option<&str>,
+        limit: i64,
+    ) -> impl Future<Output = AppResult<Vec<TransactionEvent>>> + Send;
+
+    /// Id of the latest change event, 0 without any
+    fn last_event_id(&self) -> impl Future<Output = AppResult<i64>> + Send;
+}
+
+/// Where the service keeps its data, selected by `database_url`. Alerts,
+/// GraphQL and the change notifications are only available with Postgres.
+#[derive(Clone)]
+pub enum Storage {
+    Postgres(DbPools),
+    Sqlite(SqliteSavingsRepository),
+}
+
+impl Storage {
+    pub fn postgres(&self) -> Option<&DbPools> {
+        match self {
+            Storage::Postgres(pools) => Some(pools),
+            Storage::Sqlite(_) => None,
+        }
+    }
+}
+
+impl From<DbPools> for Storage {
+    fn from(pools: DbPools) -> Self {
+        Storage::Postgres(pools)
+    }
+}
+
+impl From<PgPool> for Storage {
+    fn from(pool: PgPool) -> Self {
+        Storage::Postgres(pool.into())
+    }
+}
+
+impl From<SqliteSavingsRepository> for Storage {
+    fn from(repo: SqliteSavingsRepository) -> Self {
+        Storage::Sqlite(repo)
+    }
+}
+
+impl SavingsRepository for Storage {
+    fn cache(&self) -> Option<&Arc<dyn Cache>> {
+        match self {
+            Storage::Postgres(pools) => SavingsRepository::cache(pools),
+            Storage::Sqlite(repo) => repo.cache(),
+        }
+    }
+
+    async fn insert(&self, payload: &CreateTransaction) -> AppResult<Transaction> {
+        match self {
+            Storage::Postgres(pools) => pools.insert(payload).await,
+            Storage::Sqlite(repo) => repo.insert(payload).await,
+        }
+    }
+
+    async fn insert_many(&self, records: &[ImportedTransaction]) -> AppResult<u64> {
+        match self {
+            Storage::Postgres(pools) => pools.insert_many(records).await,
+            Storage::Sqlite(repo) => repo.insert_many(records).await,
+        }
+    }
+
+    async fn find(&self, id: i64) -> AppResult<Option<Transaction>> {
+        match self {
+            Storage::Postgres(pools) => pools.find(id).await,
+            Storage::Sqlite(repo) => repo.find(id).await,
+        }
+    }
+
+    async fn search(
+        &self,
+        filter: &TransactionFilter,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<Transaction>> {
+        match self {
+            Storage::Postgres(pools) => pools.search(filter, limit, offset).await,
+            Storage::Sqlite(repo) => repo.search(filter, limit, offset).await,
+        }
+    }
+
+    async fn count(&self, filter: &TransactionFilter) -> AppResult<i64> {
+        match self {
+            Storage::Postgres(pools) => pools.count(filter).await,
+            Storage::Sqlite(repo) => repo.count(filter).await,
+        }
+    }
+
+    async fn source_aggregates(
+        &self,
+        filter: &TransactionFilter,
+    ) -> AppResult<Vec<SourceAggregate>> {
+        match self {
+            Storage::Postgres(pools) => pools.source_aggregates(filter).await,
+            Storage::Sqlite(repo) => repo.source_aggregates(filter).await,
+        }
+    }
+
+    async fn update(&self, id: i64, payload: &UpdateTransaction) -> AppResult<Option<Transaction>> {
+        match self {
+            Storage::Postgres(pools) => pools.update(id, payload).await,
+            Storage::Sqlite(repo) => repo.update(id, payload).await,
+        }
+    }
+
+    async fn delete(&self, id: i64) -> AppResult<bool> {
+        match self {
+            Storage::Postgres(pools) => pools.delete(id).await,
+            Storage::Sqlite(repo) => repo.delete(id).await,
+        }
+    }
+
+    async fn events_since(
+        &self,
+        after_id: i64,
+        source: Option<&str>,
+        limit: i64,
+    ) -> AppResult<Vec<TransactionEvent>> {
+        match self {
+            Storage::Postgres(pools) => pools.events_since(after_id, source, limit).await,
+            Storage::Sqlite(repo) => repo.events_since(after_id, source, limit).await,
+        }
+    }
+
+    async fn last_event_id(&self) -> AppResult<i64> {
+        match self {
+            Storage::Postgres(pools) => pools.last_event_id().await,
+            Storage::Sqlite(repo) => repo.last_event_id().await,
+        }
+    }
+}
+
+/// `amount` as `DECIMAL(19, 4)` stores it
+fn stored_amount(amount: Decimal) -> Decimal {
+    let mut amount =
+        amount.round_dp_with_strategy(AMOUNT_SCALE, RoundingStrategy::MidpointAwayFromZero);
+    amount.rescale(AMOUNT_SCALE);
+    amount
+}
+
+/// `time` as `TIMESTAMPTZ` stores it
+fn stored_time(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.trunc_subsecs(6)
+}
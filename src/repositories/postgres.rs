@@ -0,0 +1,214 @@
+use crate::adapters::cache::Cache;
+use crate::adapters::db::DbPools;
+use crate::errors::{AppError, AppResult};
+use crate::models::events::TransactionEvent;
+use crate::models::transactions::{
+    CreateTransaction, ImportedTransaction, SIGNED_AMOUNT_SQL, SourceAggregate, Transaction,
+    TransactionFilter, UpdateTransaction,
+};
+use crate::repositories::SavingsRepository;
+use crate::services::MaintenanceService;
+use std::sync::Arc;
+
+impl SavingsRepository for DbPools {
+    fn cache(&self) -> Option<&Arc<dyn Cache>> {
+        DbPools::cache(self)
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::insert", skip_all, fields(db.system = "postgresql"))]
+    async fn insert(&self, payload: &CreateTransaction) -> AppResult<Transaction> {
+        sqlx::query_as::<_, Transaction>(
+            r#"
+            INSERT INTO transactions (amount, currency, kind, tags, source, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, NOW(), NOW())
+            RETURNING id, amount, currency, kind, tags, source, created_at, updated_at
+            "#,
+        )
+        .bind(payload.amount)
+        .bind(&payload.currency)
+        .bind(payload.kind)
+        .bind(&payload.tags)
+        .bind(&payload.source)
+        .fetch_one(self.write())
+        .await
+        .map_err(AppError::from)
+    }
+
+    async fn insert_many(&self, records: &[ImportedTransaction]) -> AppResult<u64> {
+        MaintenanceService::insert_transactions(self.write(), records).await
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::find", skip(self), fields(db.system = "postgresql"))]
+    async fn find(&self, id: i64) -> AppResult<Option<Transaction>> {
+        self.read_cacheable_with(|pool| async move {
+            sqlx::query_as::<_, Transaction>(
+                r#"
+                SELECT id, amount, currency, kind, tags, source, created_at, updated_at
+                FROM transactions
+                WHERE id = $1
+                "#,
+            )
+            .bind(id)
+            .fetch_optional(&pool)
+            .await
+        })
+        .await
+        .map_err(AppError::from)
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::search", skip(self), fields(db.system = "postgresql"))]
+    async fn search(
+        &self,
+        filter: &TransactionFilter,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<Transaction>> {
+        self.read_with(|pool| async move {
+            sqlx::query_as::<_, Transaction>(
+                r#"
+                SELECT id, amount, currency, kind, tags, source, created_at, updated_at
+                FROM transactions
+                WHERE ($1::VARCHAR IS NULL OR source = $1)
+                  AND ($2::DECIMAL IS NULL OR amount >= $2)
+                  AND ($3::DECIMAL IS NULL OR amount <= $3)
+                  AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4)
+                  AND ($5::TIMESTAMPTZ IS NULL OR created_at < $5)
+                ORDER BY created_at DESC, id DESC
+                LIMIT $6 OFFSET $7
+                "#,
+            )
+            .bind(&filter.source)
+            .bind(filter.min_amount)
+            .bind(filter.max_amount)
+            .bind(filter.created_after)
+            .bind(filter.created_before)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&pool)
+            .await
+        })
+        .await
+        .map_err(AppError::from)
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::count", skip(self), fields(db.system = "postgresql"))]
+    async fn count(&self, filter: &TransactionFilter) -> AppResult<i64> {
+        self.read_cacheable_with(|pool| async move {
+            sqlx::query_scalar::<_, i64>(
+                r#"
+                SELECT COUNT(*)
+                FROM transactions
+                WHERE ($1::VARCHAR IS NULL OR source = $1)
+                  AND ($2::DECIMAL IS NULL OR amount >= $2)
+                  AND ($3::DECIMAL IS NULL OR amount <= $3)
+                  AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4)
+                  AND ($5::TIMESTAMPTZ IS NULL OR created_at < $5)
+                "#,
+            )
+            .bind(&filter.source)
+            .bind(filter.min_amount)
+            .bind(filter.max_amount)
+            .bind(filter.created_after)
+            .bind(filter.created_before)
+            .fetch_one(&pool)
+            .await
+        })
+        .await
+        .map_err(AppError::from)
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::source_aggregates", skip(self), fields(db.system = "postgresql"))]
+    async fn source_aggregates(
+        &self,
+        filter: &TransactionFilter,
+    ) -> AppResult<Vec<SourceAggregate>> {
+        self.read_cacheable_with(|pool| async move {
+            sqlx::query_as::<_, SourceAggregate>(&format!(
+                r#"
+                SELECT source, currency, SUM({SIGNED_AMOUNT_SQL}) AS total, COUNT(*) AS count
+                FROM transactions
+                WHERE ($1::VARCHAR IS NULL OR source = $1)
+                  AND ($2::DECIMAL IS NULL OR amount >= $2)
+                  AND ($3::DECIMAL IS NULL OR amount <= $3)
+                  AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4)
+                  AND ($5::TIMESTAMPTZ IS NULL OR created_at < $5)
+                GROUP BY source, currency
+                ORDER BY source, currency
+                "#
+            ))
+            .bind(&filter.source)
+            .bind(filter.min_amount)
+            .bind(filter.max_amount)
+            .bind(filter.created_after)
+            .bind(filter.created_before)
+            .fetch_all(&pool)
+            .await
+        })
+        .await
+        .map_err(AppError::from)
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::update", skip(self, payload), fields(db.system = "postgresql"))]
+    async fn update(&self, id: i64, payload: &UpdateTransaction) -> AppResult<Option<Transaction>> {
+        sqlx::query_as::<_, Transaction>(
+            r#"
+            UPDATE transactions
+            SET
+                amount = COALESCE($1, amount),
+                source = COALESCE($2, source),
+                updated_at = NOW()
+            WHERE id = $3
+            RETURNING id, amount, currency, kind, tags, source, created_at, updated_at
+            "#,
+        )
+        .bind(payload.amount)
+        .bind(&payload.source)
+        .bind(id)
+        .fetch_optional(self.write())
+        .await
+        .map_err(AppError::from)
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::delete", skip(self), fields(db.system = "postgresql"))]
+    async fn delete(&self, id: i64) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM transactions WHERE id = $1")
+            .bind(id)
+            .execute(self.write())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Always read from the primary: a lagging replica would make a resuming
+    // stream skip events.
+    #[tracing::instrument(name = "SavingsRepository::events_since", skip(self), fields(db.system = "postgresql"))]
+    async fn events_since(
+        &self,
+        after_id: i64,
+        source: Option<&str>,
+        limit: i64,
+    ) -> AppResult<Vec<TransactionEvent>> {
+        sqlx::query_as::<_, TransactionEvent>(
+            r#"
+            SELECT id, operation, transaction_id, source, payload, created_at
+            FROM transaction_events
+            WHERE id > $1 AND ($2::VARCHAR IS NULL OR source = $2)
+            ORDER BY id ASC
+            LIMIT $3
+            "#,
+        )
+        .bind(after_id)
+        .bind(source)
+        .bind(limit)
+        .fetch_all(self.primary())
+        .await
+        .map_err(AppError::from)
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::last_event_id", skip(self), fields(db.system = "postgresql"))]
+    async fn last_event_id(&self) -> AppResult<i64> {
+        sqlx::query_scalar::<_, i64>("SELECT COALESCE(MAX(id), 0) FROM transaction_events")
+            .fetch_one(self.primary())
+            .await
+            .map_err(AppError::from)
+    }
+}
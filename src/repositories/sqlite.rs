@@ -0,0 +1,399 @@
+use crate::adapters::cache::Cache;
+use crate::errors::{AppError, AppResult};
+use crate::models::events::TransactionEvent;
+use crate::models::transactions::{
+    CreateTransaction, ImportedTransaction, SourceAggregate, Transaction, TransactionFilter,
+    TransactionKind, UpdateTransaction,
+};
+use crate::repositories::{SavingsRepository, stored_amount, stored_time};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use sqlx::{FromRow, SqlitePool, types::Json};
+use std::str::FromStr;
+use std::sync::Arc;
+
+const TRANSACTION_COLUMNS: &str =
+    "id, amount, currency, kind, tags, source, created_at, updated_at";
+
+/// Conditions of `TransactionFilter` SQLite can check. Amounts are text,
+/// compared as floating point numbers against the bounds of `real_bounds`,
+/// the exact bounds are checked once decoded.
+const FILTER_CONDITIONS: &str = r#"
+    (?1 IS NULL OR source = ?1)
+    AND (?2 IS NULL OR created_at >= ?2)
+    AND (?3 IS NULL OR created_at < ?3)
+    AND (?4 IS NULL OR CAST(amount AS REAL) >= ?4)
+    AND (?5 IS NULL OR CAST(amount AS REAL) <= ?5)
+"#;
+
+/// Relative error allowed for amounts converted to floating point numbers,
+/// far above the one of a `DECIMAL(19, 4)`
+const REAL_AMOUNT_EPSILON: f64 = 1e-9;
+
+/// A transaction as stored by SQLite: the amount is exact decimal text, tags
+/// a JSON array and timestamps RFC 3339 text that sorts chronologically
+#[derive(FromRow)]
+struct TransactionRow {
+    id: i64,
+    amount: String,
+    currency: String,
+    kind: TransactionKind,
+    tags: Json<Vec<String>>,
+    source: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<TransactionRow> for Transaction {
+    type Error = AppError;
+
+    fn try_from(row: TransactionRow) -> AppResult<Self> {
+        Ok(Self {
+            id: row.id,
+            amount: decode_amount(&row.amount)?,
+            currency: row.currency,
+            kind: row.kind,
+            tags: row.tags.0,
+            source: row.source,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+/// What aggregates need of a transaction
+struct AmountRow {
+    source: String,
+    currency: String,
+    kind: TransactionKind,
+    amount: Decimal,
+}
+
+/// Savings stored in SQLite, for local development and embedded use
+#[derive(Clone)]
+pub struct SqliteSavingsRepository {
+    pool: SqlitePool,
+    cache: Option<Arc<dyn Cache>>,
+}
+
+impl SqliteSavingsRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool, cache: None }
+    }
+
+    pub fn with_cache(mut self, cache: Option<Arc<dyn Cache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Transactions matching `filter`, newest first. Pagination is left to
+    /// the caller when amounts are bounded, SQLite can't compare them exactly.
+    async fn select(
+        &self,
+        filter: &TransactionFilter,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<Transaction>> {
+        let (sql_limit, sql_offset) = if filter.bounds_amount() {
+            (-1, 0)
+        } else {
+            (limit, offset)
+        };
+
+        let (min_real, max_real) = real_bounds(filter);
+        let rows = sqlx::query_as::<_, TransactionRow>(&format!(
+            r#"
+            SELECT {TRANSACTION_COLUMNS}
+            FROM transactions
+            WHERE {FILTER_CONDITIONS}
+            ORDER BY created_at DESC, id DESC
+            LIMIT ?6 OFFSET ?7
+            "#
+        ))
+        .bind(&filter.source)
+        .bind(filter.created_after.map(encode_time))
+        .bind(filter.created_before.map(encode_time))
+        .bind(min_real)
+        .bind(max_real)
+        .bind(sql_limit)
+        .bind(sql_offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(Transaction::try_from)
+            .filter(|transaction| {
+                transaction.as_ref().map_or(true, |transaction| {
+                    filter.matches_amount(transaction.amount)
+                })
+            })
+            .collect()
+    }
+
+    /// Amount of every transaction matching `filter`, by source and currency
+    async fn amounts(&self, filter: &TransactionFilter) -> AppResult<Vec<AmountRow>> {
+        let (min_real, max_real) = real_bounds(filter);
+        let rows = sqlx::query_as::<_, (String, String, TransactionKind, String)>(&format!(
+            r#"
+            SELECT source, currency, kind, amount
+            FROM transactions
+            WHERE {FILTER_CONDITIONS}
+            ORDER BY source, currency
+            "#
+        ))
+        .bind(&filter.source)
+        .bind(filter.created_after.map(encode_time))
+        .bind(filter.created_before.map(encode_time))
+        .bind(min_real)
+        .bind(max_real)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut amounts = Vec::with_capacity(rows.len());
+        for (source, currency, kind, amount) in rows {
+            let amount = decode_amount(&amount)?;
+            if filter.matches_amount(amount) {
+                amounts.push(AmountRow {
+                    source,
+                    currency,
+                    kind,
+                    amount,
+                });
+            }
+        }
+        Ok(amounts)
+    }
+}
+
+impl SavingsRepository for SqliteSavingsRepository {
+    fn cache(&self) -> Option<&Arc<dyn Cache>> {
+        self.cache.as_ref()
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::insert", skip_all, fields(db.system = "sqlite"))]
+    async fn insert(&self, payload: &CreateTransaction) -> AppResult<Transaction> {
+        let row = sqlx::query_as::<_, TransactionRow>(&format!(
+            r#"
+            INSERT INTO transactions (amount, currency, kind, tags, source, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+            RETURNING {TRANSACTION_COLUMNS}
+            "#
+        ))
+        .bind(encode_amount(payload.amount))
+        .bind(&payload.currency)
+        .bind(payload.kind)
+        .bind(Json(&payload.tags))
+        .bind(&payload.source)
+        .bind(encode_time(Utc::now()))
+        .fetch_one(&self.pool)
+        .await?;
+
+        row.try_into()
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::insert_many", skip_all, fields(db.system = "sqlite", count = records.len()))]
+    async fn insert_many(&self, records: &[ImportedTransaction]) -> AppResult<u64> {
+        let mut tx = self.pool.begin().await?;
+        let now = encode_time(Utc::now());
+        let mut inserted = 0;
+
+        for record in records {
+            let created_at = record.created_at.map(encode_time);
+            inserted += sqlx::query(
+                r#"
+                INSERT INTO transactions (amount, currency, kind, tags, source, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, COALESCE(?6, ?7), COALESCE(?6, ?7))
+                "#,
+            )
+            .bind(encode_amount(record.amount))
+            .bind(&record.currency)
+            .bind(record.kind)
+            .bind(Json(&record.tags))
+            .bind(&record.source)
+            .bind(created_at)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::find", skip(self), fields(db.system = "sqlite"))]
+    async fn find(&self, id: i64) -> AppResult<Option<Transaction>> {
+        sqlx::query_as::<_, TransactionRow>(&format!(
+            "SELECT {TRANSACTION_COLUMNS} FROM transactions WHERE id = ?1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(Transaction::try_from)
+        .transpose()
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::search", skip(self), fields(db.system = "sqlite"))]
+    async fn search(
+        &self,
+        filter: &TransactionFilter,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<Transaction>> {
+        let transactions = self.select(filter, limit, offset).await?;
+        if !filter.bounds_amount() {
+            return Ok(transactions);
+        }
+
+        Ok(transactions
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::count", skip(self), fields(db.system = "sqlite"))]
+    async fn count(&self, filter: &TransactionFilter) -> AppResult<i64> {
+        if filter.bounds_amount() {
+            return Ok(self.amounts(filter).await?.len() as i64);
+        }
+
+        let (min_real, max_real) = real_bounds(filter);
+        sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT COUNT(*) FROM transactions WHERE {FILTER_CONDITIONS}"
+        ))
+        .bind(&filter.source)
+        .bind(filter.created_after.map(encode_time))
+        .bind(filter.created_before.map(encode_time))
+        .bind(min_real)
+        .bind(max_real)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    // Summed here, SQLite would sum the amounts as floating point numbers
+    #[tracing::instrument(name = "SavingsRepository::source_aggregates", skip(self), fields(db.system = "sqlite"))]
+    async fn source_aggregates(
+        &self,
+        filter: &TransactionFilter,
+    ) -> AppResult<Vec<SourceAggregate>> {
+        let mut aggregates: Vec<SourceAggregate> = Vec::new();
+        for row in self.amounts(filter).await? {
+            let total = row.kind.signed(row.amount);
+            match aggregates.last_mut() {
+                Some(aggregate)
+                    if aggregate.source == row.source && aggregate.currency == row.currency =>
+                {
+                    aggregate.total += total;
+                    aggregate.count += 1;
+                }
+                _ => aggregates.push(SourceAggregate {
+                    source: row.source,
+                    currency: row.currency,
+                    total: stored_amount(total),
+                    count: 1,
+                }),
+            }
+        }
+        Ok(aggregates)
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::update", skip(self, payload), fields(db.system = "sqlite"))]
+    async fn update(&self, id: i64, payload: &UpdateTransaction) -> AppResult<Option<Transaction>> {
+        sqlx::query_as::<_, TransactionRow>(&format!(
+            r#"
+            UPDATE transactions
+            SET
+                amount = COALESCE(?1, amount),
+                source = COALESCE(?2, source),
+                updated_at = ?3
+            WHERE id = ?4
+            RETURNING {TRANSACTION_COLUMNS}
+            "#
+        ))
+        .bind(payload.amount.map(encode_amount))
+        .bind(&payload.source)
+        .bind(encode_time(Utc::now()))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(Transaction::try_from)
+        .transpose()
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::delete", skip(self), fields(db.system = "sqlite"))]
+    async fn delete(&self, id: i64) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM transactions WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::events_since", skip(self), fields(db.system = "sqlite"))]
+    async fn events_since(
+        &self,
+        after_id: i64,
+        source: Option<&str>,
+        limit: i64,
+    ) -> AppResult<Vec<TransactionEvent>> {
+        sqlx::query_as::<_, TransactionEvent>(
+            r#"
+            SELECT id, operation, transaction_id, source, payload, created_at
+            FROM transaction_events
+            WHERE id > ?1 AND (?2 IS NULL OR source = ?2)
+            ORDER BY id ASC
+            LIMIT ?3
+            "#,
+        )
+        .bind(after_id)
+        .bind(source)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    #[tracing::instrument(name = "SavingsRepository::last_event_id", skip(self), fields(db.system = "sqlite"))]
+    async fn last_event_id(&self) -> AppResult<i64> {
+        sqlx::query_scalar::<_, i64>("SELECT COALESCE(MAX(id), 0) FROM transaction_events")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+}
+
+/// Amounts are kept as text, with the scale Postgres would give them
+fn encode_amount(amount: Decimal) -> String {
+    stored_amount(amount).to_string()
+}
+
+fn decode_amount(amount: &str) -> AppResult<Decimal> {
+    Decimal::from_str(amount).map_err(|e| AppError::DatabaseError(sqlx::Error::Decode(e.into())))
+}
+
+/// Fixed width, so that comparing the text compares the times
+fn encode_time(time: DateTime<Utc>) -> String {
+    stored_time(time)
+        .format("%Y-%m-%dT%H:%M:%S%.6fZ")
+        .to_string()
+}
+
+/// Amount bounds of `filter` as floating point numbers, widened so that no
+/// amount within the exact bounds is left out by the conversion
+fn real_bounds(filter: &TransactionFilter) -> (Option<f64>, Option<f64>) {
+    let widen = |amount: Decimal, direction: f64| {
+        let amount = amount.to_f64().unwrap_or_default();
+        amount + direction * (amount.abs() + 1.0) * REAL_AMOUNT_EPSILON
+    };
+    (
+        filter.min_amount.map(|amount| widen(amount, -1.0)),
+        filter.max_amount.map(|amount| widen(amount, 1.0)),
+    )
+}
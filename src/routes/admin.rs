@@ -0,0 +1,43 @@
+use crate::auth;
+use crate::config::Config;
+use crate::errors::{AppResult, ErrorResponse};
+use crate::models::migrations::{AppliedMigration, MigrationStatus};
+use crate::services::MigrationsService;
+use actix_web::{
+    HttpRequest, HttpResponse, get,
+    web::{Data, ServiceConfig},
+};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(get_migrations),
+    components(schemas(AppliedMigration, MigrationStatus))
+)]
+pub struct AdminApi;
+
+#[utoipa::path(
+    tag = "admin",
+    params(("X-API-Key" = String, Header, description = "API key")),
+    responses(
+        (status = 200, description = "Applied and pending migrations", body = MigrationStatus),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+    )
+)]
+#[get("/admin/migrations")]
+#[tracing::instrument(skip_all)]
+async fn get_migrations(
+    req: HttpRequest,
+    db: Data<PgPool>,
+    config: Data<Config>,
+) -> AppResult<HttpResponse> {
+    auth::authenticate(&req, &config)?;
+
+    let status = MigrationsService::status(&db).await?;
+    Ok(HttpResponse::Ok().json(status))
+}
+
+pub fn cfg_admin_routes(cfg: &mut ServiceConfig) {
+    cfg.service(get_migrations);
+}
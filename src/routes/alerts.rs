@@ -0,0 +1,115 @@
+use crate::adapters::negotiation::{Body, Reply};
+use crate::errors::{AppError, AppResult, ErrorResponse};
+use crate::models::alerts::{
+    Alert, AlertComparison, AlertKind, AlertPeriod, AlertState, AlertThreshold, AlertsQuery,
+    CreateAlertThreshold,
+};
+use crate::services::AlertsService;
+use actix_web::{
+    HttpResponse, delete, get, post,
+    web::{Data, Path, Query, ServiceConfig},
+};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use validator::Validate;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        add_alert_threshold,
+        get_alert_thresholds,
+        delete_alert_threshold,
+        get_alerts
+    ),
+    components(schemas(
+        Alert,
+        AlertComparison,
+        AlertKind,
+        AlertPeriod,
+        AlertState,
+        AlertThreshold,
+        CreateAlertThreshold
+    ))
+)]
+pub struct AlertsApi;
+
+#[utoipa::path(
+    tag = "alerts",
+    request_body(content(
+        (CreateAlertThreshold = "application/json"),
+        (CreateAlertThreshold = "application/x-www-form-urlencoded"),
+        (CreateAlertThreshold = "application/msgpack"),
+    )),
+    responses(
+        (status = 201, description = "Threshold created", content(
+            (AlertThreshold = "application/json"),
+            (AlertThreshold = "application/msgpack"),
+        )),
+        (status = 400, description = "Invalid payload", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 413, description = "Payload too large", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Unsupported content type", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+#[post("/alerts/thresholds")]
+#[tracing::instrument(skip_all)]
+async fn add_alert_threshold(
+    db: Data<PgPool>,
+    payload: Body<CreateAlertThreshold>,
+) -> AppResult<Reply<AlertThreshold>> {
+    payload.validate()?;
+    let threshold = AlertsService::create_threshold(&db, &payload.into_inner()).await?;
+    Ok(Reply::created(threshold))
+}
+
+#[utoipa::path(
+    tag = "alerts",
+    responses((status = 200, description = "All thresholds", body = Vec<AlertThreshold>))
+)]
+#[get("/alerts/thresholds")]
+#[tracing::instrument(skip_all)]
+async fn get_alert_thresholds(db: Data<PgPool>) -> AppResult<HttpResponse> {
+    let thresholds = AlertsService::list_thresholds(&db).await?;
+    Ok(HttpResponse::Ok().json(thresholds))
+}
+
+#[utoipa::path(
+    tag = "alerts",
+    responses(
+        (status = 204, description = "Threshold deleted"),
+        (status = 400, description = "Invalid ID", body = ErrorResponse),
+        (status = 404, description = "Threshold not found", body = ErrorResponse),
+    )
+)]
+#[delete("/alerts/thresholds/{threshold_id}")]
+#[tracing::instrument(skip_all, fields(threshold_id = %threshold_id))]
+async fn delete_alert_threshold(
+    db: Data<PgPool>,
+    threshold_id: Path<i64>,
+) -> AppResult<HttpResponse> {
+    if *threshold_id <= 0 {
+        return Err(AppError::BadRequest(
+            "Invalid ID: must be a positive integer".to_string(),
+        ));
+    }
+    AlertsService::delete_threshold(&db, *threshold_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    tag = "alerts",
+    params(AlertsQuery),
+    responses((status = 200, description = "Alerts after `since_id`, oldest first", body = Vec<Alert>))
+)]
+#[get("/alerts")]
+#[tracing::instrument(skip_all)]
+async fn get_alerts(db: Data<PgPool>, query: Query<AlertsQuery>) -> AppResult<HttpResponse> {
+    let alerts = AlertsService::list_alerts(&db, &query).await?;
+    Ok(HttpResponse::Ok().json(alerts))
+}
+
+pub fn cfg_alerts_routes(cfg: &mut ServiceConfig) {
+    cfg.service(add_alert_threshold)
+        .service(get_alert_thresholds)
+        .service(delete_alert_threshold)
+        .service(get_alerts);
+}
@@ -0,0 +1,115 @@
+use crate::adapters::versioning::{ApiVersion, ApiVersioning};
+use crate::config::{Config, Environment};
+use crate::errors::{AppError, AppResult};
+use crate::routes::{
+    admin::AdminApi,
+    alerts::AlertsApi,
+    monitoring::MonitoringApi,
+    savings::{SavingsApi, SavingsV2Api},
+};
+use actix_web::{
+    HttpResponse, get,
+    web::{Data, ServiceConfig},
+};
+use utoipa::{
+    OpenApi,
+    openapi::{self, Deprecated, InfoBuilder, ServerBuilder},
+};
+
+const REDOC_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>API documentation</title>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+  </head>
+  <body>
+    <redoc spec-url="/openapi.json"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/latest/bundles/redoc.standalone.js"></script>
+  </body>
+</html>
+"#;
+
+/// Build the OpenAPI document, nesting the routes of every version under
+/// `url_prefix`
+pub fn api_doc(config: &Config) -> openapi::OpenApi {
+    // Invalid deprecations fail the application, not its documentation
+    let versioning = ApiVersioning::from_config(config).ok();
+    let mut doc = MonitoringApi::openapi().merge_from(AdminApi::openapi());
+    for version in ApiVersion::ALL {
+        let routes = match version {
+            ApiVersion::V1 => SavingsApi::openapi(),
+            ApiVersion::V2 => SavingsV2Api::openapi(),
+        }
+        .merge_from(AlertsApi::openapi());
+
+        doc = doc.nest(
+            format!("{}/{}", config.url_prefix, version),
+            versioned(
+                routes,
+                version,
+                versioning
+                    .as_ref()
+                    .is_some_and(|versioning| versioning.is_deprecated(version)),
+            ),
+        );
+    }
+
+    doc.info = InfoBuilder::new()
+        .title(&config.name)
+        .version(env!("CARGO_PKG_VERSION"))
+        .build();
+    doc.servers = Some(vec![ServerBuilder::new().url(&config.api_url).build()]);
+    doc
+}
+
+/// Prefix the operation ids with the version, several versions share their
+/// handlers, and flag the operations of deprecated versions
+fn versioned(
+    mut routes: openapi::OpenApi,
+    version: ApiVersion,
+    deprecated: bool,
+) -> openapi::OpenApi {
+    for item in routes.paths.paths.values_mut() {
+        let operations = [
+            &mut item.get,
+            &mut item.put,
+            &mut item.post,
+            &mut item.delete,
+            &mut item.patch,
+        ];
+        for operation in operations.into_iter().flatten() {
+            operation.operation_id = operation
+                .operation_id
+                .take()
+                .map(|id| format!("{}_{}", version, id));
+            if deprecated {
+                operation.deprecated = Some(Deprecated::True);
+            }
+        }
+    }
+    routes
+}
+
+#[get("/openapi.json")]
+#[tracing::instrument(skip_all)]
+async fn get_openapi(config: Data<Config>) -> HttpResponse {
+    HttpResponse::Ok().json(api_doc(&config))
+}
+
+// The documentation UI is only served in dev environments
+#[get("/docs")]
+#[tracing::instrument(skip_all)]
+async fn get_docs(config: Data<Config>) -> AppResult<HttpResponse> {
+    if config.environment != Environment::Dev {
+        return Err(AppError::NotFound("Not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(REDOC_PAGE))
+}
+
+pub fn cfg_docs_routes(cfg: &mut ServiceConfig) {
+    cfg.service(get_openapi).service(get_docs);
+}
@@ -0,0 +1,33 @@
+use crate::adapters::db::DbPools;
+use crate::adapters::negotiation::Body;
+use crate::auth;
+use crate::config::Config;
+use crate::graphql::{self, AppSchema, Authentication};
+use actix_web::{
+    HttpRequest, HttpResponse, post,
+    web::{Data, ServiceConfig},
+};
+
+#[post("/graphql")]
+#[tracing::instrument(skip_all)]
+async fn execute_graphql(
+    req: HttpRequest,
+    schema: Data<AppSchema>,
+    config: Data<Config>,
+    db: Data<DbPools>,
+    request: Body<async_graphql::Request>,
+) -> HttpResponse {
+    // Per request, so reads after a mutation of the same request see it
+    let request = request
+        .into_inner()
+        .data(db.session())
+        // Only checked by the fields that require it, reads stay open
+        .data(Authentication::from(auth::authenticate(&req, &config)))
+        .data(graphql::request_loader(db.primary().clone()));
+    let response = schema.execute(request).await;
+    HttpResponse::Ok().json(response)
+}
+
+pub fn cfg_graphql_routes(cfg: &mut ServiceConfig) {
+    cfg.service(execute_graphql);
+}
@@ -1,5 +1,27 @@
+use crate::errors::{AppError, AppResult};
+use actix_web::{HttpRequest, HttpResponse};
+
+mod admin;
+mod alerts;
+mod docs;
+mod graphql;
 mod monitoring;
+mod realtime;
 mod savings;
 
+pub use admin::cfg_admin_routes;
+pub use alerts::cfg_alerts_routes;
+pub use docs::{api_doc, cfg_docs_routes};
+pub use graphql::cfg_graphql_routes;
 pub use monitoring::cfg_monitoring_routes;
-pub use savings::cfg_savings_routes;
+pub use realtime::cfg_realtime_routes;
+pub use savings::{cfg_savings_routes, cfg_savings_v2_routes};
+
+/// Fallback for requests matching no route, answered like any other error
+pub async fn route_not_found(req: HttpRequest) -> AppResult<HttpResponse> {
+    Err(AppError::NotFound(format!(
+        "No route for {} {}",
+        req.method(),
+        req.path()
+    )))
+}
@@ -1,15 +1,85 @@
+use crate::adapters::{metrics::METRICS, readiness::Readiness};
+use crate::config::Config;
+use crate::errors::{AppError, AppResult};
+use crate::models::health::{ComponentCheck, ComponentStatus, ReadinessReport};
+use crate::repositories::Storage;
+use crate::services::HealthService;
 use actix_web::{HttpResponse, Responder, get, web};
+use std::time::Duration;
+use utoipa::OpenApi;
 
+#[derive(OpenApi)]
+#[openapi(
+    paths(get_health_check, get_readiness, check_service, get_metrics),
+    components(schemas(ReadinessReport, ComponentCheck, ComponentStatus))
+)]
+pub struct MonitoringApi;
+
+#[utoipa::path(
+    tag = "monitoring",
+    responses((status = 204, description = "Service is up"))
+)]
 #[get("/healthz")]
+#[tracing::instrument(skip_all)]
 async fn get_health_check() -> impl Responder {
     HttpResponse::NoContent().finish()
 }
 
+#[utoipa::path(
+    tag = "monitoring",
+    responses(
+        (status = 200, description = "Every component is ready", body = ReadinessReport),
+        (status = 503, description = "At least one component is not ready", body = ReadinessReport),
+    )
+)]
+#[get("/readyz")]
+#[tracing::instrument(skip_all)]
+async fn get_readiness(
+    db: web::Data<Storage>,
+    readiness: web::Data<Readiness>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    let timeout = Duration::from_millis(config.readiness_timeout_ms);
+    let report = HealthService::readiness(&db, &readiness, timeout).await;
+
+    if report.is_ready() {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+#[utoipa::path(
+    tag = "monitoring",
+    responses((status = 200, description = "Service is up", body = String))
+)]
 #[get("/checkz")]
+#[tracing::instrument(skip_all)]
 async fn check_service() -> impl Responder {
     HttpResponse::Ok().body("Service Health checked ✅")
 }
 
+#[utoipa::path(
+    tag = "monitoring",
+    responses((status = 200, description = "Metrics in the Prometheus text format", content_type = "text/plain", body = String))
+)]
+#[get("/metrics")]
+#[tracing::instrument(skip_all)]
+async fn get_metrics(db: web::Data<Storage>) -> AppResult<HttpResponse> {
+    let body = match db.get_ref() {
+        Storage::Postgres(pools) => METRICS.render(pools.primary()).await,
+        Storage::Sqlite(repo) => METRICS.render(repo.pool()).await,
+    }
+    .map_err(|e| AppError::InternalServerError(format!("Failed to encode metrics: {}", e)))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(body))
+}
+
 pub fn cfg_monitoring_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_health_check).service(check_service);
+    cfg.service(get_health_check)
+        .service(get_readiness)
+        .service(check_service)
+        .service(get_metrics);
 }
@@ -0,0 +1,195 @@
+use crate::adapters::change_feed::ChangeFeed;
+use crate::adapters::versioning::ApiVersion;
+use crate::auth;
+use crate::config::Config;
+use crate::errors::{AppError, AppResult};
+use crate::models::events::{FeedEvent, Topic};
+use crate::models::realtime::{ClientMessage, ServerMessage};
+use crate::shutdown::ShutdownCoordinator;
+use actix_web::{
+    HttpRequest, HttpResponse, get, rt,
+    web::{Data, Payload, ServiceConfig},
+};
+use actix_ws::{AggregatedMessage, AggregatedMessageStream, CloseCode, CloseReason, Session};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc::{self, error::TrySendError},
+};
+use tokio_util::sync::CancellationToken;
+
+const MAX_FRAME_SIZE: usize = 16 * 1024;
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+#[get("/ws")]
+#[tracing::instrument(skip_all)]
+async fn connect_realtime(
+    req: HttpRequest,
+    body: Payload,
+    version: ApiVersion,
+    config: Data<Config>,
+    feed: Data<ChangeFeed>,
+    shutdown: Data<ShutdownCoordinator>,
+) -> AppResult<HttpResponse> {
+    auth::authenticate(&req, &config)?;
+
+    let (response, session, stream) = actix_ws::handle(&req, body)
+        .map_err(|e| AppError::BadRequest(format!("WebSocket handshake failed: {}", e)))?;
+    let stream = stream
+        .max_frame_size(MAX_FRAME_SIZE)
+        .aggregate_continuations()
+        .max_continuation_size(MAX_MESSAGE_SIZE);
+
+    // Everything sent to the client goes through this bounded buffer
+    let (outbox, inbox) = mpsc::channel(config.ws_client_buffer.max(1));
+
+    rt::spawn(write_messages(session.clone(), inbox));
+    rt::spawn(run_session(
+        session,
+        stream,
+        feed.subscribe(),
+        version,
+        outbox,
+        feed.heartbeat_interval(),
+        shutdown.token(),
+    ));
+
+    Ok(response)
+}
+
+// Drain the client buffer into the socket, waiting on the client when it reads slowly
+async fn write_messages(mut session: Session, mut inbox: mpsc::Receiver<String>) {
+    while let Some(text) = inbox.recv().await {
+        if session.text(text).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn run_session(
+    mut session: Session,
+    mut stream: AggregatedMessageStream,
+    mut events: broadcast::Receiver<FeedEvent>,
+    version: ApiVersion,
+    outbox: mpsc::Sender<String>,
+    heartbeat_interval: Duration,
+    shutdown: CancellationToken,
+) {
+    let mut topics = HashSet::new();
+    let mut heartbeat = tokio::time::interval(heartbeat_interval);
+    let mut last_seen = Instant::now();
+
+    let close_reason = loop {
+        let queued = tokio::select! {
+            message = stream.recv() => {
+                let Some(Ok(message)) = message else {
+                    break None;
+                };
+                last_seen = Instant::now();
+
+                match message {
+                    AggregatedMessage::Text(text) => {
+                        enqueue(&outbox, &handle_client_message(&text, &mut topics))
+                    }
+                    AggregatedMessage::Binary(_) => enqueue(
+                        &outbox,
+                        &ServerMessage::Error {
+                            error: "Binary messages are not supported".to_string(),
+                        },
+                    ),
+                    AggregatedMessage::Ping(bytes) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break None;
+                        }
+                        Ok(())
+                    }
+                    AggregatedMessage::Pong(_) => Ok(()),
+                    AggregatedMessage::Close(reason) => break reason,
+                }
+            }
+            event = events.recv() => match event {
+                Ok(event) if event.topic().is_none_or(|topic| topics.contains(&topic)) => {
+                    enqueue(&outbox, &ServerMessage::event(&event, version))
+                }
+                Ok(_) => Ok(()),
+                Err(RecvError::Lagged(skipped)) => enqueue(
+                    &outbox,
+                    &ServerMessage::Error {
+                        error: format!("Missed {} events", skipped),
+                    },
+                ),
+                Err(RecvError::Closed) => break None,
+            },
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > heartbeat_interval * 2 {
+                    break Some(CloseReason {
+                        code: CloseCode::Normal,
+                        description: Some("Heartbeat timeout".to_string()),
+                    });
+                }
+                if session.ping(b"").await.is_err() {
+                    break None;
+                }
+                Ok(())
+            }
+            _ = shutdown.cancelled() => break Some(CloseReason {
+                code: CloseCode::Away,
+                description: Some("Server is shutting down".to_string()),
+            }),
+        };
+
+        if let Err(reason) = queued {
+            break Some(reason);
+        }
+    };
+
+    let _ = session.close(close_reason).await;
+}
+
+fn handle_client_message(text: &str, topics: &mut HashSet<Topic>) -> ServerMessage {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => {
+            return ServerMessage::Error {
+                error: format!("Invalid message: {}", e),
+            };
+        }
+    };
+
+    match message {
+        ClientMessage::Subscribe { topics: requested } => topics.extend(requested),
+        ClientMessage::Unsubscribe { topics: requested } => {
+            topics.retain(|topic| !requested.contains(topic))
+        }
+        ClientMessage::Ping => return ServerMessage::Pong,
+    }
+
+    let mut current: Vec<Topic> = topics.iter().copied().collect();
+    current.sort_by_key(|topic| *topic as u8);
+    ServerMessage::Subscriptions { topics: current }
+}
+
+// Queue a message for the client, a full buffer means the client can't keep up
+fn enqueue(outbox: &mpsc::Sender<String>, message: &ServerMessage) -> Result<(), CloseReason> {
+    let text = serde_json::to_string(message).unwrap_or_default();
+
+    match outbox.try_send(text) {
+        Ok(()) => Ok(()),
+        Err(TrySendError::Full(_)) => {
+            tracing::warn!("⚠️ Closing WebSocket client, send buffer is full");
+            Err(CloseReason {
+                code: CloseCode::Again,
+                description: Some("Client is too slow".to_string()),
+            })
+        }
+        Err(TrySendError::Closed(_)) => Err(CloseReason {
+            code: CloseCode::Away,
+            description: None,
+        }),
+    }
+}
+
+pub fn cfg_realtime_routes(cfg: &mut ServiceConfig) {
+    cfg.service(connect_realtime);
+}
@@ -1,38 +1,491 @@
-use crate::errors::{AppError, AppResult};
-use crate::models::transactions::CreateTransaction;
+use crate::adapters::change_feed::ChangeFeed;
+use crate::adapters::negotiation::{Body, Reply};
+use crate::adapters::versioning::ApiVersion;
+use crate::auth;
+use crate::config::Config;
+use crate::dto::{VersionedSaving, v1, v2};
+use crate::errors::{AppError, AppResult, ErrorCode, ErrorResponse, FieldError};
+use crate::models::events::{FeedEvent, StreamQuery, TransactionEvent};
+use crate::models::transactions::{
+    CreateTransaction, ImportSummary, ImportedTransaction, Transaction, TransactionKind,
+};
+use crate::repositories::Storage;
 use crate::services::SavingsService;
+use crate::shutdown::ShutdownCoordinator;
 use actix_web::{
-    HttpResponse, get, post,
-    web::{Data, Json, Path, ServiceConfig},
+    HttpRequest, HttpResponse, get,
+    http::header::{CACHE_CONTROL, ContentEncoding},
+    post,
+    web::{Bytes, Data, Path, Query, ServiceConfig},
 };
-use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
+use utoipa::OpenApi;
 use validator::Validate;
 
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+const STREAM_BUFFER_SIZE: usize = 64;
+const REPLAY_PAGE_SIZE: i64 = 500;
+/// How long a stream waits for the event ids it skipped
+const GAP_TIMEOUT: Duration = Duration::from_secs(60);
+/// Most skipped event ids a stream waits for at once
+const MAX_GAPS: usize = 1024;
+
+type SseSender = mpsc::Sender<Result<Bytes, Infallible>>;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(add_new_saving_value, import_savings, stream_savings, get_saving_by_id),
+    components(schemas(
+        v1::Saving,
+        v1::CreateSaving,
+        v1::ImportedSaving,
+        v1::ImportSavings,
+        ImportSummary,
+        ErrorResponse,
+        ErrorCode,
+        FieldError
+    ))
+)]
+pub struct SavingsApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        add_new_saving_value_v2,
+        import_savings_v2,
+        stream_savings,
+        get_saving_by_id_v2
+    ),
+    components(schemas(
+        v2::Saving,
+        v2::CreateSaving,
+        v2::ImportedSaving,
+        v2::ImportSavings,
+        TransactionKind,
+        ImportSummary,
+        ErrorResponse,
+        ErrorCode,
+        FieldError
+    ))
+)]
+pub struct SavingsV2Api;
+
+#[utoipa::path(
+    tag = "savings",
+    request_body(content(
+        (v1::CreateSaving = "application/json"),
+        (v1::CreateSaving = "application/x-www-form-urlencoded"),
+        (v1::CreateSaving = "application/msgpack"),
+    )),
+    responses(
+        (status = 201, description = "Saving created", content(
+            (v1::Saving = "application/json"),
+            (v1::Saving = "application/msgpack"),
+        )),
+        (status = 400, description = "Invalid payload", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 413, description = "Payload too large", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Unsupported content type", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
 #[post("/new-saving")]
+#[tracing::instrument(skip_all)]
 async fn add_new_saving_value(
-    db: Data<PgPool>,
-    payload: Json<CreateTransaction>,
-) -> AppResult<HttpResponse> {
+    db: Data<Storage>,
+    payload: Body<v1::CreateSaving>,
+) -> AppResult<Reply<v1::Saving>> {
+    payload.validate()?;
+    let payload = CreateTransaction::from(payload.into_inner());
+    let transaction = SavingsService::create_new_saving(db.get_ref(), &payload).await?;
+    Ok(Reply::created(transaction.into()))
+}
+
+#[utoipa::path(
+    tag = "savings",
+    operation_id = "add_new_saving_value",
+    request_body(content(
+        (v2::CreateSaving = "application/json"),
+        (v2::CreateSaving = "application/msgpack"),
+    )),
+    responses(
+        (status = 201, description = "Saving created", content(
+            (v2::Saving = "application/json"),
+            (v2::Saving = "application/msgpack"),
+        )),
+        (status = 400, description = "Invalid payload", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 413, description = "Payload too large", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Unsupported content type", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+#[post("/new-saving")]
+#[tracing::instrument(skip_all)]
+async fn add_new_saving_value_v2(
+    db: Data<Storage>,
+    payload: Body<v2::CreateSaving>,
+) -> AppResult<Reply<v2::Saving>> {
+    payload.validate()?;
+    let payload = CreateTransaction::from(payload.into_inner());
+    let transaction = SavingsService::create_new_saving(db.get_ref(), &payload).await?;
+    Ok(Reply::created(transaction.into()))
+}
+
+#[utoipa::path(
+    tag = "savings",
+    params(("X-API-Key" = String, Header, description = "API key")),
+    request_body(content(
+        (v1::ImportSavings = "application/json"),
+        (v1::ImportSavings = "application/msgpack"),
+    )),
+    responses(
+        (status = 201, description = "Every record imported", content(
+            (ImportSummary = "application/json"),
+            (ImportSummary = "application/msgpack"),
+        )),
+        (status = 400, description = "Invalid records, nothing was imported", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 413, description = "Payload too large", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Unsupported content type", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+#[post("/savings/import")]
+#[tracing::instrument(skip_all)]
+async fn import_savings(
+    req: HttpRequest,
+    db: Data<Storage>,
+    config: Data<Config>,
+    payload: Body<v1::ImportSavings>,
+) -> AppResult<Reply<ImportSummary>> {
+    auth::authenticate(&req, &config)?;
+    payload.validate()?;
+    import_records(&db, payload.into_inner().into()).await
+}
+
+#[utoipa::path(
+    tag = "savings",
+    operation_id = "import_savings",
+    params(("X-API-Key" = String, Header, description = "API key")),
+    request_body(content(
+        (v2::ImportSavings = "application/json"),
+        (v2::ImportSavings = "application/msgpack"),
+    )),
+    responses(
+        (status = 201, description = "Every record imported", content(
+            (ImportSummary = "application/json"),
+            (ImportSummary = "application/msgpack"),
+        )),
+        (status = 400, description = "Invalid records, nothing was imported", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 413, description = "Payload too large", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Unsupported content type", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+#[post("/savings/import")]
+#[tracing::instrument(skip_all)]
+async fn import_savings_v2(
+    req: HttpRequest,
+    db: Data<Storage>,
+    config: Data<Config>,
+    payload: Body<v2::ImportSavings>,
+) -> AppResult<Reply<ImportSummary>> {
+    auth::authenticate(&req, &config)?;
     payload.validate()?;
-    let transaction = SavingsService::create_new_saving(&db, &payload.into_inner()).await?;
-    Ok(HttpResponse::Created().json(transaction))
+    import_records(&db, payload.into_inner().into()).await
+}
+
+async fn import_records(
+    db: &Storage,
+    records: Vec<ImportedTransaction>,
+) -> AppResult<Reply<ImportSummary>> {
+    let imported = SavingsService::import_savings(db, &records).await?;
+    tracing::info!(imported, "📥 Imported transactions");
+    Ok(Reply::created(ImportSummary { imported }))
+}
+
+#[utoipa::path(
+    tag = "savings",
+    params(
+        StreamQuery,
+        ("Last-Event-ID" = Option<i64>, Header, description = "Resume after this event id"),
+    ),
+    responses(
+        (status = 200, description = "Server-Sent Events stream of `created`, `updated` and `deleted` savings, in the representation of the version", content_type = "text/event-stream", body = String),
+        (status = 400, description = "Invalid Last-Event-ID", body = ErrorResponse),
+    )
+)]
+#[get("/savings/stream")]
+#[tracing::instrument(skip_all)]
+async fn stream_savings(
+    req: HttpRequest,
+    db: Data<Storage>,
+    feed: Data<ChangeFeed>,
+    shutdown: Data<ShutdownCoordinator>,
+    query: Query<StreamQuery>,
+    version: ApiVersion,
+) -> AppResult<HttpResponse> {
+    let last_event_id = match req.headers().get(LAST_EVENT_ID_HEADER) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .ok_or_else(|| AppError::BadRequest("Invalid Last-Event-ID header".to_string()))?,
+        ),
+        None => query.last_event_id,
+    };
+
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+    tokio::spawn(forward_changes(
+        db.get_ref().clone(),
+        feed.get_ref().clone(),
+        query.into_inner().source,
+        last_event_id,
+        version,
+        tx,
+        shutdown.token(),
+    ));
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((CACHE_CONTROL, "no-cache"))
+        // Skip the compression middleware, it would buffer the events
+        .insert_header(ContentEncoding::Identity)
+        .streaming(ReceiverStream::new(rx)))
 }
 
+/// Event ids a stream has seen: every id up to `last_seen` but the gaps.
+/// Ids follow the order of the inserts, not of the commits, so a change
+/// committing late is published after changes with greater ids.
+struct SeenEvents {
+    last_seen: i64,
+    /// Ids skipped so far, with when they were, waited for until they
+    /// expire: a rolled back change never publishes its id
+    gaps: BTreeMap<i64, Instant>,
+}
+
+impl SeenEvents {
+    fn after(last_seen: i64) -> Self {
+        Self {
+            last_seen,
+            gaps: BTreeMap::new(),
+        }
+    }
+
+    /// Record `id`, returns whether it was not seen yet
+    fn record(&mut self, id: i64) -> bool {
+        self.gaps.retain(|_, since| since.elapsed() < GAP_TIMEOUT);
+        if id <= self.last_seen {
+            return self.gaps.remove(&id).is_some();
+        }
+
+        let now = Instant::now();
+        let first_gap = (self.last_seen + 1).max(id - MAX_GAPS as i64);
+        self.gaps.extend((first_gap..id).map(|gap| (gap, now)));
+        while self.gaps.len() > MAX_GAPS {
+            self.gaps.pop_first();
+        }
+        self.last_seen = id;
+        true
+    }
+
+    /// Id to replay the events after, so that the gaps are replayed too
+    fn replay_after(&self) -> i64 {
+        self.gaps
+            .first_key_value()
+            .map_or(self.last_seen, |(id, _)| id - 1)
+    }
+}
+
+// Forward change events to one SSE client until it disconnects or the server shuts down
+async fn forward_changes(
+    db: Storage,
+    feed: ChangeFeed,
+    source: Option<String>,
+    last_event_id: Option<i64>,
+    version: ApiVersion,
+    tx: SseSender,
+    shutdown: CancellationToken,
+) {
+    // Subscribe before replaying so no event falls between the two
+    let mut events = feed.subscribe();
+    let mut heartbeat = tokio::time::interval(feed.heartbeat_interval());
+    heartbeat.tick().await;
+
+    if send(&tx, Bytes::from_static(b"retry: 5000\n\n"))
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    // Without a position to resume from, the stream starts at the latest
+    // event, which is where it replays from after missing some
+    let mut seen = match last_event_id {
+        Some(after_id) => SeenEvents::after(after_id),
+        None => match SavingsService::last_event_id(&db).await {
+            Ok(after_id) => SeenEvents::after(after_id),
+            Err(e) => {
+                tracing::error!(error = %e, "❌ Failed to start the change stream");
+                return;
+            }
+        },
+    };
+    if last_event_id.is_some()
+        && replay(&db, &tx, source.as_deref(), version, &mut seen)
+            .await
+            .is_err()
+    {
+        return;
+    }
+
+    loop {
+        let sent = tokio::select! {
+            received = events.recv() => match received {
+                Ok(FeedEvent::Savings(event)) => {
+                    // Recorded whatever its source, to tell gaps from filtered events
+                    let unseen = seen.record(event.id);
+                    let filtered_out = source.as_ref().is_some_and(|s| *s != event.source);
+                    if !unseen || filtered_out {
+                        continue;
+                    }
+                    send(&tx, sse_frame(&event, version)).await
+                }
+                Ok(FeedEvent::Resync) => {
+                    replay(&db, &tx, source.as_deref(), version, &mut seen).await
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "⚠️ SSE client lagged behind");
+                    replay(&db, &tx, source.as_deref(), version, &mut seen).await
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = heartbeat.tick() => send(&tx, Bytes::from_static(b": heartbeat\n\n")).await,
+            _ = shutdown.cancelled() => break,
+        };
+
+        if sent.is_err() {
+            break;
+        }
+    }
+}
+
+// Send every recorded event not seen yet, used on resume and after missing
+// some
+async fn replay(
+    db: &Storage,
+    tx: &SseSender,
+    source: Option<&str>,
+    version: ApiVersion,
+    seen: &mut SeenEvents,
+) -> Result<(), ()> {
+    let mut cursor = seen.replay_after();
+    loop {
+        let events =
+            match SavingsService::list_events_since(db, cursor, source, REPLAY_PAGE_SIZE).await {
+                Ok(events) => events,
+                Err(e) => {
+                    tracing::error!(error = %e, "❌ Failed to replay change events");
+                    return Err(());
+                }
+            };
+
+        for event in &events {
+            cursor = event.id;
+            if seen.record(event.id) {
+                send(tx, sse_frame(event, version)).await?;
+            }
+        }
+
+        if (events.len() as i64) < REPLAY_PAGE_SIZE {
+            return Ok(());
+        }
+    }
+}
+
+async fn send(tx: &SseSender, frame: Bytes) -> Result<(), ()> {
+    tx.send(Ok(frame)).await.map_err(|_| ())
+}
+
+fn sse_frame(event: &TransactionEvent, version: ApiVersion) -> Bytes {
+    let saving = VersionedSaving::new(version, event.payload.0.clone());
+    let data = serde_json::to_string(&saving).unwrap_or_default();
+    Bytes::from(format!(
+        "id: {}\nevent: {}\ndata: {}\n\n",
+        event.id,
+        event.operation.as_str(),
+        data
+    ))
+}
+
+#[utoipa::path(
+    tag = "savings",
+    responses(
+        (status = 200, description = "Saving found", content(
+            (v1::Saving = "application/json"),
+            (v1::Saving = "application/msgpack"),
+        )),
+        (status = 400, description = "Invalid ID", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 404, description = "Saving not found", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+#[get("/savings/{saving_id}")]
+#[tracing::instrument(skip_all, fields(saving_id = %saving_id))]
+async fn get_saving_by_id(db: Data<Storage>, saving_id: Path<i64>) -> AppResult<Reply<v1::Saving>> {
+    let transaction = find_saving(&db, *saving_id).await?;
+    Ok(Reply::ok(transaction.into()))
+}
+
+#[utoipa::path(
+    tag = "savings",
+    operation_id = "get_saving_by_id",
+    responses(
+        (status = 200, description = "Saving found", content(
+            (v2::Saving = "application/json"),
+            (v2::Saving = "application/msgpack"),
+        )),
+        (status = 400, description = "Invalid ID", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 404, description = "Saving not found", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
 #[get("/savings/{saving_id}")]
-async fn get_saving_by_id(db: Data<PgPool>, saving_id: Path<i64>) -> AppResult<HttpResponse> {
-    if *saving_id <= 0 {
+#[tracing::instrument(skip_all, fields(saving_id = %saving_id))]
+async fn get_saving_by_id_v2(
+    db: Data<Storage>,
+    saving_id: Path<i64>,
+) -> AppResult<Reply<v2::Saving>> {
+    let transaction = find_saving(&db, *saving_id).await?;
+    Ok(Reply::ok(transaction.into()))
+}
+
+async fn find_saving(db: &Storage, saving_id: i64) -> AppResult<Transaction> {
+    if saving_id <= 0 {
         return Err(AppError::BadRequest(
             "Invalid ID: must be a positive integer".to_string(),
         ));
     }
-    let transaction = SavingsService::get_by_id(&db, *saving_id).await?;
 
-    match transaction {
-        Some(t) => Ok(HttpResponse::Ok().json(t)),
-        None => Err(AppError::NotFound("Saving not found".to_string())),
-    }
+    SavingsService::get_by_id(db, saving_id)
+        .await?
+        .ok_or(AppError::SavingNotFound(saving_id))
 }
 
 pub fn cfg_savings_routes(cfg: &mut ServiceConfig) {
-    cfg.service(add_new_saving_value).service(get_saving_by_id);
+    // The stream route must be registered before `/savings/{saving_id}`
+    cfg.service(add_new_saving_value)
+        .service(import_savings)
+        .service(stream_savings)
+        .service(get_saving_by_id);
+}
+
+pub fn cfg_savings_v2_routes(cfg: &mut ServiceConfig) {
+    cfg.service(add_new_saving_value_v2)
+        .service(import_savings_v2)
+        .service(stream_savings)
+        .service(get_saving_by_id_v2);
 }
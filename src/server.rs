@@ -0,0 +1,205 @@
+use crate::adapters::{
+    cache::{Cache, LruCache},
+    change_feed::ChangeFeed,
+    db, logger, sqlite, telemetry,
+};
+use crate::app::AppState;
+use crate::config::{Config, ConfigIssue};
+use crate::repositories::{SqliteSavingsRepository, Storage};
+use crate::{grpc, workers};
+use actix_web::HttpServer;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// Run the HTTP and gRPC servers and the background workers until a
+/// shutdown signal, then drain them. `ignored_config` are the invalid values
+/// `Config::load` ignored, logged once logging is set up.
+pub async fn serve(config: Config, ignored_config: &[ConfigIssue]) -> std::io::Result<()> {
+    // Logging is not set up yet, the error is reported by the caller
+    let tracer_provider = telemetry::init_tracer_provider(&config).map_err(|e| {
+        std::io::Error::other(format!("Failed to initialize tracing exporter: {}", e))
+    })?;
+    logger::init_logger(&config, &tracer_provider);
+    logger::log_ignored_config(ignored_config);
+    tracing::info!(config = ?config, "⚙️ Configuration loaded");
+
+    let cache = LruCache::from_config(&config);
+    let result = if sqlite::is_sqlite_url(&config.database_url) {
+        serve_sqlite(config, cache).await
+    } else {
+        serve_postgres(config, cache).await
+    };
+
+    // Flush any spans still buffered by the exporter
+    if let Err(e) = tracer_provider.shutdown() {
+        eprintln!("Failed to shut down tracer provider: {}", e);
+    }
+    result
+}
+
+async fn serve_postgres(config: Config, cache: Option<Arc<dyn Cache>>) -> std::io::Result<()> {
+    // Initialize database connection pools
+    let pools = match db::init_pools(&db::DatabaseConfig::from(&config)).await {
+        Ok(pools) => pools.with_cache(cache.clone()),
+        Err(e) => {
+            tracing::error!(error = %e, "❌ Failed to initialize database pool");
+            return Err(std::io::Error::other(e));
+        }
+    };
+    let pool = pools.primary().clone();
+
+    // Database health check
+    if let Err(e) = db::health_check(&pool).await {
+        tracing::error!(error = %e, "❌ Database health check failed");
+        return Err(std::io::Error::other(e));
+    }
+
+    if config.run_migrations_on_startup {
+        if let Err(e) = db::run_migrations(&pool).await {
+            tracing::error!(error = %e, "❌ Database migrations failed");
+            return Err(std::io::Error::other(e));
+        }
+    } else if let Err(e) = db::check_schema_compatibility(&pool).await {
+        tracing::error!(error = %e, "❌ Database schema is not supported by this build");
+        return Err(std::io::Error::other(e));
+    }
+
+    let state = AppState::new(config.clone(), pools.clone()).map_err(std::io::Error::other)?;
+    let grpc_listener = bind_grpc_server(&config).await?;
+    let readiness = state.readiness().clone();
+    let shutdown = state.shutdown().clone();
+
+    let change_feed = match ChangeFeed::start(
+        &pool,
+        Duration::from_secs(config.sse_heartbeat_secs),
+        &shutdown,
+    )
+    .await
+    {
+        Ok(change_feed) => change_feed,
+        Err(e) => {
+            tracing::error!(error = %e, "❌ Failed to start change feed listener");
+            return Err(std::io::Error::other(e));
+        }
+    };
+
+    if let Some(cache) = cache {
+        workers::spawn_cache_invalidator(cache, &change_feed, &shutdown);
+    }
+
+    workers::spawn_alert_evaluator(
+        pool.clone(),
+        Duration::from_secs(config.alert_eval_interval_secs),
+        readiness.clone(),
+        &shutdown,
+    );
+
+    workers::spawn_replica_monitor(
+        pools.clone(),
+        Duration::from_secs(config.db_replica_check_interval_secs),
+        readiness.clone(),
+        &shutdown,
+    );
+
+    spawn_grpc_server(grpc_listener, &state);
+
+    let result = run_http_server(&config, state.with_change_feed(change_feed)).await;
+    shutdown.finish(&pool).await;
+    if let Some(replica) = pools.replica() {
+        replica.close().await;
+    }
+    result
+}
+
+/// Serve the savings API from a SQLite database. Without Postgres there are
+/// no alerts, no GraphQL and no change notifications, streams only replay
+/// the recorded changes.
+async fn serve_sqlite(config: Config, cache: Option<Arc<dyn Cache>>) -> std::io::Result<()> {
+    let pool = match sqlite::init_pool(&db::DatabaseConfig::from(&config)).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            tracing::error!(error = %e, "❌ Failed to initialize SQLite database");
+            return Err(std::io::Error::other(e));
+        }
+    };
+
+    if config.run_migrations_on_startup {
+        if let Err(e) = sqlite::run_migrations(&pool).await {
+            tracing::error!(error = %e, "❌ Database migrations failed");
+            return Err(std::io::Error::other(e));
+        }
+    } else if let Err(e) = sqlite::check_schema_compatibility(&pool).await {
+        tracing::error!(error = %e, "❌ Database schema is not supported by this build");
+        return Err(std::io::Error::other(e));
+    }
+    tracing::warn!("⚠️ Running on SQLite: alerts, GraphQL and change notifications are disabled");
+
+    let storage = Storage::from(SqliteSavingsRepository::new(pool.clone()).with_cache(cache));
+    let state = AppState::new(config.clone(), storage).map_err(std::io::Error::other)?;
+    let grpc_listener = bind_grpc_server(&config).await?;
+    let shutdown = state.shutdown().clone();
+
+    spawn_grpc_server(grpc_listener, &state);
+
+    let result = run_http_server(&config, state).await;
+    shutdown.finish(&pool).await;
+    result
+}
+
+/// Bind the gRPC port before anything is started, so that startup fails
+/// when it can't be
+async fn bind_grpc_server(config: &Config) -> std::io::Result<TcpListener> {
+    grpc::bind(&config.app_host, config.grpc_port)
+        .await
+        .inspect_err(|e| {
+            tracing::error!(
+                error = %e,
+                host = %config.app_host,
+                port = config.grpc_port,
+                "❌ Failed to bind the gRPC server"
+            )
+        })
+}
+
+fn spawn_grpc_server(listener: TcpListener, state: &AppState) {
+    let state = state.clone();
+    let shutdown = state.shutdown().clone();
+    let grpc_shutdown = shutdown.token();
+    shutdown.spawn("grpc_server", async move {
+        if let Err(e) = grpc::serve(state, listener, grpc_shutdown).await {
+            tracing::error!(error = %e, "❌ gRPC server failed");
+        }
+    });
+}
+
+/// Serve `state` until the shutdown coordinator stops the server
+async fn run_http_server(config: &Config, state: AppState) -> std::io::Result<()> {
+    let bind_address = (config.app_host.as_str(), config.port);
+    let workers = num_cpus::get().clamp(1, 4);
+    let shutdown = state.shutdown().clone();
+
+    let server = HttpServer::new(move || state.app());
+
+    tracing::info!(
+        service = %config.name,
+        host = %config.app_host,
+        port = config.port,
+        "🚀 Application running"
+    );
+    // Signals are handled by the shutdown coordinator instead of actix
+    let server = server
+        .workers(workers)
+        .disable_signals()
+        .shutdown_timeout(config.shutdown_timeout_secs)
+        .bind(bind_address)?
+        .run();
+
+    let server_handle = server.handle();
+    tokio::spawn(async move {
+        shutdown.wait_for_signal().await;
+        shutdown.begin(server_handle).await;
+    });
+
+    server.await
+}
@@ -0,0 +1,218 @@
+use crate::errors::{AppError, AppResult};
+use crate::models::alerts::{
+    Alert, AlertKind, AlertState, AlertThreshold, AlertsQuery, CreateAlertThreshold,
+};
+use crate::models::transactions::SIGNED_AMOUNT_SQL;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+const DEFAULT_ALERTS_LIMIT: i64 = 100;
+const MAX_ALERTS_LIMIT: i64 = 1000;
+
+pub struct AlertsService;
+
+impl AlertsService {
+    #[tracing::instrument(name = "AlertsService::create_threshold", skip_all, fields(db.system = "postgresql"))]
+    pub async fn create_threshold(
+        db: &PgPool,
+        payload: &CreateAlertThreshold,
+    ) -> AppResult<AlertThreshold> {
+        sqlx::query_as::<_, AlertThreshold>(
+            r#"
+            INSERT INTO alert_thresholds (source, currency, period, comparison, threshold, hysteresis)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, source, currency, period, comparison, threshold, hysteresis, state,
+                      last_value, last_evaluated_at, created_at, updated_at
+            "#,
+        )
+        .bind(&payload.source)
+        .bind(&payload.currency)
+        .bind(payload.period)
+        .bind(payload.comparison)
+        .bind(payload.threshold)
+        .bind(payload.hysteresis)
+        .fetch_one(db)
+        .await
+        .map_err(AppError::from)
+    }
+
+    #[tracing::instrument(name = "AlertsService::list_thresholds", skip_all, fields(db.system = "postgresql"))]
+    pub async fn list_thresholds(db: &PgPool) -> AppResult<Vec<AlertThreshold>> {
+        sqlx::query_as::<_, AlertThreshold>(
+            r#"
+            SELECT id, source, currency, period, comparison, threshold, hysteresis, state,
+                   last_value, last_evaluated_at, created_at, updated_at
+            FROM alert_thresholds
+            ORDER BY id
+            "#,
+        )
+        .fetch_all(db)
+        .await
+        .map_err(AppError::from)
+    }
+
+    // Thresholds watching any of the given sources, used for batched loading
+    #[tracing::instrument(name = "AlertsService::thresholds_for_sources", skip(db), fields(db.system = "postgresql"))]
+    pub async fn thresholds_for_sources(
+        db: &PgPool,
+        sources: &[String],
+    ) -> AppResult<Vec<AlertThreshold>> {
+        sqlx::query_as::<_, AlertThreshold>(
+            r#"
+            SELECT id, source, currency, period, comparison, threshold, hysteresis, state,
+                   last_value, last_evaluated_at, created_at, updated_at
+            FROM alert_thresholds
+            WHERE source = ANY($1)
+            ORDER BY id
+            "#,
+        )
+        .bind(sources)
+        .fetch_all(db)
+        .await
+        .map_err(AppError::from)
+    }
+
+    #[tracing::instrument(name = "AlertsService::delete_threshold", skip(db), fields(db.system = "postgresql"))]
+    pub async fn delete_threshold(db: &PgPool, threshold_id: i64) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM alert_thresholds WHERE id = $1")
+            .bind(threshold_id)
+            .execute(db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::ThresholdNotFound(threshold_id));
+        }
+
+        Ok(())
+    }
+
+    // List alert records in ascending order so clients can poll with `since_id`
+    #[tracing::instrument(name = "AlertsService::list_alerts", skip(db), fields(db.system = "postgresql"))]
+    pub async fn list_alerts(db: &PgPool, query: &AlertsQuery) -> AppResult<Vec<Alert>> {
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_ALERTS_LIMIT)
+            .clamp(1, MAX_ALERTS_LIMIT);
+
+        sqlx::query_as::<_, Alert>(
+            r#"
+            SELECT id, threshold_id, kind, value, threshold, created_at
+            FROM alerts
+            WHERE id > $1
+            ORDER BY id ASC
+            LIMIT $2
+            "#,
+        )
+        .bind(query.since_id.unwrap_or(0))
+        .bind(limit)
+        .fetch_all(db)
+        .await
+        .map_err(AppError::from)
+    }
+
+    // Alert history for any of the given thresholds, used for batched loading
+    #[tracing::instrument(name = "AlertsService::alerts_for_thresholds", skip(db), fields(db.system = "postgresql"))]
+    pub async fn alerts_for_thresholds(
+        db: &PgPool,
+        threshold_ids: &[i64],
+    ) -> AppResult<Vec<Alert>> {
+        sqlx::query_as::<_, Alert>(
+            r#"
+            SELECT id, threshold_id, kind, value, threshold, created_at
+            FROM alerts
+            WHERE threshold_id = ANY($1)
+            ORDER BY id ASC
+            "#,
+        )
+        .bind(threshold_ids)
+        .fetch_all(db)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// Evaluate every threshold against the current savings aggregate and
+    /// record an alert for each state transition. Returns the number of
+    /// alerts created.
+    #[tracing::instrument(name = "AlertsService::evaluate_thresholds", skip_all, fields(db.system = "postgresql"))]
+    pub async fn evaluate_thresholds(db: &PgPool) -> AppResult<usize> {
+        let thresholds = Self::list_thresholds(db).await?;
+        let mut created = 0;
+
+        for threshold in thresholds {
+            let value = Self::aggregate_for(db, &threshold).await?;
+            if Self::apply_evaluation(db, &threshold, value).await? {
+                created += 1;
+            }
+        }
+
+        Ok(created)
+    }
+
+    #[tracing::instrument(name = "AlertsService::aggregate_for", skip_all, fields(db.system = "postgresql", threshold_id = threshold.id))]
+    async fn aggregate_for(db: &PgPool, threshold: &AlertThreshold) -> AppResult<Decimal> {
+        sqlx::query_scalar::<_, Decimal>(&format!(
+            r#"
+            SELECT COALESCE(SUM({SIGNED_AMOUNT_SQL}), 0)
+            FROM transactions
+            WHERE source = $1 AND currency = $2 AND created_at >= date_trunc($3, NOW())
+            "#
+        ))
+        .bind(&threshold.source)
+        .bind(&threshold.currency)
+        .bind(threshold.period.as_str())
+        .fetch_one(db)
+        .await
+        .map_err(AppError::from)
+    }
+
+    // Persist the evaluation result, returns true when an alert was recorded.
+    // Every replica evaluates, the update only applies from the state this one
+    // read so that a transition is recorded once.
+    #[tracing::instrument(name = "AlertsService::apply_evaluation", skip_all, fields(db.system = "postgresql", threshold_id = threshold.id))]
+    async fn apply_evaluation(
+        db: &PgPool,
+        threshold: &AlertThreshold,
+        value: Decimal,
+    ) -> AppResult<bool> {
+        let next_state = threshold.next_state(value);
+        let mut tx = db.begin().await?;
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE alert_thresholds
+            SET state = $1, last_value = $2, last_evaluated_at = NOW()
+            WHERE id = $3 AND state = $4
+            "#,
+        )
+        .bind(next_state)
+        .bind(value)
+        .bind(threshold.id)
+        .bind(threshold.state)
+        .execute(&mut *tx)
+        .await?;
+
+        let transitioned = next_state != threshold.state && updated.rows_affected() == 1;
+        if transitioned {
+            let kind = match next_state {
+                AlertState::Triggered => AlertKind::Triggered,
+                AlertState::Ok => AlertKind::Resolved,
+            };
+
+            sqlx::query(
+                r#"
+                INSERT INTO alerts (threshold_id, kind, value, threshold)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(threshold.id)
+            .bind(kind)
+            .bind(value)
+            .bind(threshold.threshold)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(transitioned)
+    }
+}
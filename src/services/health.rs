@@ -0,0 +1,121 @@
+use crate::adapters::{db, readiness::Readiness, sqlite};
+use crate::models::health::{ComponentCheck, ReadinessReport};
+use crate::repositories::Storage;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+pub struct HealthService;
+
+impl HealthService {
+    /// Check every dependency the service needs before it can take traffic
+    #[tracing::instrument(name = "HealthService::readiness", skip_all)]
+    pub async fn readiness(
+        db: &Storage,
+        readiness: &Readiness,
+        timeout: Duration,
+    ) -> ReadinessReport {
+        let mut components = BTreeMap::new();
+
+        components.insert(
+            "shutdown".to_string(),
+            if readiness.is_shutting_down() {
+                ComponentCheck::down("Graceful shutdown in progress")
+            } else {
+                ComponentCheck::up()
+            },
+        );
+        components.insert(
+            "database".to_string(),
+            Self::check_database(db, timeout).await,
+        );
+        components.insert(
+            "migrations".to_string(),
+            Self::check_migrations(db, timeout).await,
+        );
+        components.insert("workers".to_string(), Self::check_workers(readiness));
+
+        ReadinessReport::new(components)
+    }
+
+    async fn check_database(db: &Storage, timeout: Duration) -> ComponentCheck {
+        let started = Instant::now();
+        let check = async {
+            match db {
+                Storage::Postgres(pools) => db::health_check(pools.primary()).await,
+                Storage::Sqlite(repo) => sqlite::health_check(repo.pool()).await,
+            }
+        };
+        match tokio::time::timeout(timeout, check).await {
+            Ok(Ok(())) => ComponentCheck {
+                latency_ms: Some(started.elapsed().as_millis() as u64),
+                ..ComponentCheck::up()
+            },
+            // The error may name hosts or users, it is only logged
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "⚠️ Readiness: database unreachable");
+                ComponentCheck::down("Database unreachable")
+            }
+            Err(_) => ComponentCheck::down(format!(
+                "Database did not answer within {}ms",
+                timeout.as_millis()
+            )),
+        }
+    }
+
+    async fn check_migrations(db: &Storage, timeout: Duration) -> ComponentCheck {
+        let pending = async {
+            match db {
+                Storage::Postgres(pools) => db::pending_migrations(pools.primary()).await,
+                Storage::Sqlite(repo) => sqlite::pending_migrations(repo.pool()).await,
+            }
+        };
+        match tokio::time::timeout(timeout, pending).await {
+            Ok(Ok(pending)) if pending.is_empty() => ComponentCheck::up(),
+            Ok(Ok(pending)) => ComponentCheck {
+                details: Some(serde_json::json!({ "pending": pending })),
+                ..ComponentCheck::down(format!("{} pending migrations", pending.len()))
+            },
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "⚠️ Readiness: failed to read migrations");
+                ComponentCheck::down("Failed to read migrations")
+            }
+            Err(_) => ComponentCheck::down(format!(
+                "Migrations were not read within {}ms",
+                timeout.as_millis()
+            )),
+        }
+    }
+
+    fn check_workers(readiness: &Readiness) -> ComponentCheck {
+        let workers = readiness.workers();
+        let stalled: Vec<&str> = workers
+            .iter()
+            .filter(|worker| !worker.healthy)
+            .map(|worker| worker.name)
+            .collect();
+
+        let details = workers
+            .iter()
+            .map(|worker| {
+                (
+                    worker.name.to_string(),
+                    serde_json::json!({
+                        "healthy": worker.healthy,
+                        "seconds_since_last_beat": worker.since_last_beat.map(|d| d.as_secs()),
+                    }),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>();
+
+        let check = if stalled.is_empty() {
+            ComponentCheck::up()
+        } else {
+            ComponentCheck::down(format!("Stalled workers: {}", stalled.join(", ")))
+        };
+
+        ComponentCheck {
+            details: Some(serde_json::Value::Object(details)),
+            ..check
+        }
+    }
+}
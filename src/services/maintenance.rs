@@ -0,0 +1,129 @@
+use crate::errors::AppResult;
+use crate::models::transactions::{ImportedTransaction, Transaction, TransactionKind};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, types::Json};
+use tokio_stream::Stream;
+
+/// Rows inserted per statement by `insert_transactions`
+const INSERT_BATCH_SIZE: usize = 1000;
+
+/// Rows removed, or that would be removed, by a purge
+#[derive(Debug, Clone, Default)]
+pub struct PurgeSummary {
+    pub transaction_events: u64,
+    pub alerts: u64,
+}
+
+/// Bulk operations run from the management commands
+pub struct MaintenanceService;
+
+impl MaintenanceService {
+    /// Insert transactions in a single database transaction, either all of
+    /// them are stored or none. Missing creation dates default to now.
+    #[tracing::instrument(name = "MaintenanceService::insert_transactions", skip_all, fields(db.system = "postgresql", count = records.len()))]
+    pub async fn insert_transactions(
+        db: &PgPool,
+        records: &[ImportedTransaction],
+    ) -> AppResult<u64> {
+        let mut tx = db.begin().await?;
+        let mut inserted = 0;
+
+        for batch in records.chunks(INSERT_BATCH_SIZE) {
+            let amounts: Vec<Decimal> = batch.iter().map(|record| record.amount).collect();
+            let currencies: Vec<String> =
+                batch.iter().map(|record| record.currency.clone()).collect();
+            let kinds: Vec<TransactionKind> = batch.iter().map(|record| record.kind).collect();
+            // Postgres has no arrays of arrays, tags travel as JSON arrays
+            let tags: Vec<Json<&[String]>> = batch
+                .iter()
+                .map(|record| Json(record.tags.as_slice()))
+                .collect();
+            let sources: Vec<String> = batch.iter().map(|record| record.source.clone()).collect();
+            let created_at: Vec<Option<DateTime<Utc>>> =
+                batch.iter().map(|record| record.created_at).collect();
+
+            inserted += sqlx::query(
+                r#"
+                INSERT INTO transactions (amount, currency, kind, tags, source, created_at, updated_at)
+                SELECT amount, currency, kind,
+                       ARRAY(SELECT jsonb_array_elements_text(tags)),
+                       source, COALESCE(created_at, NOW()), COALESCE(created_at, NOW())
+                FROM UNNEST($1::DECIMAL[], $2::TEXT[], $3::TEXT[], $4::JSONB[], $5::VARCHAR[], $6::TIMESTAMPTZ[])
+                    AS t(amount, currency, kind, tags, source, created_at)
+                "#,
+            )
+            .bind(amounts)
+            .bind(currencies)
+            .bind(kinds)
+            .bind(tags)
+            .bind(sources)
+            .bind(created_at)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    /// Every transaction, oldest first, without loading them all in memory
+    pub fn stream_transactions(
+        db: &PgPool,
+    ) -> impl Stream<Item = Result<Transaction, sqlx::Error>> + '_ {
+        sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT id, amount, currency, kind, tags, source, created_at, updated_at
+            FROM transactions
+            ORDER BY id
+            "#,
+        )
+        .fetch(db)
+    }
+
+    /// Count the change events and alerts created before `before`
+    #[tracing::instrument(name = "MaintenanceService::count_history", skip(db), fields(db.system = "postgresql"))]
+    pub async fn count_history(db: &PgPool, before: DateTime<Utc>) -> AppResult<PurgeSummary> {
+        let (transaction_events, alerts): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM transaction_events WHERE created_at < $1),
+                (SELECT COUNT(*) FROM alerts WHERE created_at < $1)
+            "#,
+        )
+        .bind(before)
+        .fetch_one(db)
+        .await?;
+
+        Ok(PurgeSummary {
+            transaction_events: transaction_events as u64,
+            alerts: alerts as u64,
+        })
+    }
+
+    /// Delete the change events and alerts created before `before`.
+    /// Transactions and thresholds themselves are never purged.
+    #[tracing::instrument(name = "MaintenanceService::purge_history", skip(db), fields(db.system = "postgresql"))]
+    pub async fn purge_history(db: &PgPool, before: DateTime<Utc>) -> AppResult<PurgeSummary> {
+        let mut tx = db.begin().await?;
+
+        let transaction_events =
+            sqlx::query("DELETE FROM transaction_events WHERE created_at < $1")
+                .bind(before)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        let alerts = sqlx::query("DELETE FROM alerts WHERE created_at < $1")
+            .bind(before)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        tx.commit().await?;
+        Ok(PurgeSummary {
+            transaction_events,
+            alerts,
+        })
+    }
+}
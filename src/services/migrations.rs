@@ -0,0 +1,16 @@
+use crate::adapters::db;
+use crate::errors::AppResult;
+use crate::models::migrations::MigrationStatus;
+use sqlx::PgPool;
+
+pub struct MigrationsService;
+
+impl MigrationsService {
+    #[tracing::instrument(name = "MigrationsService::status", skip_all, fields(db.system = "postgresql"))]
+    pub async fn status(db: &PgPool) -> AppResult<MigrationStatus> {
+        let applied = db::applied_migrations(db).await?;
+        let pending = db::pending_migrations(db).await?;
+
+        Ok(MigrationStatus { applied, pending })
+    }
+}
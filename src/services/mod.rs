@@ -1,3 +1,11 @@
+mod alerts;
+mod health;
+mod maintenance;
+mod migrations;
 mod savings;
 
+pub use alerts::AlertsService;
+pub use health::HealthService;
+pub use maintenance::{MaintenanceService, PurgeSummary};
+pub use migrations::MigrationsService;
 pub use savings::SavingsService;
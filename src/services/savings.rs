@@ -1,62 +1,126 @@
+use crate::adapters::cache::{self, Cache};
+use crate::adapters::metrics::METRICS;
 use crate::errors::{AppError, AppResult};
-use crate::models::transactions::{CreateTransaction, Transaction, UpdateTransaction};
-use sqlx::PgPool;
+use crate::models::events::TransactionEvent;
+use crate::models::transactions::{
+    CreateTransaction, ImportedTransaction, SourceAggregate, Transaction, TransactionFilter,
+    UpdateTransaction,
+};
+use crate::repositories::SavingsRepository;
+use validator::Validate;
+
+const SAVING_KEY_PREFIX: &str = "saving:";
+const COUNT_KEY_PREFIX: &str = "count:";
+const AGGREGATES_KEY_PREFIX: &str = "aggregates:";
 
 pub struct SavingsService;
 
 impl SavingsService {
+    /// Drop the cached reads a change to `saving_id` makes stale: the saving
+    /// itself and every count and aggregate
+    pub fn invalidate_cache(cache: &dyn Cache, saving_id: i64) {
+        cache.remove(&format!("{}{}", SAVING_KEY_PREFIX, saving_id));
+        cache.remove_prefix(COUNT_KEY_PREFIX);
+        cache.remove_prefix(AGGREGATES_KEY_PREFIX);
+    }
+
+    fn invalidate_local_cache(repo: &impl SavingsRepository, saving_id: i64) {
+        if let Some(cache) = repo.cache() {
+            Self::invalidate_cache(cache.as_ref(), saving_id);
+            METRICS.record_cache_invalidation("local");
+        }
+    }
+
+    #[tracing::instrument(name = "SavingsService::create_new_saving", skip_all, fields(source = %payload.source))]
     pub async fn create_new_saving(
-        db: &PgPool,
+        repo: &impl SavingsRepository,
         payload: &CreateTransaction,
     ) -> AppResult<Transaction> {
-        sqlx::query_as::<_, Transaction>(
-            r#"
-            INSERT INTO transactions (amount, source, created_at, updated_at)
-            VALUES ($1, $2, NOW(), NOW())
-            RETURNING id, amount, source, created_at, updated_at
-            "#,
-        )
-        .bind(payload.amount)
-        .bind(&payload.source)
-        .fetch_one(db)
-        .await
-        .map_err(AppError::from)
+        payload.validate()?;
+        let transaction = repo.insert(payload).await?;
+
+        // A lookup of the new id may have cached its absence
+        Self::invalidate_local_cache(repo, transaction.id);
+        METRICS.record_saving_created(&transaction);
+        Ok(transaction)
     }
 
-    pub async fn get_by_id(db: &PgPool, id: i64) -> AppResult<Option<Transaction>> {
-        sqlx::query_as::<_, Transaction>(
-            r#"
-            SELECT id, amount, source, created_at, updated_at
-            FROM transactions
-            WHERE id = $1
-            "#,
-        )
-        .bind(id)
-        .fetch_optional(db)
-        .await
-        .map_err(AppError::from)
+    /// Insert every record or none of them
+    #[tracing::instrument(name = "SavingsService::import_savings", skip_all, fields(count = records.len()))]
+    pub async fn import_savings(
+        repo: &impl SavingsRepository,
+        records: &[ImportedTransaction],
+    ) -> AppResult<u64> {
+        for record in records {
+            record.validate()?;
+        }
+        let imported = repo.insert_many(records).await?;
+        METRICS.record_savings_imported(records);
+
+        if let Some(cache) = repo.cache() {
+            cache.clear();
+            METRICS.record_cache_invalidation("local");
+        }
+        Ok(imported)
+    }
+
+    #[tracing::instrument(name = "SavingsService::get_by_id", skip(repo))]
+    pub async fn get_by_id(
+        repo: &impl SavingsRepository,
+        id: i64,
+    ) -> AppResult<Option<Transaction>> {
+        let key = format!("{}{}", SAVING_KEY_PREFIX, id);
+        cache::get_or_load(repo.cache(), "saving", key, || repo.find(id)).await
     }
 
     // List all transactions
-    pub async fn list_savings(db: &PgPool, limit: i32, offset: i32) -> AppResult<Vec<Transaction>> {
-        sqlx::query_as::<_, Transaction>(
-            r#"
-            SELECT id, amount, source, created_at, updated_at
-            FROM transactions
-            ORDER BY created_at DESC
-            LIMIT $1 OFFSET $2
-            "#,
-        )
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(db)
+    #[tracing::instrument(name = "SavingsService::list_savings", skip(repo))]
+    pub async fn list_savings(
+        repo: &impl SavingsRepository,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<Transaction>> {
+        repo.search(&TransactionFilter::default(), limit, offset)
+            .await
+    }
+
+    // Search transactions matching a filter, newest first
+    #[tracing::instrument(name = "SavingsService::search_savings", skip(repo))]
+    pub async fn search_savings(
+        repo: &impl SavingsRepository,
+        filter: &TransactionFilter,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<Vec<Transaction>> {
+        repo.search(filter, limit, offset).await
+    }
+
+    #[tracing::instrument(name = "SavingsService::count_savings", skip(repo))]
+    pub async fn count_savings(
+        repo: &impl SavingsRepository,
+        filter: &TransactionFilter,
+    ) -> AppResult<i64> {
+        let key = filter_key(COUNT_KEY_PREFIX, filter)?;
+        cache::get_or_load(repo.cache(), "count", key, || repo.count(filter)).await
+    }
+
+    // Total saved per source for transactions matching a filter
+    #[tracing::instrument(name = "SavingsService::source_aggregates", skip(repo))]
+    pub async fn source_aggregates(
+        repo: &impl SavingsRepository,
+        filter: &TransactionFilter,
+    ) -> AppResult<Vec<SourceAggregate>> {
+        let key = filter_key(AGGREGATES_KEY_PREFIX, filter)?;
+        cache::get_or_load(repo.cache(), "aggregates", key, || {
+            repo.source_aggregates(filter)
+        })
         .await
-        .map_err(AppError::from)
     }
 
     // Updates
+    #[tracing::instrument(name = "SavingsService::update_saving", skip(repo, payload))]
     pub async fn update_saving(
-        db: &PgPool,
+        repo: &impl SavingsRepository,
         saving_id: i64,
         payload: &UpdateTransaction,
     ) -> AppResult<Transaction> {
@@ -65,40 +129,46 @@ impl SavingsService {
                 "At least one field must be provided for update".to_string(),
             ));
         }
+        payload.validate()?;
+
+        let result = repo.update(saving_id, payload).await?;
 
-        let result = sqlx::query_as::<_, Transaction>(
-            r#"
-            UPDATE transactions
-            SET
-                amount = COALESCE($1, amount),
-                source = COALESCE($2, source),
-                updated_at = NOW()
-            WHERE id = $3
-            RETURNING id, amount, source, created_at, updated_at
-            "#,
-        )
-        .bind(&payload.amount)
-        .bind(&payload.source)
-        .bind(saving_id)
-        .fetch_optional(db)
-        .await?;
-
-        result.ok_or_else(|| AppError::NotFound(format!("Saving with ID {} not found", saving_id)))
+        Self::invalidate_local_cache(repo, saving_id);
+        result.ok_or(AppError::SavingNotFound(saving_id))
     }
 
-    pub async fn delete_saving(db: &PgPool, saving_id: i64) -> AppResult<()> {
-        let result = sqlx::query("DELETE FROM transactions WHERE id = $1")
-            .bind(saving_id)
-            .execute(db)
-            .await?;
-
-        if result.rows_affected() == 0 {
-            return Err(AppError::NotFound(format!(
-                "Saving with ID {} not found",
-                saving_id
-            )));
+    #[tracing::instrument(name = "SavingsService::delete_saving", skip(repo))]
+    pub async fn delete_saving(repo: &impl SavingsRepository, saving_id: i64) -> AppResult<()> {
+        let deleted = repo.delete(saving_id).await?;
+
+        Self::invalidate_local_cache(repo, saving_id);
+        if !deleted {
+            return Err(AppError::SavingNotFound(saving_id));
         }
 
         Ok(())
     }
+
+    // List recorded change events after a given event id, oldest first
+    #[tracing::instrument(name = "SavingsService::list_events_since", skip(repo))]
+    pub async fn list_events_since(
+        repo: &impl SavingsRepository,
+        after_id: i64,
+        source: Option<&str>,
+        limit: i64,
+    ) -> AppResult<Vec<TransactionEvent>> {
+        repo.events_since(after_id, source, limit).await
+    }
+
+    #[tracing::instrument(name = "SavingsService::last_event_id", skip(repo))]
+    pub async fn last_event_id(repo: &impl SavingsRepository) -> AppResult<i64> {
+        repo.last_event_id().await
+    }
+}
+
+/// Cache key of a query over the transactions matching `filter`
+fn filter_key(prefix: &str, filter: &TransactionFilter) -> AppResult<String> {
+    let filter = serde_json::to_string(filter)
+        .map_err(|e| AppError::InternalServerError(format!("Invalid filter: {}", e)))?;
+    Ok(format!("{}{}", prefix, filter))
 }
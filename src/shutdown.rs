@@ -0,0 +1,189 @@
+use crate::adapters::readiness::Readiness;
+use actix_web::{
+    Error,
+    body::MessageBody,
+    dev::{ServerHandle, ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web::Data,
+};
+use sqlx::{Database, Pool};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+const POOL_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct InFlight {
+    count: AtomicUsize,
+    idle: Notify,
+}
+
+// Counts a request as in flight until its handler has produced a response
+struct InFlightGuard(Arc<InFlight>);
+
+impl InFlightGuard {
+    fn new(in_flight: Arc<InFlight>) -> Self {
+        in_flight.count.fetch_add(1, Ordering::SeqCst);
+        Self(in_flight)
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+/// Coordinates a graceful shutdown: readiness goes down, the HTTP server stops
+/// accepting connections and drains in-flight requests, registered background
+/// tasks are cancelled and awaited, and finally the database pool is closed.
+/// Everything after the signal has to fit in the configured deadline.
+#[derive(Clone)]
+pub struct ShutdownCoordinator {
+    token: CancellationToken,
+    tasks: TaskTracker,
+    readiness: Readiness,
+    deadline: Duration,
+    started_at: Arc<OnceLock<Instant>>,
+    in_flight: Arc<InFlight>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(readiness: Readiness, deadline: Duration) -> Self {
+        Self {
+            token: CancellationToken::new(),
+            tasks: TaskTracker::new(),
+            readiness,
+            deadline,
+            started_at: Arc::new(OnceLock::new()),
+            in_flight: Arc::new(InFlight::default()),
+        }
+    }
+
+    /// Cancelled as soon as the shutdown starts
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    pub fn deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    /// Spawn a background task the shutdown waits for. The task is expected to
+    /// return once `token()` is cancelled.
+    pub fn spawn<F>(&self, name: &'static str, task: F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.spawn(async move {
+            task.await;
+            tracing::info!(task = name, "Background task stopped");
+        })
+    }
+
+    /// Resolve on SIGINT or SIGTERM
+    pub async fn wait_for_signal(&self) {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("Failed to install SIGTERM handler");
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+
+    /// Number of requests whose handler has not produced a response yet
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.count.load(Ordering::SeqCst)
+    }
+
+    /// Mark the service as not ready, stop accepting connections, cancel
+    /// background work and wait for in-flight requests before stopping the
+    /// server. Requests still running at the deadline are abandoned.
+    pub async fn begin(&self, server: ServerHandle) {
+        if self.started_at.set(Instant::now()).is_err() {
+            return;
+        }
+
+        tracing::info!(
+            deadline_secs = self.deadline.as_secs(),
+            in_flight = self.in_flight(),
+            "🛑 Shutdown requested, draining in-flight requests"
+        );
+        self.readiness.mark_shutting_down();
+        server.pause().await;
+        self.token.cancel();
+
+        if tokio::time::timeout(self.deadline, self.wait_for_idle())
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                in_flight = self.in_flight(),
+                "⚠️ Requests still in flight at the shutdown deadline"
+            );
+        }
+
+        // Nothing is in flight anymore, stopping only closes idle connections
+        server.stop(true).await;
+    }
+
+    async fn wait_for_idle(&self) {
+        loop {
+            // Register before checking so a wake-up in between is not missed
+            let idle = self.in_flight.idle.notified();
+            if self.in_flight() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    /// Wait for registered background tasks up to the deadline, then close the pool
+    pub async fn finish<DB: Database>(&self, pool: &Pool<DB>) {
+        self.token.cancel();
+        self.tasks.close();
+
+        let started_at = *self.started_at.get_or_init(Instant::now);
+        let remaining = self.deadline.saturating_sub(started_at.elapsed());
+        if tokio::time::timeout(remaining, self.tasks.wait())
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                pending = self.tasks.len(),
+                "⚠️ Background tasks still running at the shutdown deadline"
+            );
+        }
+
+        if tokio::time::timeout(POOL_CLOSE_TIMEOUT, pool.close())
+            .await
+            .is_err()
+        {
+            tracing::warn!("⚠️ Database pool did not close in time");
+        }
+
+        tracing::info!("✅ Shutdown complete");
+    }
+}
+
+/// Middleware counting the requests the shutdown has to wait for. Requires the
+/// coordinator to be registered as app data.
+pub async fn track_in_flight(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let _guard = req
+        .app_data::<Data<ShutdownCoordinator>>()
+        .map(|shutdown| InFlightGuard::new(shutdown.in_flight.clone()));
+
+    next.call(req).await
+}
@@ -0,0 +1,45 @@
+use crate::adapters::readiness::Readiness;
+use crate::services::AlertsService;
+use crate::shutdown::ShutdownCoordinator;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+const WORKER_NAME: &str = "alert_evaluator";
+
+/// Spawn the background task that periodically evaluates alert thresholds
+pub fn spawn_alert_evaluator(
+    pool: PgPool,
+    interval: Duration,
+    readiness: Readiness,
+    shutdown: &ShutdownCoordinator,
+) -> JoinHandle<()> {
+    tracing::info!(
+        interval_secs = interval.as_secs(),
+        "⏰ Starting alert evaluator"
+    );
+
+    readiness.register_worker(WORKER_NAME, interval);
+
+    let token = shutdown.token();
+    shutdown.spawn(WORKER_NAME, async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = token.cancelled() => break,
+            }
+
+            match AlertsService::evaluate_thresholds(&pool).await {
+                Ok(0) => tracing::debug!("Alert thresholds evaluated, no state changes"),
+                Ok(count) => {
+                    tracing::info!(count, "🔔 Alert thresholds evaluated, new alerts recorded")
+                }
+                Err(e) => tracing::error!(error = %e, "❌ Alert evaluation failed"),
+            }
+            readiness.beat(WORKER_NAME);
+        }
+    })
+}
@@ -0,0 +1,53 @@
+use crate::adapters::cache::Cache;
+use crate::adapters::change_feed::ChangeFeed;
+use crate::adapters::metrics::METRICS;
+use crate::models::events::FeedEvent;
+use crate::services::SavingsService;
+use crate::shutdown::ShutdownCoordinator;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+
+const WORKER_NAME: &str = "cache_invalidator";
+
+/// Spawn the background task dropping cached savings reads whenever the
+/// database publishes a change, whichever replica or command made it
+pub fn spawn_cache_invalidator(
+    cache: Arc<dyn Cache>,
+    change_feed: &ChangeFeed,
+    shutdown: &ShutdownCoordinator,
+) -> JoinHandle<()> {
+    tracing::info!("🧹 Starting cache invalidator");
+
+    let mut events = change_feed.subscribe();
+    let token = shutdown.token();
+    shutdown.spawn(WORKER_NAME, async move {
+        loop {
+            let received = tokio::select! {
+                received = events.recv() => received,
+                _ = token.cancelled() => break,
+            };
+
+            match received {
+                Ok(FeedEvent::Savings(event)) => {
+                    SavingsService::invalidate_cache(cache.as_ref(), event.transaction_id);
+                    METRICS.record_cache_invalidation("notify");
+                }
+                // Changes made while the listener was reconnecting were missed
+                Ok(FeedEvent::Resync) => {
+                    tracing::warn!("⚠️ Change feed reconnected, clearing the cache");
+                    cache.clear();
+                    METRICS.record_cache_invalidation("notify");
+                }
+                Ok(_) => {}
+                // Missed changes could be anywhere
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "⚠️ Cache invalidator lagged, clearing the cache");
+                    cache.clear();
+                    METRICS.record_cache_invalidation("notify");
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
+}
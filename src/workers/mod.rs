@@ -0,0 +1,7 @@
+mod alert_evaluator;
+mod cache_invalidator;
+mod replica_monitor;
+
+pub use alert_evaluator::spawn_alert_evaluator;
+pub use cache_invalidator::spawn_cache_invalidator;
+pub use replica_monitor::spawn_replica_monitor;
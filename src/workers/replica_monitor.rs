@@ -0,0 +1,42 @@
+use crate::adapters::{db::DbPools, readiness::Readiness};
+use crate::shutdown::ShutdownCoordinator;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+const WORKER_NAME: &str = "replica_monitor";
+
+/// Spawn the background task that periodically checks the read replica lag,
+/// deciding whether reads may use it. Does nothing without a replica.
+pub fn spawn_replica_monitor(
+    pools: DbPools,
+    interval: Duration,
+    readiness: Readiness,
+    shutdown: &ShutdownCoordinator,
+) -> Option<JoinHandle<()>> {
+    pools.replica()?;
+
+    tracing::info!(
+        interval_secs = interval.as_secs(),
+        "⏰ Starting read replica monitor"
+    );
+
+    readiness.register_worker(WORKER_NAME, interval);
+
+    let token = shutdown.token();
+    Some(shutdown.spawn(WORKER_NAME, async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = token.cancelled() => break,
+            }
+
+            if let Some(Ok(lag)) = pools.check_replica().await {
+                tracing::debug!(lag_ms = lag.as_millis() as u64, "Read replica checked");
+            }
+            readiness.beat(WORKER_NAME);
+        }
+    }))
+}
@@ -0,0 +1,372 @@
+//! The savings REST API end to end, through `app_factory`, against each
+//! storage backend. See `savings_repository` for the storage behaviour itself.
+
+mod common;
+
+use actix_web::{
+    http::{StatusCode, header},
+    test::{self, TestRequest},
+};
+use common::{send, test_config};
+use gsn_push_processing::{Config, SqliteSavingsRepository, Storage, app_factory};
+use rust_decimal::Decimal;
+use serde_json::{Value, json};
+
+macro_rules! api_tests {
+    ($($case:ident),* $(,)?) => {
+        mod sqlite {
+            use gsn_push_processing::SqliteSavingsRepository;
+
+            $(
+                #[actix_web::test]
+                async fn $case() {
+                    let pool = crate::common::sqlite_database().await;
+                    super::$case(SqliteSavingsRepository::new(pool).into()).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            $(
+                #[actix_web::test]
+                async fn $case() {
+                    crate::common::with_database(|pool| super::$case(pool.into())).await;
+                }
+            )*
+        }
+    };
+}
+
+api_tests!(
+    creates_and_gets_savings,
+    keeps_amounts_exact,
+    serves_v1_representation_with_deprecation,
+    negotiates_unversioned_paths,
+    accepts_form_bodies_on_v1,
+    rejects_invalid_savings,
+    rejects_malformed_bodies,
+    reports_missing_savings,
+    imports_savings,
+    rejects_invalid_imports_entirely,
+    reports_readiness,
+);
+
+#[actix_web::test]
+async fn rejects_invalid_limit_rules() {
+    let pool = common::sqlite_database().await;
+    let invalid = [
+        Config {
+            rate_limit_enabled: true,
+            rate_limit_default: "fast".to_string(),
+            ..test_config()
+        },
+        Config {
+            rate_limit_enabled: true,
+            rate_limit_routes: vec!["GET /savings=0/60".to_string()],
+            ..test_config()
+        },
+        Config {
+            payload_limit_routes: vec!["POST /new-saving=big".to_string()],
+            ..test_config()
+        },
+        Config {
+            api_deprecations: vec!["v1=soon".to_string()],
+            ..test_config()
+        },
+    ];
+
+    for config in invalid {
+        let storage = SqliteSavingsRepository::new(pool.clone());
+        let err = app_factory(config, storage)
+            .err()
+            .expect("invalid configuration");
+        assert_eq!(err.issues.len(), 1, "{}", err);
+    }
+}
+
+async fn creates_and_gets_savings(storage: Storage) {
+    let app = test::init_service(app_factory(test_config(), storage).unwrap()).await;
+
+    let (status, created) = send(
+        &app,
+        TestRequest::post()
+            .uri("/api/v2/new-saving")
+            .set_json(json!({
+                "amount": "12.50",
+                "currency": "EUR",
+                "kind": "interest",
+                "tags": ["monthly"],
+                "source": "bank"
+            }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["amount"], "12.5000");
+    assert_eq!(created["currency"], "EUR");
+    assert_eq!(created["kind"], "interest");
+    assert_eq!(created["tags"], json!(["monthly"]));
+    assert_eq!(created["source"], "bank");
+    assert_eq!(created["created_at"], created["updated_at"]);
+
+    let uri = format!("/api/v2/savings/{}", created["id"]);
+    let (status, found) = send(&app, TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(found, created);
+}
+
+async fn keeps_amounts_exact(storage: Storage) {
+    let app = test::init_service(app_factory(test_config(), storage).unwrap()).await;
+
+    for amount in ["0.0001", "123456789012345.1234", "0.1", "0.30"] {
+        let (status, created) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v2/new-saving")
+                .set_json(json!({ "amount": amount, "currency": "USD", "source": "bank" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let uri = format!("/api/v2/savings/{}", created["id"]);
+        let (_, found) = send(&app, TestRequest::get().uri(&uri).to_request()).await;
+        let expected = format!("{:.4}", amount.parse::<Decimal>().unwrap());
+        assert_eq!(found["amount"], expected.as_str());
+    }
+}
+
+async fn serves_v1_representation_with_deprecation(storage: Storage) {
+    let config = Config {
+        api_deprecations: vec!["v1=2026-10-19/2027-04-30".to_string()],
+        ..test_config()
+    };
+    let app = test::init_service(app_factory(config, storage).unwrap()).await;
+
+    let response = test::call_service(
+        &app,
+        TestRequest::post()
+            .uri("/api/v1/new-saving")
+            .set_json(json!({ "amount": 10, "source": "cash" }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(response.headers().contains_key("deprecation"));
+    assert!(response.headers().contains_key("sunset"));
+
+    let created: Value = test::read_body_json(response).await;
+    assert_eq!(created["amount"], "10.0000");
+    assert!(created.get("currency").is_none());
+    assert!(created.get("tags").is_none());
+
+    let uri = format!("/api/v1/savings/{}", created["id"]);
+    let (status, found) = send(&app, TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(found, created);
+}
+
+async fn negotiates_unversioned_paths(storage: Storage) {
+    let app = test::init_service(app_factory(test_config(), storage).unwrap()).await;
+
+    let (_, created) = send(
+        &app,
+        TestRequest::post()
+            .uri("/api/v2/new-saving")
+            .set_json(json!({ "amount": "1", "currency": "USD", "source": "bank" }))
+            .to_request(),
+    )
+    .await;
+    let uri = format!("/api/savings/{}", created["id"]);
+
+    // The default version, v1
+    let (status, found) = send(&app, TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(found.get("currency").is_none());
+
+    let (status, found) = send(
+        &app,
+        TestRequest::get()
+            .uri(&uri)
+            .insert_header((header::ACCEPT, "application/json; version=2"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(found["currency"], "USD");
+
+    let (status, error) = send(
+        &app,
+        TestRequest::get()
+            .uri(&uri)
+            .insert_header((header::ACCEPT, "application/json; version=9"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+    assert_eq!(error["code"], "UNSUPPORTED_API_VERSION");
+}
+
+async fn accepts_form_bodies_on_v1(storage: Storage) {
+    let app = test::init_service(app_factory(test_config(), storage).unwrap()).await;
+
+    let (status, created) = send(
+        &app,
+        TestRequest::post()
+            .uri("/api/v1/new-saving")
+            .set_form([("amount", "7.25"), ("source", "cash")])
+            .to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["amount"], "7.2500");
+}
+
+async fn rejects_invalid_savings(storage: Storage) {
+    let app = test::init_service(app_factory(test_config(), storage).unwrap()).await;
+
+    let (status, error) = send(
+        &app,
+        TestRequest::post()
+            .uri("/api/v2/new-saving")
+            .set_json(json!({ "amount": "-1", "currency": "euro", "source": "" }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "VALIDATION_FAILED");
+
+    let mut fields: Vec<&str> = error["errors"]
+        .as_array()
+        .expect("field errors")
+        .iter()
+        .map(|e| e["field"].as_str().unwrap())
+        .collect();
+    fields.sort();
+    fields.dedup();
+    assert_eq!(fields, ["amount", "currency", "source"]);
+}
+
+async fn rejects_malformed_bodies(storage: Storage) {
+    let app = test::init_service(app_factory(test_config(), storage).unwrap()).await;
+
+    let response = test::call_service(
+        &app,
+        TestRequest::post()
+            .uri("/api/v2/new-saving")
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload("{\"amount\":")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/problem+json"
+    );
+
+    let (status, error) = send(
+        &app,
+        TestRequest::post()
+            .uri("/api/v2/new-saving")
+            .insert_header((header::CONTENT_TYPE, "text/plain"))
+            .set_payload("amount=1")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(error["code"], "UNSUPPORTED_MEDIA_TYPE");
+}
+
+async fn reports_missing_savings(storage: Storage) {
+    let app = test::init_service(app_factory(test_config(), storage).unwrap()).await;
+
+    let (status, error) = send(
+        &app,
+        TestRequest::get().uri("/api/v2/savings/4242").to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error["code"], "SAVING_NOT_FOUND");
+
+    let (status, error) = send(
+        &app,
+        TestRequest::get().uri("/api/v2/savings/0").to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "BAD_REQUEST");
+
+    let (status, error) = send(
+        &app,
+        TestRequest::get().uri("/api/v2/savings/abc").to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "INVALID_PATH_PARAMETER");
+}
+
+async fn imports_savings(storage: Storage) {
+    let app = test::init_service(app_factory(test_config(), storage).unwrap()).await;
+
+    let (status, summary) = send(
+        &app,
+        TestRequest::post()
+            .uri("/api/v2/savings/import")
+            .set_json(json!({
+                "records": [
+                    { "amount": "1.5", "currency": "USD", "source": "bank", "created_at": "2026-01-01T00:00:00Z" },
+                    { "amount": "2", "currency": "EUR", "tags": ["a", "b"], "source": "cash" },
+                ]
+            }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(summary["imported"], 2);
+
+    let (status, imported) = send(
+        &app,
+        TestRequest::get().uri("/api/v2/savings/1").to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(imported["amount"], "1.5000");
+    assert_eq!(imported["created_at"], "2026-01-01T00:00:00Z");
+}
+
+async fn rejects_invalid_imports_entirely(storage: Storage) {
+    let app = test::init_service(app_factory(test_config(), storage).unwrap()).await;
+
+    let (status, error) = send(
+        &app,
+        TestRequest::post()
+            .uri("/api/v2/savings/import")
+            .set_json(json!({
+                "records": [
+                    { "amount": "1", "currency": "USD", "source": "bank" },
+                    { "amount": "0", "currency": "USD", "source": "bank" },
+                ]
+            }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "VALIDATION_FAILED");
+
+    let (status, _) = send(
+        &app,
+        TestRequest::get().uri("/api/v2/savings/1").to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+async fn reports_readiness(storage: Storage) {
+    let app = test::init_service(app_factory(test_config(), storage).unwrap()).await;
+
+    let (status, readiness) = send(&app, TestRequest::get().uri("/readyz").to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(readiness["components"]["database"]["status"], "up");
+    assert_eq!(readiness["components"]["migrations"]["status"], "up");
+}
@@ -0,0 +1,82 @@
+//! The change feed relaying database notifications, and what its subscribers
+//! do when the listener loses its connection.
+
+mod common;
+
+use common::with_database;
+use gsn_push_processing::adapters::cache::{Cache, LruCache};
+use gsn_push_processing::adapters::change_feed::ChangeFeed;
+use gsn_push_processing::adapters::readiness::Readiness;
+use gsn_push_processing::models::events::FeedEvent;
+use gsn_push_processing::shutdown::ShutdownCoordinator;
+use gsn_push_processing::workers::spawn_cache_invalidator;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+const WAIT: Duration = Duration::from_secs(5);
+
+async fn next_event(events: &mut broadcast::Receiver<FeedEvent>) -> FeedEvent {
+    tokio::time::timeout(WAIT, events.recv())
+        .await
+        .expect("a feed event")
+        .expect("an open feed")
+}
+
+#[tokio::test]
+async fn announces_listener_reconnects_and_clears_the_cache() {
+    with_database(|pool| async move {
+        let shutdown = ShutdownCoordinator::new(Readiness::new(), Duration::from_secs(5));
+        let feed = ChangeFeed::start(&pool, Duration::from_secs(30), &shutdown)
+            .await
+            .unwrap();
+        let mut events = feed.subscribe();
+
+        let cache: Arc<dyn Cache> = Arc::new(LruCache::new(
+            NonZeroUsize::new(10).unwrap(),
+            Duration::from_secs(60),
+        ));
+        let invalidator = spawn_cache_invalidator(cache.clone(), &feed, &shutdown);
+        cache.insert(
+            "savings:count:{}".to_string(),
+            b"1".to_vec(),
+            cache.generation(),
+        );
+
+        let terminated: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(pg_terminate_backend(pid))
+            FROM pg_stat_activity
+            WHERE datname = current_database() AND query LIKE 'LISTEN%'
+            "#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(terminated, 1);
+
+        assert!(matches!(next_event(&mut events).await, FeedEvent::Resync));
+        tokio::time::timeout(WAIT, async {
+            while cache.get("savings:count:{}").is_some() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the cache cleared");
+
+        // Listening again on every channel
+        sqlx::query("INSERT INTO transactions (id, amount, source) VALUES (1, 1, 'bank')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(matches!(
+            next_event(&mut events).await,
+            FeedEvent::Savings(event) if event.transaction_id == 1
+        ));
+
+        shutdown.token().cancel();
+        invalidator.await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,235 @@
+//! Status codes and problem details of every `AppError`. Constraint
+//! violations are raised for real, on a Postgres database per test and on
+//! SQLite, so the mapping of their codes is checked against the databases.
+
+mod common;
+
+use actix_web::{ResponseError, http::StatusCode};
+use common::with_database;
+use gsn_push_processing::{AppError, CreateTransaction, ErrorCode};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, SqlitePool};
+use validator::Validate;
+
+/// Status, code and detail clients get for `err`
+fn problem(err: AppError) -> (StatusCode, ErrorCode, String) {
+    let response = err.to_error_response();
+    assert_eq!(response.status, err.status_code().as_u16());
+    assert_eq!(response.problem_type, response.code.problem_type());
+    (err.status_code(), response.code, response.detail)
+}
+
+async fn postgres_error(pool: &PgPool, sql: &str) -> AppError {
+    sqlx::query(sql)
+        .execute(pool)
+        .await
+        .expect_err("statement should violate a constraint")
+        .into()
+}
+
+async fn sqlite_error(pool: &SqlitePool, sql: &str) -> AppError {
+    sqlx::query(sql)
+        .execute(pool)
+        .await
+        .expect_err("statement should violate a constraint")
+        .into()
+}
+
+#[test]
+fn maps_request_errors() {
+    let cases = [
+        (
+            AppError::BadRequest("bad".into()),
+            StatusCode::BAD_REQUEST,
+            ErrorCode::BadRequest,
+        ),
+        (
+            AppError::InvalidJson("json".into()),
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidJson,
+        ),
+        (
+            AppError::InvalidPath("path".into()),
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidPathParameter,
+        ),
+        (
+            AppError::InvalidQuery("query".into()),
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidQueryParameter,
+        ),
+        (
+            AppError::InvalidBody("body".into()),
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidBody,
+        ),
+        (
+            AppError::PayloadTooLarge(1024),
+            StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::PayloadTooLarge,
+        ),
+        (
+            AppError::UnsupportedMediaType("text/csv".into()),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::UnsupportedMediaType,
+        ),
+        (
+            AppError::UnsupportedApiVersion("v9".into()),
+            StatusCode::NOT_ACCEPTABLE,
+            ErrorCode::UnsupportedApiVersion,
+        ),
+        (
+            AppError::Unauthorized("Missing API key".into()),
+            StatusCode::UNAUTHORIZED,
+            ErrorCode::Unauthorized,
+        ),
+        (
+            AppError::NotFound("nothing here".into()),
+            StatusCode::NOT_FOUND,
+            ErrorCode::NotFound,
+        ),
+        (
+            AppError::SavingNotFound(7),
+            StatusCode::NOT_FOUND,
+            ErrorCode::SavingNotFound,
+        ),
+        (
+            AppError::ThresholdNotFound(7),
+            StatusCode::NOT_FOUND,
+            ErrorCode::ThresholdNotFound,
+        ),
+        (
+            AppError::InternalServerError("boom".into()),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::InternalError,
+        ),
+    ];
+
+    for (err, expected_status, expected_code) in cases {
+        let (status, code, _) = problem(err);
+        assert_eq!(status, expected_status, "{}", expected_code);
+        assert_eq!(code, expected_code);
+    }
+}
+
+#[test]
+fn maps_validation_errors_with_their_fields() {
+    let payload = CreateTransaction::deposit(Decimal::ZERO, String::new());
+    let err = AppError::from(payload.validate().expect_err("invalid payload"));
+
+    let response = err.to_error_response();
+    assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.code, ErrorCode::ValidationFailed);
+
+    let mut fields: Vec<String> = response
+        .errors
+        .expect("field errors")
+        .into_iter()
+        .map(|e| e.field)
+        .collect();
+    fields.sort();
+    assert_eq!(fields, ["amount", "source"]);
+}
+
+#[test]
+fn maps_missing_rows_to_not_found() {
+    let (status, code, _) = problem(sqlx::Error::RowNotFound.into());
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(code, ErrorCode::NotFound);
+}
+
+#[actix_web::test]
+async fn maps_postgres_unique_violations_to_conflict() {
+    with_database(|pool| async move {
+        let sql = "INSERT INTO transactions (id, amount, source) VALUES (1, 1, 'bank')";
+        sqlx::query(sql).execute(&pool).await.unwrap();
+
+        let (status, code, detail) = problem(postgres_error(&pool, sql).await);
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(code, ErrorCode::Duplicate);
+        // The database message, naming the constraint, is never exposed
+        assert_eq!(detail, ErrorCode::Duplicate.title());
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn maps_postgres_foreign_key_violations_to_bad_request() {
+    with_database(|pool| async move {
+        let err = postgres_error(
+            &pool,
+            "INSERT INTO alerts (threshold_id, kind, value, threshold) VALUES (42, 'triggered', 1, 1)",
+        )
+        .await;
+
+        let (status, code, _) = problem(err);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(code, ErrorCode::ReferenceNotFound);
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn maps_postgres_not_null_violations_to_bad_request() {
+    with_database(|pool| async move {
+        let err = postgres_error(
+            &pool,
+            "INSERT INTO transactions (amount, source) VALUES (1, NULL)",
+        )
+        .await;
+
+        let (status, code, _) = problem(err);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(code, ErrorCode::MissingField);
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn maps_other_postgres_errors_to_internal_errors() {
+    with_database(|pool| async move {
+        let err = postgres_error(
+            &pool,
+            "INSERT INTO transactions (amount, currency, source) VALUES (1, 'usd', 'bank')",
+        )
+        .await;
+
+        let (status, code, detail) = problem(err);
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(code, ErrorCode::DatabaseError);
+        assert_eq!(detail, ErrorCode::DatabaseError.title());
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn maps_sqlite_constraint_violations_like_postgres() {
+    let pool = common::sqlite_database().await;
+    let sql = "INSERT INTO transactions (id, amount, source) VALUES (1, '1.0000', 'bank')";
+    sqlx::query(sql).execute(&pool).await.unwrap();
+
+    let (status, code, _) = problem(sqlite_error(&pool, sql).await);
+    assert_eq!((status, code), (StatusCode::CONFLICT, ErrorCode::Duplicate));
+
+    let err = sqlite_error(
+        &pool,
+        "INSERT INTO alerts (threshold_id, kind, value, threshold) VALUES (42, 'triggered', '1', '1')",
+    )
+    .await;
+    let (status, code, _) = problem(err);
+    assert_eq!(
+        (status, code),
+        (StatusCode::BAD_REQUEST, ErrorCode::ReferenceNotFound)
+    );
+
+    let err = sqlite_error(
+        &pool,
+        "INSERT INTO transactions (amount, source) VALUES ('1.0000', NULL)",
+    )
+    .await;
+    let (status, code, _) = problem(err);
+    assert_eq!(
+        (status, code),
+        (StatusCode::BAD_REQUEST, ErrorCode::MissingField)
+    );
+}
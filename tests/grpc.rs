@@ -0,0 +1,156 @@
+//! Binding the gRPC server, whose failures must fail startup, and the API
+//! key and rate limit checks of its writes.
+
+mod common;
+
+use common::{sqlite_database, test_config};
+use gsn_push_processing::grpc::{
+    self,
+    proto::{
+        CreateSavingRequest, DeleteSavingRequest, ListSavingsRequest, UpdateSavingRequest,
+        savings_client::SavingsClient,
+    },
+};
+use gsn_push_processing::{AppState, Config, SqliteSavingsRepository};
+use tokio_util::sync::CancellationToken;
+use tonic::transport::Channel;
+use tonic::{Code, Request};
+
+const API_KEY: &str = "grpc-test-key";
+
+/// Serve `config` from an in-memory SQLite database until the returned token
+/// is cancelled
+async fn start(config: Config) -> (SavingsClient<Channel>, CancellationToken) {
+    let storage = SqliteSavingsRepository::new(sqlite_database().await);
+    let state = AppState::new(config, storage).unwrap();
+    let listener = grpc::bind("127.0.0.1", 0).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let shutdown = CancellationToken::new();
+    tokio::spawn(grpc::serve(state, listener, shutdown.clone()));
+
+    let client = SavingsClient::connect(format!("http://{}", addr))
+        .await
+        .unwrap();
+    (client, shutdown)
+}
+
+fn with_key<T>(message: T, key: &str) -> Request<T> {
+    let mut request = Request::new(message);
+    request
+        .metadata_mut()
+        .insert("x-api-key", key.parse().unwrap());
+    request
+}
+
+fn create(amount: &str) -> CreateSavingRequest {
+    CreateSavingRequest {
+        amount: amount.to_string(),
+        source: "bank".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn binds_host_names() {
+    let listener = grpc::bind("localhost", 0).await.unwrap();
+    assert!(listener.local_addr().unwrap().ip().is_loopback());
+}
+
+#[tokio::test]
+async fn reports_ports_in_use() {
+    let taken = grpc::bind("127.0.0.1", 0).await.unwrap();
+    let port = taken.local_addr().unwrap().port();
+
+    let err = grpc::bind("127.0.0.1", port).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+}
+
+#[tokio::test]
+async fn reports_unresolvable_hosts() {
+    assert!(grpc::bind("host.invalid", 0).await.is_err());
+}
+
+#[tokio::test]
+async fn requires_an_api_key_for_writes() {
+    let (mut client, shutdown) = start(Config {
+        api_keys: vec![API_KEY.to_string()],
+        ..test_config()
+    })
+    .await;
+
+    let status = client.create_saving(create("1")).await.unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+    assert_eq!(status.message(), "Missing API key");
+
+    let status = client
+        .create_saving(with_key(create("1"), "wrong"))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+    assert_eq!(status.message(), "Invalid API key");
+
+    let created = client
+        .create_saving(with_key(create("1"), API_KEY))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let status = client
+        .update_saving(UpdateSavingRequest {
+            id: created.id,
+            amount: Some("2".to_string()),
+            source: None,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+    let status = client
+        .delete_saving(DeleteSavingRequest { id: created.id })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    // Reads stay open, like the HTTP routes
+    let listed = client
+        .list_savings(ListSavingsRequest::default())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(listed.total_count, 1);
+
+    client
+        .delete_saving(with_key(DeleteSavingRequest { id: created.id }, API_KEY))
+        .await
+        .unwrap();
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn rate_limits_writes() {
+    let (mut client, shutdown) = start(Config {
+        rate_limit_enabled: true,
+        rate_limit_routes: vec!["POST /savings.v1.Savings/CreateSaving=2/60".to_string()],
+        ..test_config()
+    })
+    .await;
+
+    for _ in 0..2 {
+        client.create_saving(create("1")).await.unwrap();
+    }
+    let status = client.create_saving(create("1")).await.unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert_eq!(status.metadata().get("error-code").unwrap(), "RATE_LIMITED");
+    assert_eq!(status.metadata().get("retry-after").unwrap(), "30");
+
+    // Reads are not limited, other writes have buckets of their own
+    for _ in 0..3 {
+        client
+            .list_savings(ListSavingsRequest::default())
+            .await
+            .unwrap();
+    }
+    client
+        .delete_saving(DeleteSavingRequest { id: 1 })
+        .await
+        .unwrap();
+    shutdown.cancel();
+}
@@ -0,0 +1,63 @@
+//! Migrations on Postgres while another instance holds the migration lock,
+//! on a database of its own, see `common`.
+
+mod common;
+
+use common::TestDatabase;
+use gsn_push_processing::adapters::db::{self, DatabaseConfig, MIGRATION_LOCK_KEY};
+use sqlx::{Connection, PgConnection};
+use std::time::Duration;
+
+#[actix_web::test]
+async fn waits_for_the_migration_lock_longer_than_the_timeouts() {
+    let database = TestDatabase::create().await;
+    let config = DatabaseConfig {
+        url: database.url(),
+        max_connections: 1,
+        min_connections: 0,
+        statement_timeout_ms: 100,
+        lock_timeout_ms: 100,
+        ..Default::default()
+    };
+    let pool = db::init_pool(&config)
+        .await
+        .expect("connect to test database");
+
+    let mut holder = PgConnection::connect(&database.url()).await.unwrap();
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut holder)
+        .await
+        .unwrap();
+
+    let migrating = actix_web::rt::spawn({
+        let pool = pool.clone();
+        async move { db::run_migrations(&pool).await }
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(
+        !migrating.is_finished(),
+        "migrations did not wait for the lock"
+    );
+
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut holder)
+        .await
+        .unwrap();
+    holder.close().await.unwrap();
+    migrating
+        .await
+        .unwrap()
+        .expect("migrations once the lock is released");
+
+    // The connection went back to the pool with the timeouts of the pool
+    let lock_timeout: String = sqlx::query_scalar("SHOW lock_timeout")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(lock_timeout, "100ms");
+
+    pool.close().await;
+    database.teardown().await;
+}
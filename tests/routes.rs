@@ -0,0 +1,951 @@
+//! Every route of the app on Postgres, through `app_factory` and
+//! `actix_web::test`, each test on a database of its own, see `common`.
+
+mod common;
+
+use actix_web::{
+    body::MessageBody,
+    http::{StatusCode, header},
+    test::{self, TestRequest},
+};
+use common::{send, test_config, with_database};
+use gsn_push_processing::adapters::change_feed::ChangeFeed;
+use gsn_push_processing::services::AlertsService;
+use gsn_push_processing::{AppState, Config, app_factory};
+use rust_decimal::Decimal;
+use serde_json::{Value, json};
+use sqlx::postgres::PgPoolOptions;
+use std::future::poll_fn;
+use std::pin::pin;
+use std::time::Duration;
+
+const API_KEY: &str = "test-key";
+
+fn config_with_api_key() -> Config {
+    Config {
+        api_keys: vec![API_KEY.to_string()],
+        ..test_config()
+    }
+}
+
+/// Read a streaming body until it contains every needle, failing after a
+/// second
+async fn read_until(body: impl MessageBody, needles: &[&str]) -> String {
+    let mut body = pin!(body);
+    let mut received = String::new();
+
+    tokio::time::timeout(Duration::from_secs(1), async {
+        while !needles.iter().all(|needle| received.contains(needle)) {
+            match poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+                Some(Ok(chunk)) => received.push_str(&String::from_utf8_lossy(&chunk)),
+                _ => break,
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("no {:?} in the stream, got {:?}", needles, received));
+    received
+}
+
+#[actix_web::test]
+async fn serves_monitoring_routes() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let response =
+            test::call_service(&app, TestRequest::get().uri("/healthz").to_request()).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response =
+            test::call_service(&app, TestRequest::get().uri("/checkz").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let (status, readiness) = send(&app, TestRequest::get().uri("/readyz").to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(readiness["status"], "up");
+
+        let (status, _) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v2/new-saving")
+                .set_json(json!({ "amount": "5", "currency": "CHF", "source": "client-chosen-42" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let response =
+            test::call_service(&app, TestRequest::get().uri("/metrics").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = test::read_body(response).await;
+        let metrics = String::from_utf8_lossy(&body);
+        assert!(metrics.contains("http_requests_total"));
+        assert!(metrics.contains(
+            r#"savings_amount_total{currency="CHF",kind="deposit",source="client-chosen-42"} 5"#
+        ));
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn serves_documentation() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let (status, openapi) =
+            send(&app, TestRequest::get().uri("/openapi.json").to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        let paths = openapi["paths"].as_object().expect("paths");
+        assert!(paths.contains_key("/api/v2/new-saving"));
+        assert!(paths.contains_key("/api/v1/alerts/thresholds"));
+
+        let response = test::call_service(&app, TestRequest::get().uri("/docs").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn answers_unknown_routes_with_problem_details() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let response =
+            test::call_service(&app, TestRequest::get().uri("/api/v2/nowhere").to_request()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/problem+json"
+        );
+        let error: Value = test::read_body_json(response).await;
+        assert_eq!(error["code"], "NOT_FOUND");
+        assert_eq!(error["status"], 404);
+        assert_eq!(error["type"], "urn:gsn-push-processing:problem:not-found");
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn creates_savings_from_every_body_format() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let (status, created) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/new-saving")
+                .set_json(json!({ "amount": 10, "source": "json" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["source"], "json");
+
+        let (status, created) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/new-saving")
+                .set_form([("amount", "10"), ("source", "form")])
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["source"], "form");
+
+        let body = rmp_serde::to_vec_named(&json!({
+            "amount": "10",
+            "currency": "EUR",
+            "source": "msgpack"
+        }))
+        .unwrap();
+        let response = test::call_service(
+            &app,
+            TestRequest::post()
+                .uri("/api/v2/new-saving")
+                .insert_header((header::CONTENT_TYPE, "application/msgpack"))
+                .insert_header((header::ACCEPT, "application/msgpack"))
+                .set_payload(body)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created: Value = rmp_serde::from_slice(&test::read_body(response).await).unwrap();
+        assert_eq!(created["source"], "msgpack");
+        assert_eq!(created["currency"], "EUR");
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn validates_savings() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let cases = [
+            (json!({ "amount": 0, "source": "bank" }), "amount"),
+            (json!({ "amount": -5, "source": "bank" }), "amount"),
+            (json!({ "amount": 5, "source": "" }), "source"),
+            (json!({ "amount": 5, "source": "x".repeat(256) }), "source"),
+        ];
+        for (body, field) in cases {
+            let (status, error) = send(
+                &app,
+                TestRequest::post()
+                    .uri("/api/v1/new-saving")
+                    .set_json(&body)
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+            assert_eq!(error["code"], "VALIDATION_FAILED");
+            assert_eq!(error["errors"][0]["field"], field);
+        }
+
+        let cases = [
+            json!({ "amount": 5, "currency": "usd", "source": "bank" }),
+            json!({ "amount": 5, "currency": "USD", "tags": [""], "source": "bank" }),
+            json!({ "amount": 5, "currency": "USD", "tags": vec!["t"; 21], "source": "bank" }),
+        ];
+        for body in cases {
+            let (status, error) = send(
+                &app,
+                TestRequest::post()
+                    .uri("/api/v2/new-saving")
+                    .set_json(&body)
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+            assert_eq!(error["code"], "VALIDATION_FAILED");
+        }
+
+        // Unknown fields and types the model can't hold never reach validation
+        let cases = [
+            json!({ "amount": 5, "source": "bank", "unknown": true }),
+            json!({ "amount": "lots", "source": "bank" }),
+            json!({ "source": "bank" }),
+            json!({ "amount": 5, "currency": "USD", "kind": "gift", "source": "bank" }),
+        ];
+        for body in cases {
+            let (status, error) = send(
+                &app,
+                TestRequest::post()
+                    .uri("/api/v2/new-saving")
+                    .set_json(&body)
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+            assert_eq!(error["code"], "INVALID_JSON");
+        }
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn rejects_unsupported_and_oversized_bodies() {
+    with_database(|pool| async move {
+        let config = Config {
+            payload_limit_bytes: 64,
+            ..test_config()
+        };
+        let app = test::init_service(app_factory(config, pool).unwrap()).await;
+
+        let (status, error) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/new-saving")
+                .insert_header((header::CONTENT_TYPE, "text/csv"))
+                .set_payload("amount,source\n1,bank")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(error["code"], "UNSUPPORTED_MEDIA_TYPE");
+
+        let (status, error) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/new-saving")
+                .set_json(json!({ "amount": 1, "source": "x".repeat(100) }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(error["code"], "PAYLOAD_TOO_LARGE");
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn gets_savings_by_id() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let (_, created) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v2/new-saving")
+                .set_json(json!({ "amount": "3.5", "currency": "USD", "source": "bank" }))
+                .to_request(),
+        )
+        .await;
+
+        for version in ["v1", "v2"] {
+            let uri = format!("/api/{}/savings/{}", version, created["id"]);
+            let (status, found) = send(&app, TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(found["id"], created["id"]);
+            assert_eq!(found["amount"], "3.5000");
+
+            let uri = format!("/api/{}/savings/999999", version);
+            let (status, error) = send(&app, TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            assert_eq!(error["code"], "SAVING_NOT_FOUND");
+            assert_eq!(error["detail"], "Saving with ID 999999 not found");
+
+            let uri = format!("/api/{}/savings/-1", version);
+            let (status, error) = send(&app, TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(error["code"], "BAD_REQUEST");
+
+            let uri = format!("/api/{}/savings/one", version);
+            let (status, error) = send(&app, TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(error["code"], "INVALID_PATH_PARAMETER");
+        }
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn imports_savings_with_an_api_key() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(config_with_api_key(), pool).unwrap()).await;
+        let body = json!({ "records": [{ "amount": 1, "source": "bank" }] });
+
+        let (status, error) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/savings/import")
+                .set_json(&body)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error["code"], "UNAUTHORIZED");
+
+        let (status, error) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/savings/import")
+                .insert_header(("X-API-Key", "wrong"))
+                .set_json(&body)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error["detail"], "Invalid API key");
+
+        let (status, summary) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/savings/import")
+                .insert_header(("X-API-Key", API_KEY))
+                .set_json(&body)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(summary["imported"], 1);
+
+        let (status, error) = send(
+            &app,
+            TestRequest::post()
+                .uri(&format!("/api/v2/savings/import?api_key={}", API_KEY))
+                .set_json(json!({ "records": [] }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], "VALIDATION_FAILED");
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn reports_unreachable_databases_without_their_errors() {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://secret-user@127.0.0.1:1/savings")
+        .unwrap();
+    let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+    let (status, readiness) = send(&app, TestRequest::get().uri("/readyz").to_request()).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        readiness["components"]["database"]["error"],
+        "Database unreachable"
+    );
+    assert_eq!(
+        readiness["components"]["migrations"]["error"],
+        "Failed to read migrations"
+    );
+}
+
+#[actix_web::test]
+async fn streams_saving_changes() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let (_, created) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/new-saving")
+                .set_json(json!({ "amount": 2, "source": "bank" }))
+                .to_request(),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            TestRequest::get()
+                .uri("/api/v2/savings/stream")
+                .insert_header(("Last-Event-ID", "0"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        let received = read_until(response.into_body(), &["event: created"]).await;
+        assert!(received.starts_with("retry: 5000\n\n"));
+        assert!(received.contains(&format!("\"id\":{}", created["id"])));
+
+        let (status, error) = send(
+            &app,
+            TestRequest::get()
+                .uri("/api/v2/savings/stream")
+                .insert_header(("Last-Event-ID", "latest"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], "BAD_REQUEST");
+
+        let (status, error) = send(
+            &app,
+            TestRequest::get()
+                .uri("/api/v2/savings/stream?last_event_id=x")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], "INVALID_QUERY_PARAMETER");
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn streams_changes_committed_out_of_order() {
+    with_database(|pool| async move {
+        let state = AppState::new(test_config(), pool.clone()).unwrap();
+        let feed = ChangeFeed::start(&pool, Duration::from_secs(30), state.shutdown())
+            .await
+            .unwrap();
+        let shutdown = state.shutdown().clone();
+        let app = test::init_service(state.with_change_feed(feed).app()).await;
+
+        let response = test::call_service(
+            &app,
+            TestRequest::get()
+                .uri("/api/v2/savings/stream")
+                .insert_header(("Last-Event-ID", "0"))
+                .to_request(),
+        )
+        .await;
+
+        // The first insert gets the first event id but commits last
+        let insert = "INSERT INTO transactions (amount, source) VALUES (1, 'bank')";
+        let mut first = pool.begin().await.unwrap();
+        sqlx::query(insert).execute(&mut *first).await.unwrap();
+        let mut second = pool.begin().await.unwrap();
+        sqlx::query(insert).execute(&mut *second).await.unwrap();
+        second.commit().await.unwrap();
+        first.commit().await.unwrap();
+
+        let received = read_until(response.into_body(), &["id: 1\n", "id: 2\n"]).await;
+        assert_eq!(
+            received.matches("event: created").count(),
+            2,
+            "{}",
+            received
+        );
+
+        // Stops the listener, handing its connection back
+        shutdown.token().cancel();
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn manages_alert_thresholds() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let (status, threshold) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/alerts/thresholds")
+                .set_json(json!({
+                    "source": "bank",
+                    "period": "month",
+                    "comparison": "below",
+                    "threshold": 200
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(threshold["state"], "ok");
+        assert_eq!(threshold["source"], "bank");
+
+        let (status, thresholds) = send(
+            &app,
+            TestRequest::get()
+                .uri("/api/v2/alerts/thresholds")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(thresholds.as_array().unwrap().len(), 1);
+
+        let uri = format!("/api/v1/alerts/thresholds/{}", threshold["id"]);
+        let response = test::call_service(&app, TestRequest::delete().uri(&uri).to_request()).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let (status, error) = send(&app, TestRequest::delete().uri(&uri).to_request()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error["code"], "THRESHOLD_NOT_FOUND");
+
+        let (status, error) = send(
+            &app,
+            TestRequest::delete()
+                .uri("/api/v1/alerts/thresholds/0")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], "BAD_REQUEST");
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn evaluates_thresholds_net_of_withdrawals_in_their_currency() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool.clone()).unwrap()).await;
+
+        let (status, _) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/alerts/thresholds")
+                .set_json(json!({
+                    "source": "bank",
+                    "currency": "USD",
+                    "period": "month",
+                    "comparison": "below",
+                    "threshold": 10
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        sqlx::query(
+            r#"
+            INSERT INTO transactions (amount, currency, kind, source) VALUES
+                (10, 'USD', 'deposit', 'bank'),
+                (3, 'USD', 'withdrawal', 'bank'),
+                (5, 'USD', 'transfer', 'bank'),
+                (100, 'EUR', 'deposit', 'bank')
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // 10 - 3 USD, below the threshold; a plain sum would be 118
+        let created = AlertsService::evaluate_thresholds(&pool).await.unwrap();
+        assert_eq!(created, 1);
+
+        let (_, thresholds) = send(
+            &app,
+            TestRequest::get()
+                .uri("/api/v1/alerts/thresholds")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(thresholds[0]["state"], "triggered");
+        let last_value: Decimal = thresholds[0]["last_value"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(last_value, Decimal::from(7));
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn records_each_transition_once_across_replicas() {
+    with_database(|pool| async move {
+        sqlx::query(
+            r#"
+            INSERT INTO alert_thresholds (source, currency, period, comparison, threshold)
+            VALUES ('bank', 'USD', 'month', 'below', 10)
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Every replica runs the evaluator, possibly in the same tick
+        let (a, b, c) = tokio::join!(
+            AlertsService::evaluate_thresholds(&pool),
+            AlertsService::evaluate_thresholds(&pool),
+            AlertsService::evaluate_thresholds(&pool),
+        );
+        assert_eq!(a.unwrap() + b.unwrap() + c.unwrap(), 1);
+
+        let alerts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM alerts")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(alerts, 1);
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn validates_alert_thresholds() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let (status, error) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/alerts/thresholds")
+                .set_json(json!({
+                    "source": "",
+                    "period": "week",
+                    "comparison": "above",
+                    "threshold": -1,
+                    "hysteresis": -1
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], "VALIDATION_FAILED");
+        assert_eq!(error["errors"].as_array().unwrap().len(), 3);
+
+        let (status, error) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/v1/alerts/thresholds")
+                .set_json(json!({
+                    "source": "bank",
+                    "period": "year",
+                    "comparison": "above",
+                    "threshold": 1
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], "INVALID_JSON");
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn lists_alerts() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let (status, alerts) = send(
+            &app,
+            TestRequest::get()
+                .uri("/api/v1/alerts?since_id=0&limit=10")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(alerts, json!([]));
+
+        for uri in ["/api/v1/alerts?limit=many", "/api/v1/alerts?unknown=1"] {
+            let (status, error) = send(&app, TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(error["code"], "INVALID_QUERY_PARAMETER");
+        }
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn executes_graphql_operations() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let (status, response) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/graphql")
+                .set_json(json!({
+                    "query": r#"mutation {
+                        createSaving(input: { amount: "4.20", source: "bank" }) { id amount currency }
+                    }"#
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let created = &response["data"]["createSaving"];
+        assert_eq!(created["amount"], "4.2000");
+        assert_eq!(created["currency"], "USD");
+
+        let (_, response) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/graphql")
+                .set_json(json!({
+                    "query": "query($id: Int!) { saving(id: $id) { source } }",
+                    "variables": { "id": created["id"] }
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response["data"]["saving"]["source"], "bank");
+
+        let (_, response) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/graphql")
+                .set_json(json!({
+                    "query": r#"mutation { createSaving(input: { amount: "0", source: "bank" }) { id } }"#
+                }))
+                .to_request(),
+        )
+        .await;
+        let error = &response["errors"][0]["extensions"];
+        assert_eq!(error["code"], "VALIDATION_FAILED");
+        assert_eq!(error["status"], 400);
+
+        // A page costs its selection once per requested saving
+        let page = "query($limit: Int!) { savings(limit: $limit) { items { id amount currency } } }";
+        let (_, response) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/graphql")
+                .set_json(json!({ "query": page, "variables": { "limit": 20 } }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response["errors"], Value::Null);
+        assert_eq!(response["data"]["savings"]["items"][0]["amount"], "4.2000");
+
+        let (_, response) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/graphql")
+                .set_json(json!({ "query": page, "variables": { "limit": 100 } }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response["data"], Value::Null);
+        assert_eq!(response["errors"][0]["message"], "Query is too complex.");
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn requires_an_api_key_for_graphql_mutations() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(config_with_api_key(), pool).unwrap()).await;
+        let create = json!({
+            "query": r#"mutation { createSaving(input: { amount: "1", source: "bank" }) { id } }"#
+        });
+
+        for key in [None, Some("wrong")] {
+            let mut request = TestRequest::post().uri("/api/graphql").set_json(&create);
+            if let Some(key) = key {
+                request = request.insert_header(("X-API-Key", key));
+            }
+            let (_, response) = send(&app, request.to_request()).await;
+            assert_eq!(response["data"], Value::Null, "{:?}", key);
+            let error = &response["errors"][0]["extensions"];
+            assert_eq!(error["code"], "UNAUTHORIZED");
+            assert_eq!(error["status"], 401);
+        }
+
+        for mutation in [
+            r#"mutation { updateSaving(id: 1, input: { source: "cash" }) { id } }"#,
+            "mutation { deleteSaving(id: 1) }",
+        ] {
+            let (_, response) = send(
+                &app,
+                TestRequest::post()
+                    .uri("/api/graphql")
+                    .set_json(json!({ "query": mutation }))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(
+                response["errors"][0]["extensions"]["code"], "UNAUTHORIZED",
+                "{}",
+                mutation
+            );
+        }
+
+        let (_, response) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/graphql")
+                .insert_header(("X-API-Key", API_KEY))
+                .set_json(&create)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response["errors"], Value::Null);
+        assert!(response["data"]["createSaving"]["id"].is_i64());
+
+        // Reads stay open, like the HTTP routes
+        let (_, response) = send(
+            &app,
+            TestRequest::post()
+                .uri("/api/graphql")
+                .set_json(json!({ "query": "{ savings { totalCount } }" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response["data"]["savings"]["totalCount"], 1);
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn reports_migrations_to_admins() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(config_with_api_key(), pool).unwrap()).await;
+
+        let (status, error) = send(
+            &app,
+            TestRequest::get().uri("/admin/migrations").to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error["detail"], "Missing API key");
+
+        let (status, migrations) = send(
+            &app,
+            TestRequest::get()
+                .uri("/admin/migrations")
+                .insert_header(("X-API-Key", API_KEY))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(migrations["pending"], json!([]));
+        assert!(!migrations["applied"].as_array().unwrap().is_empty());
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn requires_a_websocket_handshake() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(config_with_api_key(), pool).unwrap()).await;
+
+        let (status, _) = send(&app, TestRequest::get().uri("/api/ws").to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        for path in ["/api/ws", "/api/v1/ws", "/api/v2/ws"] {
+            let (status, error) = send(
+                &app,
+                TestRequest::get()
+                    .uri(&format!("{}?api_key={}", path, API_KEY))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", path);
+            assert_eq!(error["code"], "BAD_REQUEST");
+        }
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn limits_request_rates() {
+    with_database(|pool| async move {
+        let config = Config {
+            rate_limit_enabled: true,
+            rate_limit_routes: vec!["POST /api/v1/new-saving=1/60".to_string()],
+            ..test_config()
+        };
+        let app = test::init_service(app_factory(config, pool).unwrap()).await;
+        let request = || {
+            TestRequest::post()
+                .uri("/api/v1/new-saving")
+                .peer_addr("10.0.0.1:4000".parse().unwrap())
+                .set_json(json!({ "amount": 1, "source": "bank" }))
+                .to_request()
+        };
+
+        let response = test::call_service(&app, request()).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers().get("x-ratelimit-limit").unwrap(), "1");
+
+        let response = test::call_service(&app, request()).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+        let error: Value = test::read_body_json(response).await;
+        assert_eq!(error["code"], "RATE_LIMITED");
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn negotiates_api_versions() {
+    with_database(|pool| async move {
+        let app = test::init_service(app_factory(test_config(), pool).unwrap()).await;
+
+        let response = test::call_service(
+            &app,
+            TestRequest::post()
+                .uri("/api/new-saving")
+                .insert_header((header::ACCEPT, "application/json; version=2"))
+                .set_json(json!({ "amount": 1, "currency": "CHF", "source": "bank" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert!(!response.headers().contains_key("deprecation"));
+        let created: Value = test::read_body_json(response).await;
+        assert_eq!(created["currency"], "CHF");
+
+        let (status, error) = send(
+            &app,
+            TestRequest::get()
+                .uri("/api/savings/1")
+                .insert_header((header::ACCEPT, "application/json; version=3"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(error["code"], "UNSUPPORTED_API_VERSION");
+    })
+    .await;
+}
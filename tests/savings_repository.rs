@@ -0,0 +1,744 @@
+//! Conformance suite of `SavingsRepository`: every case runs through
+//! `SavingsService` against each backend, which must behave the same.
+//!
+//! The SQLite backend runs on an in-memory database, the Postgres one
+//! creates a database per test on the server of `TEST_DATABASE_URL`, see
+//! `common`.
+
+mod common;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use gsn_push_processing::adapters::cache::{Cache, LruCache};
+use gsn_push_processing::models::events::ChangeOperation;
+use gsn_push_processing::{
+    AppError, CreateTransaction, DbPools, ImportedTransaction, SavingsRepository, SavingsService,
+    SqliteSavingsRepository, TransactionFilter, TransactionKind, UpdateTransaction,
+};
+use rust_decimal::Decimal;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::Arc;
+
+macro_rules! conformance {
+    ($($case:ident),* $(,)?) => {
+        mod memory {
+            use gsn_push_processing::InMemorySavingsRepository;
+
+            $(
+                #[tokio::test]
+                async fn $case() {
+                    super::$case(InMemorySavingsRepository::new()).await;
+                }
+            )*
+        }
+
+        mod sqlite {
+            use gsn_push_processing::SqliteSavingsRepository;
+
+            $(
+                #[tokio::test]
+                async fn $case() {
+                    let pool = crate::common::sqlite_database().await;
+                    super::$case(SqliteSavingsRepository::new(pool)).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            use gsn_push_processing::DbPools;
+
+            $(
+                #[tokio::test]
+                async fn $case() {
+                    crate::common::with_database(|pool| super::$case(DbPools::new(pool))).await;
+                }
+            )*
+        }
+    };
+}
+
+conformance!(
+    creates_and_finds_savings,
+    lists_newest_first_with_ties_by_newest_id,
+    paginates_savings,
+    filters_savings,
+    filters_amounts_exactly_at_their_bounds,
+    aggregates_by_source,
+    aggregates_net_of_withdrawals_per_currency,
+    updates_savings,
+    deletes_savings,
+    rejects_invalid_savings,
+    rejects_amounts_out_of_storage_range,
+    imports_all_or_nothing,
+    records_change_events,
+    records_one_event_per_update,
+);
+
+fn dec(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap()
+}
+
+fn at(day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 1, day, 12, 0, 0).unwrap()
+}
+
+fn deposit(amount: &str, source: &str) -> CreateTransaction {
+    CreateTransaction::deposit(dec(amount), source.to_string())
+}
+
+fn imported(amount: &str, source: &str, created_at: Option<DateTime<Utc>>) -> ImportedTransaction {
+    ImportedTransaction {
+        amount: dec(amount),
+        currency: "USD".to_string(),
+        kind: TransactionKind::Deposit,
+        tags: Vec::new(),
+        source: source.to_string(),
+        created_at,
+    }
+}
+
+fn update(amount: Option<&str>, source: Option<&str>) -> UpdateTransaction {
+    UpdateTransaction {
+        amount: amount.map(dec),
+        source: source.map(str::to_string),
+    }
+}
+
+/// Ids of the savings matching `filter`, in listing order
+async fn search_ids(repo: &impl SavingsRepository, filter: &TransactionFilter) -> Vec<i64> {
+    SavingsService::search_savings(repo, filter, 100, 0)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|transaction| transaction.id)
+        .collect()
+}
+
+async fn creates_and_finds_savings(repo: impl SavingsRepository) {
+    let payload = CreateTransaction {
+        amount: dec("12.5"),
+        currency: "EUR".to_string(),
+        kind: TransactionKind::Interest,
+        tags: vec!["bonus".to_string()],
+        source: "bank".to_string(),
+    };
+    let created = SavingsService::create_new_saving(&repo, &payload)
+        .await
+        .unwrap();
+
+    assert_eq!(created.amount.to_string(), "12.5000");
+    assert_eq!(created.currency, "EUR");
+    assert_eq!(created.kind, TransactionKind::Interest);
+    assert_eq!(created.tags, vec!["bonus".to_string()]);
+    assert_eq!(created.source, "bank");
+    assert_eq!(created.created_at, created.updated_at);
+
+    let found = SavingsService::get_by_id(&repo, created.id)
+        .await
+        .unwrap()
+        .expect("created saving is found");
+    assert_eq!(found.id, created.id);
+    assert_eq!(found.amount.to_string(), "12.5000");
+    assert_eq!(found.created_at, created.created_at);
+
+    let other = SavingsService::create_new_saving(&repo, &deposit("1", "bank"))
+        .await
+        .unwrap();
+    assert!(other.id > created.id);
+
+    let missing = SavingsService::get_by_id(&repo, other.id + 1)
+        .await
+        .unwrap();
+    assert!(missing.is_none());
+}
+
+async fn lists_newest_first_with_ties_by_newest_id(repo: impl SavingsRepository) {
+    let records = [
+        imported("1", "bank", Some(at(1))),
+        imported("2", "bank", Some(at(3))),
+        imported("3", "bank", Some(at(2))),
+        imported("4", "bank", Some(at(3))),
+    ];
+    SavingsService::import_savings(&repo, &records)
+        .await
+        .unwrap();
+
+    let amounts: Vec<String> = SavingsService::list_savings(&repo, 10, 0)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|transaction| transaction.amount.normalize().to_string())
+        .collect();
+    assert_eq!(amounts, ["4", "2", "3", "1"]);
+}
+
+async fn paginates_savings(repo: impl SavingsRepository) {
+    let records: Vec<_> = (1..=5)
+        .map(|day| imported(&day.to_string(), "bank", Some(at(day))))
+        .collect();
+    SavingsService::import_savings(&repo, &records)
+        .await
+        .unwrap();
+
+    let filter = TransactionFilter::default();
+    let all = search_ids(&repo, &filter).await;
+    assert_eq!(all.len(), 5);
+
+    let page: Vec<i64> = SavingsService::search_savings(&repo, &filter, 2, 1)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|transaction| transaction.id)
+        .collect();
+    assert_eq!(page, all[1..3]);
+
+    let past_the_end = SavingsService::search_savings(&repo, &filter, 2, 5)
+        .await
+        .unwrap();
+    assert!(past_the_end.is_empty());
+}
+
+async fn filters_savings(repo: impl SavingsRepository) {
+    let records = [
+        imported("10", "bank", Some(at(1))),
+        imported("20", "bank", Some(at(2))),
+        imported("30", "cash", Some(at(3))),
+        imported("40", "cash", Some(at(4))),
+    ];
+    SavingsService::import_savings(&repo, &records)
+        .await
+        .unwrap();
+    let all = search_ids(&repo, &TransactionFilter::default()).await;
+    let [day4, day3, day2, day1] = all[..] else {
+        panic!("expected 4 savings, got {:?}", all);
+    };
+
+    let cases = [
+        (
+            TransactionFilter {
+                source: Some("bank".to_string()),
+                ..Default::default()
+            },
+            vec![day2, day1],
+        ),
+        (
+            TransactionFilter {
+                source: Some("unknown".to_string()),
+                ..Default::default()
+            },
+            vec![],
+        ),
+        // Amount bounds are inclusive
+        (
+            TransactionFilter {
+                min_amount: Some(dec("20")),
+                max_amount: Some(dec("30")),
+                ..Default::default()
+            },
+            vec![day3, day2],
+        ),
+        // Creation bounds include the start and exclude the end
+        (
+            TransactionFilter {
+                created_after: Some(at(2)),
+                created_before: Some(at(4)),
+                ..Default::default()
+            },
+            vec![day3, day2],
+        ),
+        (
+            TransactionFilter {
+                source: Some("cash".to_string()),
+                min_amount: Some(dec("35")),
+                created_after: Some(at(1)),
+                ..Default::default()
+            },
+            vec![day4],
+        ),
+    ];
+
+    for (filter, expected) in cases {
+        assert_eq!(search_ids(&repo, &filter).await, expected, "{:?}", filter);
+        let count = SavingsService::count_savings(&repo, &filter).await.unwrap();
+        assert_eq!(count, expected.len() as i64, "{:?}", filter);
+    }
+}
+
+async fn filters_amounts_exactly_at_their_bounds(repo: impl SavingsRepository) {
+    // Neighbours a floating point number can't tell apart
+    let amounts = [
+        "123456789012345.1233",
+        "123456789012345.1234",
+        "123456789012345.1235",
+        "0.0001",
+        "0.0002",
+    ];
+    let records: Vec<ImportedTransaction> = amounts
+        .iter()
+        .enumerate()
+        .map(|(day, amount)| imported(amount, "bank", Some(at(day as u32 + 1))))
+        .collect();
+    SavingsService::import_savings(&repo, &records)
+        .await
+        .unwrap();
+
+    let cases = [
+        (
+            Some("123456789012345.1234"),
+            Some("123456789012345.1234"),
+            vec![2],
+        ),
+        (Some("123456789012345.1234"), None, vec![3, 2]),
+        (None, Some("123456789012345.1234"), vec![5, 4, 2, 1]),
+        (Some("0.0002"), Some("0.0002"), vec![5]),
+        (None, Some("0.0001"), vec![4]),
+    ];
+    for (min_amount, max_amount, expected) in cases {
+        let filter = TransactionFilter {
+            min_amount: min_amount.map(dec),
+            max_amount: max_amount.map(dec),
+            ..Default::default()
+        };
+        assert_eq!(search_ids(&repo, &filter).await, expected, "{:?}", filter);
+        let count = SavingsService::count_savings(&repo, &filter).await.unwrap();
+        assert_eq!(count, expected.len() as i64, "{:?}", filter);
+    }
+}
+
+async fn aggregates_by_source(repo: impl SavingsRepository) {
+    let records = [
+        imported("1.25", "cash", Some(at(1))),
+        imported("10", "bank", Some(at(2))),
+        imported("2.5", "cash", Some(at(3))),
+        imported("100", "bank", Some(at(4))),
+    ];
+    SavingsService::import_savings(&repo, &records)
+        .await
+        .unwrap();
+
+    let aggregates = SavingsService::source_aggregates(&repo, &TransactionFilter::default())
+        .await
+        .unwrap();
+    let aggregates: Vec<(String, String, i64)> = aggregates
+        .into_iter()
+        .map(|aggregate| {
+            (
+                aggregate.source,
+                aggregate.total.to_string(),
+                aggregate.count,
+            )
+        })
+        .collect();
+    assert_eq!(
+        aggregates,
+        [
+            ("bank".to_string(), "110.0000".to_string(), 2),
+            ("cash".to_string(), "3.7500".to_string(), 2),
+        ]
+    );
+
+    let filter = TransactionFilter {
+        max_amount: Some(dec("10")),
+        ..Default::default()
+    };
+    let filtered = SavingsService::source_aggregates(&repo, &filter)
+        .await
+        .unwrap();
+    assert_eq!(filtered.len(), 2);
+    assert_eq!(filtered[0].total, dec("10"));
+    assert_eq!(filtered[0].count, 1);
+
+    let filter = TransactionFilter {
+        source: Some("unknown".to_string()),
+        ..Default::default()
+    };
+    let none = SavingsService::source_aggregates(&repo, &filter)
+        .await
+        .unwrap();
+    assert!(none.is_empty());
+}
+
+async fn aggregates_net_of_withdrawals_per_currency(repo: impl SavingsRepository) {
+    let record = |amount: &str, currency: &str, kind: TransactionKind| ImportedTransaction {
+        currency: currency.to_string(),
+        kind,
+        ..imported(amount, "bank", None)
+    };
+    let records = [
+        record("10", "USD", TransactionKind::Deposit),
+        record("3", "USD", TransactionKind::Withdrawal),
+        record("1", "USD", TransactionKind::Interest),
+        record("5", "USD", TransactionKind::Transfer),
+        record("7", "EUR", TransactionKind::Deposit),
+    ];
+    SavingsService::import_savings(&repo, &records)
+        .await
+        .unwrap();
+
+    let aggregates = SavingsService::source_aggregates(&repo, &TransactionFilter::default())
+        .await
+        .unwrap();
+    let aggregates: Vec<(String, String, Decimal, i64)> = aggregates
+        .into_iter()
+        .map(|aggregate| {
+            (
+                aggregate.source,
+                aggregate.currency,
+                aggregate.total,
+                aggregate.count,
+            )
+        })
+        .collect();
+    assert_eq!(
+        aggregates,
+        [
+            ("bank".to_string(), "EUR".to_string(), dec("7"), 1),
+            ("bank".to_string(), "USD".to_string(), dec("8"), 4),
+        ]
+    );
+}
+
+async fn updates_savings(repo: impl SavingsRepository) {
+    let created = SavingsService::create_new_saving(&repo, &deposit("5", "bank"))
+        .await
+        .unwrap();
+
+    let updated = SavingsService::update_saving(&repo, created.id, &update(Some("7.5"), None))
+        .await
+        .unwrap();
+    assert_eq!(updated.amount.to_string(), "7.5000");
+    assert_eq!(updated.source, "bank");
+    assert_eq!(updated.created_at, created.created_at);
+    assert!(updated.updated_at >= created.updated_at);
+
+    let updated = SavingsService::update_saving(&repo, created.id, &update(None, Some("cash")))
+        .await
+        .unwrap();
+    assert_eq!(updated.amount, dec("7.5"));
+    assert_eq!(updated.source, "cash");
+
+    let found = SavingsService::get_by_id(&repo, created.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.source, "cash");
+
+    let empty = SavingsService::update_saving(&repo, created.id, &update(None, None)).await;
+    assert!(matches!(empty, Err(AppError::BadRequest(_))), "{:?}", empty);
+
+    let invalid = SavingsService::update_saving(&repo, created.id, &update(Some("0"), None)).await;
+    assert!(
+        matches!(invalid, Err(AppError::ValidationError(_))),
+        "{:?}",
+        invalid
+    );
+
+    let missing_id = created.id + 1;
+    let missing = SavingsService::update_saving(&repo, missing_id, &update(Some("1"), None)).await;
+    assert!(
+        matches!(missing, Err(AppError::SavingNotFound(id)) if id == missing_id),
+        "{:?}",
+        missing
+    );
+}
+
+async fn deletes_savings(repo: impl SavingsRepository) {
+    let kept = SavingsService::create_new_saving(&repo, &deposit("1", "bank"))
+        .await
+        .unwrap();
+    let deleted = SavingsService::create_new_saving(&repo, &deposit("2", "bank"))
+        .await
+        .unwrap();
+
+    SavingsService::delete_saving(&repo, deleted.id)
+        .await
+        .unwrap();
+    let found = SavingsService::get_by_id(&repo, deleted.id).await.unwrap();
+    assert!(found.is_none());
+    assert_eq!(
+        search_ids(&repo, &TransactionFilter::default()).await,
+        [kept.id]
+    );
+
+    let again = SavingsService::delete_saving(&repo, deleted.id).await;
+    assert!(
+        matches!(again, Err(AppError::SavingNotFound(id)) if id == deleted.id),
+        "{:?}",
+        again
+    );
+}
+
+async fn rejects_invalid_savings(repo: impl SavingsRepository) {
+    let invalid = [
+        deposit("0", "bank"),
+        deposit("-1", "bank"),
+        deposit("1", ""),
+        deposit("1", &"s".repeat(256)),
+        CreateTransaction {
+            currency: "usd".to_string(),
+            ..deposit("1", "bank")
+        },
+        CreateTransaction {
+            tags: vec![String::new()],
+            ..deposit("1", "bank")
+        },
+        CreateTransaction {
+            tags: (0..21).map(|i| i.to_string()).collect(),
+            ..deposit("1", "bank")
+        },
+    ];
+
+    for payload in invalid {
+        let result = SavingsService::create_new_saving(&repo, &payload).await;
+        assert!(
+            matches!(result, Err(AppError::ValidationError(_))),
+            "{:?} gave {:?}",
+            payload,
+            result
+        );
+    }
+
+    let count = SavingsService::count_savings(&repo, &TransactionFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+/// `DECIMAL(19, 4)` bounds, applied by validation whatever the backend
+async fn rejects_amounts_out_of_storage_range(repo: impl SavingsRepository) {
+    for amount in [
+        "1000000000000000",
+        "999999999999999.99995",
+        "-1000000000000000",
+        "100000000000000000000",
+    ] {
+        let payload = deposit(amount, "bank");
+        let result = SavingsService::create_new_saving(&repo, &payload).await;
+        assert!(
+            matches!(result, Err(AppError::ValidationError(_))),
+            "{} gave {:?}",
+            amount,
+            result
+        );
+    }
+
+    let largest =
+        SavingsService::create_new_saving(&repo, &deposit("999999999999999.99994", "bank"))
+            .await
+            .unwrap();
+    assert_eq!(largest.amount, dec("999999999999999.9999"));
+
+    let result =
+        SavingsService::update_saving(&repo, largest.id, &update(Some("1000000000000000"), None))
+            .await;
+    assert!(
+        matches!(result, Err(AppError::ValidationError(_))),
+        "{:?}",
+        result
+    );
+
+    let result =
+        SavingsService::import_savings(&repo, &[imported("1000000000000000", "bank", None)]).await;
+    assert!(
+        matches!(result, Err(AppError::ValidationError(_))),
+        "{:?}",
+        result
+    );
+
+    let count = SavingsService::count_savings(&repo, &TransactionFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+async fn imports_all_or_nothing(repo: impl SavingsRepository) {
+    let records = [
+        imported("1", "bank", Some(at(1))),
+        imported("0", "bank", Some(at(2))),
+    ];
+    let result = SavingsService::import_savings(&repo, &records).await;
+    assert!(
+        matches!(result, Err(AppError::ValidationError(_))),
+        "{:?}",
+        result
+    );
+    let count = SavingsService::count_savings(&repo, &TransactionFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+
+    let before = Utc::now() - Duration::seconds(1);
+    let records = [
+        imported("1", "bank", Some(at(1))),
+        imported("2", "cash", None),
+    ];
+    let imported = SavingsService::import_savings(&repo, &records)
+        .await
+        .unwrap();
+    assert_eq!(imported, 2);
+
+    let savings = SavingsService::list_savings(&repo, 10, 0).await.unwrap();
+    assert_eq!(savings.len(), 2);
+    // Without a creation date a record is created now, the newest one
+    assert_eq!(savings[0].source, "cash");
+    assert!(savings[0].created_at >= before);
+    assert_eq!(savings[1].created_at, at(1));
+    assert_eq!(savings[1].updated_at, at(1));
+
+    let empty = SavingsService::import_savings(&repo, &[]).await.unwrap();
+    assert_eq!(empty, 0);
+}
+
+async fn records_change_events(repo: impl SavingsRepository) {
+    let bank = SavingsService::create_new_saving(&repo, &deposit("1", "bank"))
+        .await
+        .unwrap();
+    let cash = SavingsService::create_new_saving(&repo, &deposit("2", "cash"))
+        .await
+        .unwrap();
+    SavingsService::update_saving(&repo, bank.id, &update(Some("3"), None))
+        .await
+        .unwrap();
+    SavingsService::delete_saving(&repo, cash.id).await.unwrap();
+
+    let events = SavingsService::list_events_since(&repo, 0, None, 100)
+        .await
+        .unwrap();
+    let summary: Vec<(ChangeOperation, i64, String)> = events
+        .iter()
+        .map(|event| (event.operation, event.transaction_id, event.source.clone()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (ChangeOperation::Created, bank.id, "bank".to_string()),
+            (ChangeOperation::Created, cash.id, "cash".to_string()),
+            (ChangeOperation::Updated, bank.id, "bank".to_string()),
+            (ChangeOperation::Deleted, cash.id, "cash".to_string()),
+        ]
+    );
+    assert!(events.windows(2).all(|pair| pair[0].id < pair[1].id));
+    assert_eq!(events[2].payload.amount, dec("3"));
+    // A deletion carries the deleted saving
+    assert_eq!(events[3].payload.id, cash.id);
+    assert_eq!(events[3].payload.amount, dec("2"));
+
+    let after_first = SavingsService::list_events_since(&repo, events[0].id, None, 2)
+        .await
+        .unwrap();
+    let ids: Vec<i64> = after_first.iter().map(|event| event.id).collect();
+    assert_eq!(ids, [events[1].id, events[2].id]);
+
+    let cash_events = SavingsService::list_events_since(&repo, 0, Some("cash"), 100)
+        .await
+        .unwrap();
+    let operations: Vec<ChangeOperation> =
+        cash_events.iter().map(|event| event.operation).collect();
+    assert_eq!(
+        operations,
+        [ChangeOperation::Created, ChangeOperation::Deleted]
+    );
+
+    let none = SavingsService::list_events_since(&repo, events[3].id, None, 100)
+        .await
+        .unwrap();
+    assert!(none.is_empty());
+}
+
+async fn records_one_event_per_update(repo: impl SavingsRepository) {
+    assert_eq!(SavingsService::last_event_id(&repo).await.unwrap(), 0);
+    let created = SavingsService::create_new_saving(&repo, &deposit("5", "bank"))
+        .await
+        .unwrap();
+    let events = SavingsService::list_events_since(&repo, 0, None, 100)
+        .await
+        .unwrap();
+    let created_event = events.last().unwrap().id;
+
+    let updated = SavingsService::update_saving(&repo, created.id, &update(Some("6"), None))
+        .await
+        .unwrap();
+
+    let events = SavingsService::list_events_since(&repo, created_event, None, 100)
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1, "{:?}", events);
+    assert_eq!(events[0].operation, ChangeOperation::Updated);
+    assert_eq!(events[0].payload.amount, dec("6"));
+    assert_eq!(events[0].payload.updated_at, updated.updated_at);
+    assert_eq!(
+        SavingsService::last_event_id(&repo).await.unwrap(),
+        events[0].id
+    );
+}
+
+/// Updates leaving `updated_at` unchanged are followed by another setting
+/// it, which must not be recorded as a change of its own
+#[tokio::test]
+async fn records_one_event_per_sqlite_update_refreshing_updated_at() {
+    let pool = common::sqlite_database().await;
+    let repo = SqliteSavingsRepository::new(pool.clone());
+    let created = SavingsService::create_new_saving(&repo, &deposit("5", "bank"))
+        .await
+        .unwrap();
+    // SQLite timestamps have millisecond precision
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+    sqlx::query("UPDATE transactions SET source = 'cash' WHERE id = ?1")
+        .bind(created.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let found = SavingsService::get_by_id(&repo, created.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(found.updated_at > created.updated_at);
+
+    let events = SavingsService::list_events_since(&repo, 0, None, 100)
+        .await
+        .unwrap();
+    let operations: Vec<ChangeOperation> = events.iter().map(|event| event.operation).collect();
+    assert_eq!(
+        operations,
+        [ChangeOperation::Created, ChangeOperation::Updated]
+    );
+    assert_eq!(events[1].payload.source, "cash");
+    assert_eq!(events[1].payload.updated_at, found.updated_at);
+}
+
+/// The replica is a database of its own that never sees the writes, as a
+/// replica lagging behind forever would
+#[tokio::test]
+async fn loads_cache_misses_from_the_primary() {
+    common::with_database(|primary| async move {
+        common::with_database(|replica| async move {
+            let cache: Arc<dyn Cache> = Arc::new(LruCache::new(
+                NonZeroUsize::new(100).unwrap(),
+                std::time::Duration::from_secs(60),
+            ));
+            let pools = DbPools::new(primary)
+                .with_replica(replica, std::time::Duration::from_secs(5))
+                .with_cache(Some(cache));
+            assert!(matches!(pools.check_replica().await, Some(Ok(_))));
+
+            let created = SavingsService::create_new_saving(&pools, &deposit("5", "bank"))
+                .await
+                .unwrap();
+            let found = SavingsService::get_by_id(&pools, created.id).await.unwrap();
+            assert_eq!(found.map(|saving| saving.id), Some(created.id));
+
+            let count = SavingsService::count_savings(&pools, &TransactionFilter::default())
+                .await
+                .unwrap();
+            assert_eq!(count, 1);
+
+            // Reads that are never cached still go to the replica
+            let listed = SavingsService::list_savings(&pools, 10, 0).await.unwrap();
+            assert!(listed.is_empty());
+        })
+        .await;
+    })
+    .await;
+}
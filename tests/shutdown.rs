@@ -0,0 +1,113 @@
+use actix_web::{App, HttpResponse, HttpServer, get, middleware::from_fn, web::Data};
+use gsn_push_processing::adapters::readiness::Readiness;
+use gsn_push_processing::shutdown::{ShutdownCoordinator, track_in_flight};
+use sqlx::{PgPool, postgres::PgPoolOptions};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const IN_FLIGHT_REQUESTS: usize = 8;
+const HANDLER_DELAY: Duration = Duration::from_millis(500);
+
+#[get("/slow")]
+async fn slow() -> HttpResponse {
+    tokio::time::sleep(HANDLER_DELAY).await;
+    HttpResponse::Ok().body("done")
+}
+
+async fn get_slow(addr: SocketAddr) -> std::io::Result<String> {
+    let mut stream = TcpStream::connect(addr).await?;
+    stream
+        .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    Ok(response)
+}
+
+// The pool is never used to connect, the coordinator only needs to close it
+fn unused_pool() -> PgPool {
+    PgPoolOptions::new()
+        .connect_lazy("postgres://postgres@127.0.0.1:1/unused")
+        .expect("valid database url")
+}
+
+#[actix_web::test]
+async fn shutdown_drains_in_flight_requests() {
+    let readiness = Readiness::new();
+    let shutdown = ShutdownCoordinator::new(readiness.clone(), Duration::from_secs(10));
+    let pool = unused_pool();
+
+    let app_shutdown = Data::new(shutdown.clone());
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(app_shutdown.clone())
+            .wrap(from_fn(track_in_flight))
+            .service(slow)
+    })
+    .workers(1)
+    .disable_signals()
+    .shutdown_timeout(10)
+    .bind(("127.0.0.1", 0))
+    .expect("bind test server");
+    let addr = server.addrs()[0];
+    let server = server.run();
+    let handle = server.handle();
+    let server_task = actix_web::rt::spawn(server);
+
+    let token = shutdown.token();
+    let background = shutdown.spawn("test_worker", async move {
+        token.cancelled().await;
+    });
+
+    let requests: Vec<_> = (0..IN_FLIGHT_REQUESTS)
+        .map(|_| actix_web::rt::spawn(get_slow(addr)))
+        .collect();
+
+    // Let every request reach the handler before shutting down
+    tokio::time::sleep(HANDLER_DELAY / 5).await;
+    assert_eq!(shutdown.in_flight(), IN_FLIGHT_REQUESTS);
+    shutdown.begin(handle).await;
+    assert!(readiness.is_shutting_down());
+
+    for request in requests {
+        let response = request
+            .await
+            .expect("request task")
+            .expect("request completed");
+        assert!(
+            response.starts_with("HTTP/1.1 200"),
+            "unexpected response: {}",
+            response
+        );
+        assert!(response.ends_with("done"));
+    }
+
+    // New connections are refused once the server has stopped
+    assert!(TcpStream::connect(addr).await.is_err());
+    server_task
+        .await
+        .expect("server task")
+        .expect("server stopped cleanly");
+
+    shutdown.finish(&pool).await;
+    assert!(background.is_finished());
+    assert!(pool.is_closed());
+}
+
+#[actix_web::test]
+async fn shutdown_gives_up_on_background_tasks_after_the_deadline() {
+    let shutdown = ShutdownCoordinator::new(Readiness::new(), Duration::from_millis(200));
+    let pool = unused_pool();
+
+    // Ignores cancellation entirely
+    shutdown.spawn("stuck_worker", std::future::pending());
+
+    let started = Instant::now();
+    shutdown.finish(&pool).await;
+
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(pool.is_closed());
+}
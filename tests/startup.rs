@@ -0,0 +1,122 @@
+//! Startup of the binary: failures end the process with an error and a
+//! failure status, never with a panic, and configuration problems go to the
+//! logs.
+
+use gsn_push_processing::adapters::{db, sqlite};
+use std::path::PathBuf;
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Startup fails well before this, a server running past it never will
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// SQLite database file of its own for the calling test
+fn database_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("startup-{}-{}.db", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn free_port() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("a free port");
+    listener.local_addr().unwrap().port().to_string()
+}
+
+async fn serve(database_url: &str, env: &[(&str, &str)]) -> Output {
+    let child = Command::new(env!("CARGO_BIN_EXE_gsn_push_processing"))
+        .arg("serve")
+        .env("DATABASE_URL", database_url)
+        .env("PORT", free_port())
+        .env("GRPC_PORT", free_port())
+        .envs(env.iter().copied())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .expect("start the server");
+
+    tokio::time::timeout(STARTUP_TIMEOUT, child.wait_with_output())
+        .await
+        .expect("startup to fail")
+        .expect("server output")
+}
+
+fn assert_failed_cleanly(output: &Output, reason: &str) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+    assert!(stderr.contains("Server failed"), "{}", stderr);
+    assert!(stderr.contains(reason), "{}", stderr);
+}
+
+#[tokio::test]
+async fn fails_on_a_schema_newer_than_the_build() {
+    let path = database_path("schema");
+    let url = format!("sqlite://{}?mode=rwc", path.display());
+    let pool = sqlite::init_pool(&db::DatabaseConfig {
+        url: url.clone(),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    sqlite::run_migrations(&pool).await.unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+        VALUES (99990101000000, 'from a newer build', TRUE, x'00', 0)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    pool.close().await;
+
+    for migrate in ["false", "true"] {
+        let output = serve(&url, &[("RUN_MIGRATIONS_ON_STARTUP", migrate)]).await;
+        assert_failed_cleanly(&output, "99990101000000");
+    }
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn fails_on_an_invalid_tracing_exporter() {
+    let path = database_path("tracing");
+    let url = format!("sqlite://{}?mode=rwc", path.display());
+
+    let output = serve(
+        &url,
+        &[("OTEL_EXPORTER", "otlp"), ("OTEL_ENDPOINT", "not a url")],
+    )
+    .await;
+    assert_failed_cleanly(&output, "Failed to initialize tracing exporter");
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn logs_ignored_config_values() {
+    let path = database_path("ignored");
+    let url = format!("sqlite://{}?mode=rwc", path.display());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gsn_push_processing"))
+        .args(["migrate", "status"])
+        .env("DATABASE_URL", &url)
+        .env("ENVIRONMENT", "dev")
+        .env("DB_MAX_CONNECTIONS", "lots")
+        .output()
+        .await
+        .expect("run migrate status");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    let warning = stderr
+        .lines()
+        .find(|line| line.contains("Ignoring invalid config value"))
+        .unwrap_or_else(|| panic!("no warning in {}", stderr));
+    assert!(warning.contains("WARN"), "{}", warning);
+    assert!(warning.contains("db_max_connections"), "{}", warning);
+
+    let _ = std::fs::remove_file(&path);
+}